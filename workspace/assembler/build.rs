@@ -0,0 +1,299 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+#![allow(non_upper_case_globals)]
+
+
+use ::std::collections::BTreeMap;
+use ::std::collections::HashMap;
+use ::std::collections::HashSet;
+use ::std::env::var_os;
+use ::std::fs::read_to_string;
+use ::std::fs::write;
+use ::std::path::PathBuf;
+
+
+/// Generates `Operands.rs` in `OUT_DIR` from the specialised methods in `src/InstructionStream.instructions.rs`.
+///
+/// Each mnemonic gets a trait for each number of operands (eg `AddOperands<Operand0, Operand1>` with method `add()`), implemented once for each distinct list of operand types.
+fn main()
+{
+	const Instructions: &str = "src/InstructionStream.instructions.rs";
+	
+	println!("cargo:rerun-if-changed={}", Instructions);
+	
+	let instructions = read_to_string(Instructions).expect("Could not read instructions");
+	let operands = generate_operands(&parse_specialised_methods(&instructions));
+	
+	let out_dir = PathBuf::from(var_os("OUT_DIR").expect("OUT_DIR was not set"));
+	write(out_dir.join("Operands.rs"), operands).expect("Could not write Operands.rs");
+}
+
+struct SpecialisedMethod<'a>
+{
+	name: &'a str,
+	mnemonic: &'a str,
+	operand_types: Vec<&'a str>,
+	return_type: Option<&'a str>,
+	
+	/// `false` for methods with implicit operands or prefixes spelt out in their name, such as `shl_Register8Bit_CL()`.
+	name_spells_out_operand_types: bool,
+}
+
+impl<'a> SpecialisedMethod<'a>
+{
+	/// Lower is better; prefers methods that do not return a result, then the `_r64` encoding with the register in the opcode (eg `push_Register64Bit_r64()` over `push_Register64Bit_rm64()`), then methods that are not the alternative `_1` encoding.
+	#[inline(always)]
+	fn preference(&self) -> (bool, bool, bool)
+	{
+		(self.return_type.is_some(), !self.name.ends_with("_r64"), self.name.ends_with("_1"))
+	}
+}
+
+fn parse_specialised_methods(instructions: &str) -> Vec<SpecialisedMethod<'_>>
+{
+	const Attribute: &str = "\t#[inline(always)]";
+	const Function: &str = "\tpub fn ";
+	const Receiver: &str = "(&mut self";
+	const Returns: &str = " -> ";
+	
+	let lines: Vec<&str> = instructions.lines().collect();
+	
+	let mut methods = Vec::new();
+	for pair in lines.windows(2)
+	{
+		if pair[0] != Attribute || !pair[1].starts_with(Function)
+		{
+			continue
+		}
+		let signature = &pair[1][Function.len() .. ];
+		
+		let (name, parameters_and_return_type) = match signature.find(Receiver)
+		{
+			None => continue,
+			Some(index) => (&signature[ .. index], &signature[index + Receiver.len() .. ]),
+		};
+		let (parameters, return_type) = match parameters_and_return_type.find(')')
+		{
+			None => continue,
+			Some(index) => (&parameters_and_return_type[ .. index], &parameters_and_return_type[index + 1 .. ]),
+		};
+		let return_type = if return_type.is_empty()
+		{
+			None
+		}
+		else if let Some(return_type) = return_type.strip_prefix(Returns)
+		{
+			Some(return_type)
+		}
+		else
+		{
+			continue
+		};
+		
+		let operand_types: Vec<&str> = parameters.split(", ").filter_map(|parameter|
+		{
+			let mut name_and_type = parameter.splitn(2, ": ");
+			match (name_and_type.next(), name_and_type.next())
+			{
+				(Some(name), Some(operand_type)) if name.starts_with("arg") => Some(operand_type),
+				_ => None,
+			}
+		}).collect();
+		
+		let mut tokens = name.split('_');
+		let mnemonic = tokens.next().unwrap();
+		let mut spelt_out_types: Vec<&str> = tokens.collect();
+		while let Some(&"1") | Some(&"r64") | Some(&"rm64") | Some(&"m64") = spelt_out_types.last()
+		{
+			spelt_out_types.pop();
+		}
+		let name_spells_out_operand_types = !operand_types.is_empty() && spelt_out_types.len() == operand_types.len() && spelt_out_types.iter().zip(operand_types.iter()).all(|(&spelt_out_type, &operand_type)| spells_out(spelt_out_type, operand_type));
+		
+		methods.push
+		(
+			SpecialisedMethod
+			{
+				name,
+				mnemonic,
+				operand_types,
+				return_type,
+				name_spells_out_operand_types,
+			}
+		)
+	}
+	methods
+}
+
+/// Method names abbreviate `YMMRegister` and `ZMMRegister`, and spell out merge-only masking (`OpmaskRegister`) as `Masking`.
+#[inline(always)]
+fn spells_out(spelt_out_type: &str, operand_type: &str) -> bool
+{
+	match (spelt_out_type, operand_type)
+	{
+		("YMM", "YMMRegister") | ("ZMM", "ZMMRegister") | ("Masking", "OpmaskRegister") => true,
+		_ => spelt_out_type == operand_type,
+	}
+}
+
+/// A trailing operand of one of these types makes a trait for a variant of another trait with one fewer operands, eg `vaddpd_masked()`.
+#[inline(always)]
+fn modifier(operand_type: &str) -> Option<&'static str>
+{
+	match operand_type
+	{
+		"Masking" | "OpmaskRegister" => Some("masked"),
+		"BranchHint" => Some("hinted"),
+		_ => None,
+	}
+}
+
+fn generate_operands(methods: &[SpecialisedMethod]) -> String
+{
+	const Header: &str = "// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.\n// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.\n\n";
+	
+	let method_names: HashSet<&str> = methods.iter().map(|method| method.name).collect();
+	
+	let mut mnemonics = Vec::new();
+	let mut methods_by_mnemonic: HashMap<&str, Vec<&SpecialisedMethod>> = HashMap::new();
+	for method in methods.iter().filter(|method| method.name_spells_out_operand_types)
+	{
+		methods_by_mnemonic.entry(method.mnemonic).or_insert_with(||
+		{
+			mnemonics.push(method.mnemonic);
+			Vec::new()
+		}).push(method)
+	}
+	
+	let mut operands = String::from(Header);
+	for mnemonic in mnemonics
+	{
+		let mut methods_by_arity: BTreeMap<usize, Vec<&SpecialisedMethod>> = BTreeMap::new();
+		for &method in methods_by_mnemonic[mnemonic].iter()
+		{
+			methods_by_arity.entry(method.operand_types.len()).or_default().push(method)
+		}
+		
+		let minimum_arity = *methods_by_arity.keys().next().unwrap();
+		let modifiers: BTreeMap<usize, Option<&str>> = methods_by_arity.iter().map(|(&arity, methods)|
+		{
+			let mut modifiers = methods.iter().map(|method| modifier(method.operand_types[arity - 1]));
+			let first_modifier = modifiers.next().unwrap();
+			let modifier = if methods_by_arity.len() > 1 && arity > minimum_arity && modifiers.all(|modifier| modifier == first_modifier)
+			{
+				first_modifier
+			}
+			else
+			{
+				None
+			};
+			(arity, modifier)
+		}).collect();
+		let smallest_unmodified_arity = *modifiers.iter().find(|&(_, modifier)| modifier.is_none()).unwrap().0;
+		
+		for (&arity, methods) in methods_by_arity.iter()
+		{
+			let trait_method_name = match modifiers[&arity]
+			{
+				Some(modifier) => format!("{}_{}", mnemonic, modifier),
+				None => if arity == smallest_unmodified_arity && !method_names.contains(mnemonic)
+				{
+					mnemonic.to_string()
+				}
+				else
+				{
+					format!("{}{}", mnemonic, arity)
+				},
+			};
+			
+			let mut chosen: Vec<&SpecialisedMethod> = Vec::new();
+			for &method in methods.iter()
+			{
+				match chosen.iter().position(|chosen| chosen.operand_types == method.operand_types)
+				{
+					None => chosen.push(method),
+					Some(index) => if method.preference() < chosen[index].preference()
+					{
+						chosen[index] = method
+					},
+				}
+			}
+			
+			generate_trait(&mut operands, mnemonic, &trait_method_name, arity, &chosen);
+		}
+	}
+	
+	let length = operands.trim_end_matches('\n').len();
+	operands.truncate(length);
+	operands.push('\n');
+	operands
+}
+
+fn generate_trait(operands: &mut String, mnemonic: &str, trait_method_name: &str, arity: usize, chosen: &[&SpecialisedMethod])
+{
+	let trait_name: String = trait_method_name.split('_').map(|word|
+	{
+		let mut characters = word.chars();
+		match characters.next()
+		{
+			None => String::new(),
+			Some(first) => first.to_uppercase().chain(characters).collect(),
+		}
+	}).collect::<String>() + "Operands";
+	
+	let rust_method_name = if trait_method_name == "loop"
+	{
+		format!("r#{}", trait_method_name)
+	}
+	else
+	{
+		trait_method_name.to_string()
+	};
+	
+	let description = if trait_method_name.ends_with("_masked")
+	{
+		", with a mask"
+	}
+	else if trait_method_name.ends_with("_hinted")
+	{
+		", with a branch hint"
+	}
+	else
+	{
+		""
+	};
+	
+	let mut return_types: Vec<&str> = chosen.iter().filter_map(|method| method.return_type).collect();
+	return_types.sort();
+	return_types.dedup();
+	assert!(return_types.len() <= 1, "Methods for `{}()` have different return types", trait_method_name);
+	let returns = match return_types.first()
+	{
+		None => String::new(),
+		Some(return_type) => format!(" -> {}", return_type),
+	};
+	
+	let generic_types: Vec<String> = (0 .. arity).map(|index| format!("Operand{}", index)).collect();
+	let generic_parameters: Vec<String> = (0 .. arity).map(|index| format!("operand{}: Operand{}", index, index)).collect();
+	let arguments: Vec<String> = (0 .. arity).map(|index| format!("operand{}", index)).collect();
+	
+	operands.push_str(&format!("/// Operands for `{}()`.\n", trait_method_name));
+	operands.push_str(&format!("pub trait {}<{}>\n{{\n", trait_name, generic_types.join(", ")));
+	operands.push_str(&format!("\t/// Emits `{}`{}, choosing the `InstructionStream` method for the types of the operands.\n", mnemonic.to_uppercase(), description));
+	operands.push_str(&format!("\tfn {}(&mut self, {}){};\n}}\n\n", rust_method_name, generic_parameters.join(", "), returns));
+	
+	for method in chosen
+	{
+		let parameters: Vec<String> = method.operand_types.iter().enumerate().map(|(index, operand_type)| format!("operand{}: {}", index, operand_type)).collect();
+		let body = if !returns.is_empty() && method.return_type.is_none()
+		{
+			format!("\t\tself.{}({});\n\t\tOk(())\n", method.name, arguments.join(", "))
+		}
+		else
+		{
+			format!("\t\tself.{}({})\n", method.name, arguments.join(", "))
+		};
+		operands.push_str(&format!("impl<'a> {}<{}> for InstructionStream<'a>\n{{\n\t#[inline(always)]\n\tfn {}(&mut self, {}){}\n\t{{\n{}\t}}\n}}\n\n", trait_name, method.operand_types.join(", "), rust_method_name, parameters.join(", "), returns, body));
+	}
+}
@@ -0,0 +1,65 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Errors from assembling into an `InstructionStream`.
+///
+/// Offsets are in bytes from the start of the instruction stream.
+#[derive(Debug)]
+pub enum AssemblerError
+{
+	/// A jump, call or emitted label referred to a `Label` which was never attached to a location.
+	UnresolvedLabel(Label),
+
+	/// The displacement to `Label` does not fit; the `usize` is the offset at which the displacement was inserted and the `u8` is the number of bits of the displacement (8 or 32).
+	DisplacementOverflow(Label, usize, u8),
+
+	/// Growing the memory map to fit `usize` more bytes failed.
+	OutOfMemory(io::Error, usize),
+
+	/// A `Label` was attached to more than one location.
+	LabelAlreadyAttached(Label),
+
+	/// An emitted label referred to a `Label` attached to a location without an `ENDBR64` landing pad whilst emitting landing pads for indirect branch tracking.
+	MissingLandingPad(Label),
+
+	/// Changing the protection of the memory map, to make it writable or executable, failed.
+	ProtectionFailed(io::Error),
+
+	/// A function pointer was created for offset `usize`, which does not start with an `ENDBR64` landing pad, whilst emitting landing pads for indirect branch tracking.
+	MissingFunctionPointerLandingPad(usize),
+}
+
+impl Display for AssemblerError
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		use self::AssemblerError::*;
+
+		match *self
+		{
+			UnresolvedLabel(label) => write!(f, "{:?} was never attached", label),
+			DisplacementOverflow(label, offset, bits) => write!(f, "displacement to {:?} at offset {} does not fit in {} bits", label, offset, bits),
+			OutOfMemory(ref error, bytes) => write!(f, "growing memory map for {} bytes failed with '{}'", bytes, error),
+			LabelAlreadyAttached(label) => write!(f, "{:?} has already been attached", label),
+			MissingLandingPad(label) => write!(f, "{:?} is an indirect branch target without an ENDBR64 landing pad", label),
+			ProtectionFailed(ref error) => write!(f, "changing the protection of the memory map failed with '{}'", error),
+			MissingFunctionPointerLandingPad(offset) => write!(f, "function pointer to offset {} does not start with an ENDBR64 landing pad", offset),
+		}
+	}
+}
+
+impl Error for AssemblerError
+{
+	#[inline(always)]
+	fn source(&self) -> Option<&(dyn Error + 'static)>
+	{
+		use self::AssemblerError::*;
+
+		match *self
+		{
+			OutOfMemory(ref error, _) | ProtectionFailed(ref error) => Some(error),
+			_ => None,
+		}
+	}
+}
@@ -0,0 +1,215 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Records near (32-bit displacement) `JMP` and `Jcc` instructions to labels so that they can be shrunk to short (8-bit displacement) forms when `InstructionStream.finish()` is called.
+///
+/// Shrinking only ever moves code closer together, so a displacement that fits in 8 bits stays fitting as more jumps are shrunk.
+#[derive(Debug, Default)]
+pub(crate) struct BranchRelaxation
+{
+	relaxable_jumps: Vec<RelaxableJump>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct RelaxableJump
+{
+	label: Label,
+	opcode_instruction_pointer: InstructionPointer,
+	insert_at_instruction_pointer: InstructionPointer,
+	short_opcode: u8,
+}
+
+impl RelaxableJump
+{
+	const ShortLength: usize = 2;
+	
+	#[inline(always)]
+	fn end_instruction_pointer(&self) -> InstructionPointer
+	{
+		self.insert_at_instruction_pointer + 4
+	}
+	
+	#[inline(always)]
+	fn saving(&self) -> usize
+	{
+		self.end_instruction_pointer() - self.opcode_instruction_pointer - Self::ShortLength
+	}
+}
+
+impl BranchRelaxation
+{
+	/// Records a near `JMP` or `Jcc` whose opcode starts at `opcode_instruction_pointer` and whose 32-bit displacement starts at `insert_at_instruction_pointer`; `short_opcode` is the opcode of its short form.
+	#[inline(always)]
+	pub(crate) fn record_near_jump(&mut self, label: Label, opcode_instruction_pointer: InstructionPointer, insert_at_instruction_pointer: InstructionPointer, short_opcode: u8)
+	{
+		self.relaxable_jumps.push
+		(
+			RelaxableJump
+			{
+				label,
+				opcode_instruction_pointer,
+				insert_at_instruction_pointer,
+				short_opcode,
+			}
+		)
+	}
+	
+	/// Nothing before `pinned_instruction_pointer` may move, so jumps recorded before it are removed and returned as `(label, insert_at_instruction_pointer)` to be made ordinary near jumps.
+	#[inline(always)]
+	pub(crate) fn pin(&mut self, pinned_instruction_pointer: InstructionPointer) -> impl Iterator<Item=(Label, InstructionPointer)> + '_
+	{
+		let number_of_pinned_jumps = self.relaxable_jumps.partition_point(|relaxable_jump| relaxable_jump.opcode_instruction_pointer < pinned_instruction_pointer);
+		self.relaxable_jumps.drain(.. number_of_pinned_jumps).map(|relaxable_jump| (relaxable_jump.label, relaxable_jump.insert_at_instruction_pointer))
+	}
+	
+	/// Shrinks as many jumps as possible, moves code to close up the gaps and relocates everything that refers to a location in the instruction stream.
+	///
+	/// Jumps to unresolved labels are left as near jumps.
+	///
+	/// Returns the relocation, for anything else that refers to a location in the instruction stream.
+	pub(crate) fn relax(self, byte_emitter: &mut ByteEmitter, labelled_locations: &mut LabelledLocations, instruction_pointers_to_replace_labels_with_8_bit_displacements: &mut Vec<(Label, InstructionPointer)>, instruction_pointers_to_replace_labels_with_32_bit_displacements: &mut Vec<(Label, InstructionPointer)>, emitted_labels: &mut [(Label, InstructionPointer)], function_calls: &mut [(usize, InstructionPointer)]) -> Relocation
+	{
+		let relaxable_jumps = self.relaxable_jumps;
+		let shrunk = Self::choose_jumps_to_shrink(&relaxable_jumps, labelled_locations);
+		let relocation = Relocation::new(&relaxable_jumps, &shrunk);
+		
+		labelled_locations.relocate(|instruction_pointer| relocation.relocate(instruction_pointer));
+		for &mut (_, ref mut insert_at_instruction_pointer) in instruction_pointers_to_replace_labels_with_8_bit_displacements.iter_mut().chain(instruction_pointers_to_replace_labels_with_32_bit_displacements.iter_mut()).chain(emitted_labels.iter_mut())
+		{
+			*insert_at_instruction_pointer = relocation.relocate(*insert_at_instruction_pointer)
+		}
+		
+		let mut read_from = byte_emitter.start_instruction_pointer;
+		let mut write_to = read_from;
+		for (relaxable_jump, &is_shrunk) in relaxable_jumps.iter().zip(shrunk.iter())
+		{
+			if is_shrunk
+			{
+				let length = relaxable_jump.opcode_instruction_pointer - read_from;
+				byte_emitter.move_bytes(read_from, write_to, length);
+				write_to += length;
+				
+				byte_emitter.emit_u8_at(relaxable_jump.short_opcode, write_to);
+				instruction_pointers_to_replace_labels_with_8_bit_displacements.push((relaxable_jump.label, write_to + 1));
+				write_to += RelaxableJump::ShortLength;
+				
+				read_from = relaxable_jump.end_instruction_pointer();
+			}
+			else
+			{
+				instruction_pointers_to_replace_labels_with_32_bit_displacements.push((relaxable_jump.label, relocation.relocate(relaxable_jump.insert_at_instruction_pointer)))
+			}
+		}
+		let length = byte_emitter.instruction_pointer - read_from;
+		byte_emitter.move_bytes(read_from, write_to, length);
+		byte_emitter.instruction_pointer = write_to + length;
+		
+		// The 32-bit relative displacement of a `CALL` to an absolute address needs recalculating if code before it moved.
+		for &mut (absolute_address, ref mut insert_at_instruction_pointer) in function_calls.iter_mut()
+		{
+			*insert_at_instruction_pointer = relocation.relocate(*insert_at_instruction_pointer);
+			let insert_at_instruction_pointer = *insert_at_instruction_pointer;
+			let displacement = (absolute_address as isize) - ((insert_at_instruction_pointer + 4) as isize);
+			byte_emitter.emit_u32_at(displacement as i32 as u32, insert_at_instruction_pointer)
+		}
+		
+		relocation
+	}
+	
+	/// Starts with all jumps near and repeatedly shrinks any that would now fit, until nothing changes.
+	#[inline(always)]
+	fn choose_jumps_to_shrink(relaxable_jumps: &[RelaxableJump], labelled_locations: &LabelledLocations) -> Vec<bool>
+	{
+		const ShortDisplacements: RangeInclusive<isize> = (i8::MIN as isize) ..= (i8::MAX as isize);
+		
+		let mut shrunk = vec![false; relaxable_jumps.len()];
+		
+		loop
+		{
+			let relocation = Relocation::new(relaxable_jumps, &shrunk);
+			let mut changed = false;
+			
+			for (relaxable_jump, is_shrunk) in relaxable_jumps.iter().zip(shrunk.iter_mut())
+			{
+				if *is_shrunk
+				{
+					continue
+				}
+				
+				let target_instruction_pointer = labelled_locations.potential_target_instruction_pointer(relaxable_jump.label);
+				if !target_instruction_pointer.is_valid()
+				{
+					continue
+				}
+				
+				let mut relocated_target_instruction_pointer = relocation.relocate(target_instruction_pointer);
+				if target_instruction_pointer >= relaxable_jump.end_instruction_pointer()
+				{
+					relocated_target_instruction_pointer -= relaxable_jump.saving();
+				}
+				let end_of_short_jmp_instruction = relocation.relocate(relaxable_jump.opcode_instruction_pointer) + RelaxableJump::ShortLength;
+				
+				let displacement = (relocated_target_instruction_pointer as isize) - (end_of_short_jmp_instruction as isize);
+				if ShortDisplacements.contains(&displacement)
+				{
+					*is_shrunk = true;
+					changed = true;
+				}
+			}
+			
+			if !changed
+			{
+				return shrunk
+			}
+		}
+	}
+}
+
+/// Maps an instruction pointer before relaxation to one after it.
+pub(crate) struct Relocation
+{
+	end_instruction_pointers: Vec<InstructionPointer>,
+	cumulative_savings: Vec<usize>,
+}
+
+impl Relocation
+{
+	#[inline(always)]
+	fn new(relaxable_jumps: &[RelaxableJump], shrunk: &[bool]) -> Self
+	{
+		let mut end_instruction_pointers = Vec::with_capacity(relaxable_jumps.len());
+		let mut cumulative_savings = Vec::with_capacity(relaxable_jumps.len());
+		let mut cumulative_saving = 0;
+		for (relaxable_jump, &is_shrunk) in relaxable_jumps.iter().zip(shrunk.iter())
+		{
+			if is_shrunk
+			{
+				cumulative_saving += relaxable_jump.saving();
+				end_instruction_pointers.push(relaxable_jump.end_instruction_pointer());
+				cumulative_savings.push(cumulative_saving);
+			}
+		}
+		
+		Self
+		{
+			end_instruction_pointers,
+			cumulative_savings,
+		}
+	}
+	
+	/// Only valid for instruction pointers that are not inside a shrunk jump.
+	#[inline(always)]
+	pub(crate) fn relocate(&self, instruction_pointer: InstructionPointer) -> InstructionPointer
+	{
+		let number_of_shrunk_jumps_before = self.end_instruction_pointers.partition_point(|&end_instruction_pointer| end_instruction_pointer <= instruction_pointer);
+		if number_of_shrunk_jumps_before == 0
+		{
+			instruction_pointer
+		}
+		else
+		{
+			instruction_pointer - self.cumulative_savings[number_of_shrunk_jumps_before - 1]
+		}
+	}
+}
@@ -9,6 +9,7 @@ pub(crate) struct ByteEmitter
 	instruction_pointer: InstructionPointer,
 	end_instruction_pointer: InstructionPointer,
 	bookmark: InstructionPointer,
+	writable_offset: usize,
 }
 
 impl ByteEmitter
@@ -16,24 +17,45 @@ impl ByteEmitter
 	#[inline(always)]
 	pub(crate) fn new(executable_anonymous_memory_map: & mut ExecutableAnonymousMemoryMap) -> Self
 	{
-		let instruction_pointer = executable_anonymous_memory_map.address as usize;
+		let address = executable_anonymous_memory_map.address as usize;
+		let instruction_pointer = address + executable_anonymous_memory_map.start_of_unfinished_code();
 		let length = executable_anonymous_memory_map.length;
 		
 		Self
 		{
 			start_instruction_pointer: instruction_pointer,
 			instruction_pointer,
-			end_instruction_pointer: instruction_pointer + length,
+			end_instruction_pointer: address + length,
 			bookmark: instruction_pointer,
+			writable_offset: (executable_anonymous_memory_map.writable_address as usize).wrapping_sub(address),
 		}
 	}
 	
+	/// Instruction pointers are always where code will execute; when dual mapped, bytes are written elsewhere.
+	#[inline(always)]
+	fn writable(&self, instruction_pointer: InstructionPointer) -> usize
+	{
+		instruction_pointer.wrapping_add(self.writable_offset)
+	}
+	
 	#[inline(always)]
 	pub(crate) fn remaining_space(&mut self) -> usize
 	{
 		self.end_instruction_pointer - self.instruction_pointer
 	}
 	
+	/// Throws away everything emitted so far and emits into `scratch` from now on; used once assembly has failed so that further emission stays in bounds.
+	#[inline(always)]
+	pub(crate) fn discard(&mut self, scratch: &mut [u8])
+	{
+		let instruction_pointer = scratch.as_mut_ptr() as usize;
+		self.start_instruction_pointer = instruction_pointer;
+		self.instruction_pointer = instruction_pointer;
+		self.end_instruction_pointer = instruction_pointer + scratch.len();
+		self.bookmark = instruction_pointer;
+		self.writable_offset = 0;
+	}
+	
 	#[inline(always)]
 	pub(crate) fn store_bookmark(&mut self)
 	{
@@ -76,7 +98,7 @@ impl ByteEmitter
 		debug_assert!(self.instruction_pointer + length <= self.end_instruction_pointer, "Not enough space to emit '{}' byte(s)", length);
 		unsafe
 		{
-			copy_nonoverlapping(bytes.as_ptr(), self.instruction_pointer as *mut u8, length);
+			copy_nonoverlapping(bytes.as_ptr(), self.writable(self.instruction_pointer) as *mut u8, length);
 			self.instruction_pointer += length;
 		}
 	}
@@ -113,6 +135,16 @@ impl ByteEmitter
 		self.emit_u8((W << 7) | ((!vvvv.index() << 3) & 0x78) | (l << 2) | pp);
 	}
 	
+	// See Figure 2-11, Intel Manual Volume 2A Section 2-37 (May 2018).
+	#[inline(always)]
+	pub(crate) fn emit_4_byte_evex_prefix(&mut self, r_bit: u8, x_bit: u8, b_bit: u8, r_prime_bit: u8, mm: u8, W: u8, vvvv: impl Register, pp: u8, z: u8, LL: u8, b: u8, aaa: u8)
+	{
+		self.emit_u8(0x62);
+		self.emit_u8(r_bit | x_bit | b_bit | r_prime_bit | mm);
+		self.emit_u8((W << 7) | ((!vvvv.index() << 3) & 0x78) | 0x04 | pp);
+		self.emit_u8(z | (LL << 5) | (b << 4) | ((!vvvv.index() >> 1) & 0x08) | aaa);
+	}
+	
 	#[inline(always)]
 	pub(crate) fn emit_u8_if_not_zero(&mut self, byte: u8)
 	{
@@ -162,22 +194,74 @@ impl ByteEmitter
 		Ok(())
 	}
 	
+	/// The displacement already at `insert_at_instruction_pointer` is added to the target (so `RIP+displacement` relative to a label works).
+	#[inline(always)]
+	pub(crate) fn insert_32_bit_relative_instruction_pointer_displacement(&mut self, insert_at_instruction_pointer: InstructionPointer, end_instruction_pointer: InstructionPointer, target_instruction_pointer: InstructionPointer) -> NearJmpResult
+	{
+		let addend = self.read_u32_at(insert_at_instruction_pointer) as i32 as isize;
+		
+		let displacement = (target_instruction_pointer as isize) + addend - (end_instruction_pointer as isize);
+		
+		const Minimum: isize = i32::MIN as isize;
+		const Maximum: isize = i32::MAX as isize;
+		
+		if unlikely!(!(Minimum ..= Maximum).contains(&displacement))
+		{
+			return Err(())
+		}
+		
+		self.emit_u32_at(displacement as u32, insert_at_instruction_pointer);
+		
+		Ok(())
+	}
+	
+	/// Emits the offset of `target_instruction_pointer` relative to `base_instruction_pointer` (eg the start of a jump table).
+	#[inline(always)]
+	pub(crate) fn insert_32_bit_relative_offset(&mut self, insert_at_instruction_pointer: InstructionPointer, base_instruction_pointer: InstructionPointer, target_instruction_pointer: InstructionPointer) -> NearJmpResult
+	{
+		let offset = (target_instruction_pointer as isize) - (base_instruction_pointer as isize);
+		
+		const Minimum: isize = i32::MIN as isize;
+		const Maximum: isize = i32::MAX as isize;
+		
+		if unlikely!(!(Minimum ..= Maximum).contains(&offset))
+		{
+			return Err(())
+		}
+		
+		self.emit_u32_at(offset as u32, insert_at_instruction_pointer);
+		
+		Ok(())
+	}
+	
+	#[inline(always)]
+	pub(crate) fn read_u32_at(&self, at: InstructionPointer) -> u32
+	{
+		unsafe { (self.writable(at) as *const u32).read_unaligned() }
+	}
+	
+	#[inline(always)]
+	pub(crate) fn move_bytes(&mut self, from: InstructionPointer, to: InstructionPointer, length: usize)
+	{
+		unsafe { copy(self.writable(from) as *const u8, self.writable(to) as *mut u8, length) }
+	}
+	
 	#[inline(always)]
 	pub(crate) fn emit_u8_at(&mut self, emit: u8, at: InstructionPointer)
 	{
-		unsafe { *(at as *mut u8) = emit };
+		unsafe { *(self.writable(at) as *mut u8) = emit };
 	}
 	
 	#[inline(always)]
 	pub(crate) fn emit_u32_at(&mut self, emit: u32, at: InstructionPointer)
 	{
-		unsafe { *(at as *mut u32) = emit };
+		unsafe { (self.writable(at) as *mut u32).write_unaligned(emit) };
 	}
 	
 	#[inline(always)]
 	pub(crate) fn emit_u64_at(&mut self, emit: u64, at: InstructionPointer)
 	{
-		unsafe { *(at as *mut u64) = emit };
+		unsafe { (self.writable(at) as *mut u64).write_unaligned(emit) };
 	}
 	
 	#[inline(always)]
@@ -185,7 +269,7 @@ impl ByteEmitter
 	{
 		const Size: usize = 1;
 		debug_assert!(self.instruction_pointer + Size <= self.end_instruction_pointer, "Not enough space to emit an u8");
-		unsafe { *(self.instruction_pointer as *mut u8) = emit };
+		unsafe { *(self.writable(self.instruction_pointer) as *mut u8) = emit };
 		self.instruction_pointer += Size;
 	}
 	
@@ -194,7 +278,7 @@ impl ByteEmitter
 	{
 		const Size: usize = 2;
 		debug_assert!(self.instruction_pointer + Size <= self.end_instruction_pointer, "Not enough space to emit an u16");
-		unsafe { *(self.instruction_pointer as *mut u16) = emit.to_le() };
+		unsafe { (self.writable(self.instruction_pointer) as *mut u16).write_unaligned(emit.to_le()) };
 		self.instruction_pointer += Size;
 	}
 	
@@ -203,7 +287,7 @@ impl ByteEmitter
 	{
 		const Size: usize = 4;
 		debug_assert!(self.instruction_pointer + Size <= self.end_instruction_pointer, "Not enough space to emit an u32");
-		unsafe { *(self.instruction_pointer as *mut u32) = emit.to_le() };
+		unsafe { (self.writable(self.instruction_pointer) as *mut u32).write_unaligned(emit.to_le()) };
 		self.instruction_pointer += Size;
 	}
 	
@@ -212,7 +296,7 @@ impl ByteEmitter
 	{
 		const Size: usize = 8;
 		debug_assert!(self.instruction_pointer + Size <= self.end_instruction_pointer, "Not enough space to emit an u64");
-		unsafe { *(self.instruction_pointer as *mut u64) = emit.to_le() };
+		unsafe { (self.writable(self.instruction_pointer) as *mut u64).write_unaligned(emit.to_le()) };
 		self.instruction_pointer += Size;
 	}
 	
@@ -221,7 +305,7 @@ impl ByteEmitter
 	{
 		const Size: usize = 16;
 		debug_assert!(self.instruction_pointer + Size <= self.end_instruction_pointer, "Not enough space to emit an u128");
-		unsafe { *(self.instruction_pointer as *mut u128) = emit.to_le() };
+		unsafe { (self.writable(self.instruction_pointer) as *mut u128).write_unaligned(emit.to_le()) };
 		self.instruction_pointer += Size;
 	}
 	
@@ -0,0 +1,307 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Call frame information (CFI) recorded whilst emitting, from which an `.eh_frame` section is built.
+///
+/// Directives are only recorded inside a procedure, between `InstructionStream.cfi_start_procedure()` and `InstructionStream.cfi_end_procedure()`.
+#[derive(Debug)]
+pub(crate) struct CallFrameInformation
+{
+	procedures: Vec<CallFrameProcedure>,
+	is_open: bool,
+	state: CallFrameState,
+	remembered_states: Vec<CallFrameState>,
+}
+
+impl Default for CallFrameInformation
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		Self
+		{
+			procedures: Vec::new(),
+			is_open: false,
+			state: CallFrameState::OnEntry,
+			remembered_states: Vec::new(),
+		}
+	}
+}
+
+impl CallFrameInformation
+{
+	const DW_CFA_advance_loc: u8 = 0x40;
+	
+	const DW_CFA_advance_loc1: u8 = 0x02;
+	
+	const DW_CFA_advance_loc2: u8 = 0x03;
+	
+	const DW_CFA_advance_loc4: u8 = 0x04;
+	
+	const DW_CFA_nop: u8 = 0x00;
+	
+	const DW_EH_PE_absptr: u8 = 0x00;
+	
+	/// Starts a procedure (function) at `instruction_pointer`, ending any procedure still open.
+	#[inline(always)]
+	pub(crate) fn start_procedure(&mut self, instruction_pointer: InstructionPointer)
+	{
+		self.end_procedure(instruction_pointer);
+		
+		self.procedures.push
+		(
+			CallFrameProcedure
+			{
+				start_instruction_pointer: instruction_pointer,
+				end_instruction_pointer: instruction_pointer,
+				instructions: Vec::new(),
+			}
+		);
+		self.is_open = true;
+		self.state = CallFrameState::OnEntry;
+		self.remembered_states.clear();
+	}
+	
+	/// Ends any open procedure at `instruction_pointer`.
+	#[inline(always)]
+	pub(crate) fn end_procedure(&mut self, instruction_pointer: InstructionPointer)
+	{
+		if self.is_open
+		{
+			self.is_open = false;
+			self.procedures.last_mut().unwrap().end_instruction_pointer = instruction_pointer;
+		}
+	}
+	
+	/// Records `instruction` as taking effect at `instruction_pointer`, if a procedure is open.
+	#[inline(always)]
+	pub(crate) fn record(&mut self, instruction_pointer: InstructionPointer, instruction: CallFrameInstruction)
+	{
+		use self::CallFrameInstruction::*;
+		use self::Register64Bit::RSP;
+		
+		if !self.is_open
+		{
+			return
+		}
+		
+		match instruction
+		{
+			DefineCanonicalFrameAddress(register, offset) =>
+			{
+				self.state.canonical_frame_address_register = register;
+				self.state.canonical_frame_address_offset = offset;
+			}
+			
+			DefineCanonicalFrameAddressRegister(register) => self.state.canonical_frame_address_register = register,
+			
+			DefineCanonicalFrameAddressOffset(offset) => self.state.canonical_frame_address_offset = offset,
+			
+			RememberState => self.remembered_states.push(self.state),
+			
+			RestoreState => if let Some(state) = self.remembered_states.pop()
+			{
+				self.state = state
+			},
+			
+			Offset(_, _) | Restore(_) => (),
+		}
+		
+		if self.state.canonical_frame_address_register == RSP
+		{
+			self.state.stack_depth = self.state.canonical_frame_address_offset
+		}
+		
+		self.procedures.last_mut().unwrap().instructions.push((instruction_pointer, instruction))
+	}
+	
+	/// Records the effect of `PUSH register` ending at `instruction_pointer`.
+	#[inline(always)]
+	pub(crate) fn push(&mut self, instruction_pointer: InstructionPointer, register: Register64Bit)
+	{
+		use self::CallFrameInstruction::*;
+		
+		self.adjust_stack(instruction_pointer, 8);
+		let offset = -(self.state.stack_depth as i32);
+		self.record(instruction_pointer, Offset(register, offset))
+	}
+	
+	/// Records the effect of `POP register` ending at `instruction_pointer`.
+	#[inline(always)]
+	pub(crate) fn pop(&mut self, instruction_pointer: InstructionPointer, register: Register64Bit)
+	{
+		use self::CallFrameInstruction::*;
+		
+		self.adjust_stack(instruction_pointer, -8);
+		self.record(instruction_pointer, Restore(register))
+	}
+	
+	/// Records the effect of moving `RSP` down by `bytes` (or up, if negative) with an instruction ending at `instruction_pointer`.
+	#[inline(always)]
+	pub(crate) fn adjust_stack(&mut self, instruction_pointer: InstructionPointer, bytes: i64)
+	{
+		use self::CallFrameInstruction::*;
+		use self::Register64Bit::RSP;
+		
+		if !self.is_open
+		{
+			return
+		}
+		
+		self.state.stack_depth = (self.state.stack_depth as i64 + bytes) as u32;
+		if self.state.canonical_frame_address_register == RSP
+		{
+			let stack_depth = self.state.stack_depth;
+			self.record(instruction_pointer, DefineCanonicalFrameAddressOffset(stack_depth))
+		}
+	}
+	
+	/// Moves all procedures and directives using `relocate`.
+	#[inline(always)]
+	pub(crate) fn relocate(&mut self, relocate: impl Fn(InstructionPointer) -> InstructionPointer)
+	{
+		for procedure in self.procedures.iter_mut()
+		{
+			procedure.start_instruction_pointer = relocate(procedure.start_instruction_pointer);
+			procedure.end_instruction_pointer = relocate(procedure.end_instruction_pointer);
+			for &mut (ref mut instruction_pointer, _) in procedure.instructions.iter_mut()
+			{
+				*instruction_pointer = relocate(*instruction_pointer)
+			}
+		}
+	}
+	
+	/// Builds an `.eh_frame` section, with one Common Information Entry (CIE) followed by a Frame Description Entry (FDE) for each procedure, and ending with a zero terminator.
+	///
+	/// Addresses in the FDEs are absolute; `None` if there are no procedures.
+	pub(crate) fn eh_frame(&self) -> Option<Vec<u8>>
+	{
+		if self.procedures.is_empty()
+		{
+			return None
+		}
+		
+		let mut eh_frame = Vec::new();
+		
+		let common_information_entry_offset = Self::start_entry(&mut eh_frame);
+		eh_frame.extend_from_slice(&0u32.to_le_bytes());
+		eh_frame.push(1);
+		eh_frame.extend_from_slice(b"zR\0");
+		// Code alignment factor.
+		push_unsigned_leb128(&mut eh_frame, 1);
+		// Data alignment factor, as a signed LEB128 (which fits in one byte).
+		eh_frame.push((CallFrameInstruction::DataAlignmentFactor as i8 as u8) & 0x7F);
+		eh_frame.push(CallFrameInstruction::ReturnAddressRegister);
+		// Augmentation data (for 'R'): the encoding of addresses in FDEs.
+		push_unsigned_leb128(&mut eh_frame, 1);
+		eh_frame.push(Self::DW_EH_PE_absptr);
+		// On entry, the CFA is `RSP + 8` and the return address is at `CFA - 8`.
+		CallFrameInstruction::DefineCanonicalFrameAddress(Register64Bit::RSP, 8).encode(&mut eh_frame);
+		eh_frame.push(CallFrameInstruction::DW_CFA_offset | CallFrameInstruction::ReturnAddressRegister);
+		push_unsigned_leb128(&mut eh_frame, 1);
+		Self::end_entry(&mut eh_frame, common_information_entry_offset);
+		
+		for procedure in self.procedures.iter()
+		{
+			let frame_description_entry_offset = Self::start_entry(&mut eh_frame);
+			let common_information_entry_pointer = (eh_frame.len() - common_information_entry_offset) as u32;
+			eh_frame.extend_from_slice(&common_information_entry_pointer.to_le_bytes());
+			eh_frame.extend_from_slice(&(procedure.start_instruction_pointer as u64).to_le_bytes());
+			eh_frame.extend_from_slice(&((procedure.end_instruction_pointer - procedure.start_instruction_pointer) as u64).to_le_bytes());
+			// No augmentation data.
+			push_unsigned_leb128(&mut eh_frame, 0);
+			
+			let mut location = procedure.start_instruction_pointer;
+			for &(instruction_pointer, instruction) in procedure.instructions.iter()
+			{
+				Self::advance_location(&mut eh_frame, instruction_pointer - location);
+				location = instruction_pointer;
+				instruction.encode(&mut eh_frame);
+			}
+			Self::end_entry(&mut eh_frame, frame_description_entry_offset);
+		}
+		
+		eh_frame.extend_from_slice(&0u32.to_le_bytes());
+		Some(eh_frame)
+	}
+	
+	/// Reserves space for the length of an entry, returning the offset of the entry.
+	#[inline(always)]
+	fn start_entry(eh_frame: &mut Vec<u8>) -> usize
+	{
+		let offset = eh_frame.len();
+		eh_frame.extend_from_slice(&0u32.to_le_bytes());
+		offset
+	}
+	
+	/// Pads the entry at `offset` to a multiple of 8 bytes and fills in its length, which does not include the length itself.
+	#[inline(always)]
+	fn end_entry(eh_frame: &mut Vec<u8>, offset: usize)
+	{
+		while !(eh_frame.len() - offset).is_multiple_of(8)
+		{
+			eh_frame.push(Self::DW_CFA_nop)
+		}
+		let length = (eh_frame.len() - offset - 4) as u32;
+		eh_frame[offset .. offset + 4].copy_from_slice(&length.to_le_bytes());
+	}
+	
+	#[inline(always)]
+	fn advance_location(eh_frame: &mut Vec<u8>, delta: usize)
+	{
+		match delta
+		{
+			0 => (),
+			
+			0x01 ..= 0x3F => eh_frame.push(Self::DW_CFA_advance_loc | (delta as u8)),
+			
+			0x40 ..= 0xFF =>
+			{
+				eh_frame.push(Self::DW_CFA_advance_loc1);
+				eh_frame.push(delta as u8)
+			}
+			
+			0x100 ..= 0xFFFF =>
+			{
+				eh_frame.push(Self::DW_CFA_advance_loc2);
+				eh_frame.extend_from_slice(&(delta as u16).to_le_bytes())
+			}
+			
+			_ =>
+			{
+				eh_frame.push(Self::DW_CFA_advance_loc4);
+				eh_frame.extend_from_slice(&(delta as u32).to_le_bytes())
+			}
+		}
+	}
+}
+
+#[derive(Debug)]
+struct CallFrameProcedure
+{
+	start_instruction_pointer: InstructionPointer,
+	end_instruction_pointer: InstructionPointer,
+	instructions: Vec<(InstructionPointer, CallFrameInstruction)>,
+}
+
+/// What the directives recorded so far say about the stack.
+#[derive(Debug, Copy, Clone)]
+struct CallFrameState
+{
+	canonical_frame_address_register: Register64Bit,
+	canonical_frame_address_offset: u32,
+	
+	/// Bytes from the Canonical Frame Address down to `RSP`.
+	stack_depth: u32,
+}
+
+impl CallFrameState
+{
+	const OnEntry: Self = Self
+	{
+		canonical_frame_address_register: Register64Bit::RSP,
+		canonical_frame_address_offset: 8,
+		stack_depth: 8,
+	};
+}
@@ -0,0 +1,134 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A DWARF call frame information (CFI) directive, describing, from the current location onwards, how an unwinder finds the caller's frame and the registers saved by the current function.
+///
+/// The caller's frame is located using the Canonical Frame Address (CFA), the value of `RSP` just before the `CALL` to the current function; on entry to a function, the CFA is `RSP + 8`.
+///
+/// Each variant is equivalent to the GNU assembler directive named in its documentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CallFrameInstruction
+{
+	/// `.cfi_def_cfa register, offset`: the CFA is now `register + offset`.
+	DefineCanonicalFrameAddress(Register64Bit, u32),
+
+	/// `.cfi_def_cfa_register register`: the CFA is now `register` plus the current offset.
+	DefineCanonicalFrameAddressRegister(Register64Bit),
+
+	/// `.cfi_def_cfa_offset offset`: the CFA is now the current register plus `offset`.
+	DefineCanonicalFrameAddressOffset(u32),
+
+	/// `.cfi_offset register, offset`: the caller's value of `register` is saved at `CFA + offset`; `offset` is a negative multiple of 8.
+	Offset(Register64Bit, i32),
+
+	/// `.cfi_restore register`: `register` is no longer saved, and holds the caller's value.
+	Restore(Register64Bit),
+
+	/// `.cfi_remember_state`: pushes all rules onto a stack; typically used before an epilogue in the middle of a function.
+	RememberState,
+
+	/// `.cfi_restore_state`: pops all rules from the stack pushed by `RememberState`.
+	RestoreState,
+}
+
+impl CallFrameInstruction
+{
+	const DW_CFA_def_cfa: u8 = 0x0C;
+
+	const DW_CFA_def_cfa_register: u8 = 0x0D;
+
+	const DW_CFA_def_cfa_offset: u8 = 0x0E;
+
+	pub(crate) const DW_CFA_offset: u8 = 0x80;
+
+	const DW_CFA_restore: u8 = 0xC0;
+
+	const DW_CFA_remember_state: u8 = 0x0A;
+
+	const DW_CFA_restore_state: u8 = 0x0B;
+
+	/// The DWARF data alignment factor that `Offset` is divided by.
+	pub(crate) const DataAlignmentFactor: i32 = -8;
+
+	/// DWARF register number of the return address.
+	pub(crate) const ReturnAddressRegister: u8 = 16;
+
+	/// Appends the DWARF encoding.
+	pub(crate) fn encode(self, bytes: &mut Vec<u8>)
+	{
+		use self::CallFrameInstruction::*;
+
+		match self
+		{
+			DefineCanonicalFrameAddress(register, offset) =>
+			{
+				bytes.push(Self::DW_CFA_def_cfa);
+				push_unsigned_leb128(bytes, Self::dwarf_register(register) as u64);
+				push_unsigned_leb128(bytes, offset as u64);
+			}
+
+			DefineCanonicalFrameAddressRegister(register) =>
+			{
+				bytes.push(Self::DW_CFA_def_cfa_register);
+				push_unsigned_leb128(bytes, Self::dwarf_register(register) as u64);
+			}
+
+			DefineCanonicalFrameAddressOffset(offset) =>
+			{
+				bytes.push(Self::DW_CFA_def_cfa_offset);
+				push_unsigned_leb128(bytes, offset as u64);
+			}
+
+			Offset(register, offset) =>
+			{
+				debug_assert!(offset < 0 && offset % 8 == 0, "offset '{}' is not a negative multiple of 8", offset);
+				bytes.push(Self::DW_CFA_offset | Self::dwarf_register(register));
+				push_unsigned_leb128(bytes, (offset / Self::DataAlignmentFactor) as u64);
+			}
+
+			Restore(register) => bytes.push(Self::DW_CFA_restore | Self::dwarf_register(register)),
+
+			RememberState => bytes.push(Self::DW_CFA_remember_state),
+
+			RestoreState => bytes.push(Self::DW_CFA_restore_state),
+		}
+	}
+
+	/// See Figure 3.36, System V Application Binary Interface AMD64 Architecture Processor Supplement.
+	#[inline(always)]
+	pub(crate) fn dwarf_register(register: Register64Bit) -> u8
+	{
+		use self::Register64Bit::*;
+
+		match register
+		{
+			RAX => 0,
+			RDX => 1,
+			RCX => 2,
+			RBX => 3,
+			RSI => 4,
+			RDI => 5,
+			RBP => 6,
+			RSP => 7,
+			_ => register.index(),
+		}
+	}
+}
+
+/// Appends `value` as an unsigned LEB128.
+#[inline(always)]
+pub(crate) fn push_unsigned_leb128(bytes: &mut Vec<u8>, mut value: u64)
+{
+	loop
+	{
+		let byte = (value & 0x7F) as u8;
+		value >>= 7;
+		if value == 0
+		{
+			bytes.push(byte);
+			return
+		}
+		bytes.push(byte | 0x80);
+	}
+}
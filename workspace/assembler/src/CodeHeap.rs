@@ -0,0 +1,78 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A heap of executable code, handing out aligned allocations from one dual mapped `ExecutableAnonymousMemoryMap`.
+///
+/// Each allocation can be written to using its own instruction streams; when a `CodeHeapAllocation` is dropped its memory is given back to the heap and merged with any free neighbours.
+///
+/// Allocations keep the heap's memory alive, so the heap itself can be dropped first.
+#[derive(Debug, Clone)]
+pub struct CodeHeap(Rc<CodeHeapInner>);
+
+#[derive(Debug)]
+struct CodeHeapInner
+{
+	executable_anonymous_memory_map: ExecutableAnonymousMemoryMap,
+	free_blocks: RefCell<CodeHeapFreeBlocks>,
+}
+
+impl CodeHeap
+{
+	/// Create a new instance.
+	///
+	/// Arguments are as for `ExecutableAnonymousMemoryMap::new_dual_mapped()`; the heap does not grow.
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[inline(always)]
+	pub fn new(length: usize, allocate_in_first_2Gb: bool, ignore_mlock_failure: bool) -> Result<Self, ExecutableAnonymousMemoryMapCreationError>
+	{
+		let executable_anonymous_memory_map = ExecutableAnonymousMemoryMap::new_dual_mapped(length, allocate_in_first_2Gb, ignore_mlock_failure)?;
+		let free_blocks = CodeHeapFreeBlocks::new(executable_anonymous_memory_map.length);
+		
+		Ok
+		(
+			CodeHeap
+			(
+				Rc::new
+				(
+					CodeHeapInner
+					{
+						executable_anonymous_memory_map,
+						free_blocks: RefCell::new(free_blocks),
+					}
+				)
+			)
+		)
+	}
+	
+	/// Allocates at least `length` bytes whose (executable) address is a multiple of `alignment`, which must be a power of two.
+	///
+	/// Lengths are rounded up to a multiple of 16 bytes.
+	///
+	/// Returns `None` if there is no free block large enough.
+	#[inline(always)]
+	pub fn allocate(&self, length: usize, alignment: usize) -> Option<CodeHeapAllocation>
+	{
+		assert!(alignment.is_power_of_two(), "alignment '{}' is not a power of two", alignment);
+		
+		let executable_anonymous_memory_map = &self.0.executable_anonymous_memory_map;
+		let (offset, length) = self.0.free_blocks.borrow_mut().allocate(executable_anonymous_memory_map.address as usize, length, alignment)?;
+		
+		Some
+		(
+			CodeHeapAllocation
+			{
+				executable_anonymous_memory_map: executable_anonymous_memory_map.sub_region(offset, length),
+				offset,
+				code_heap: self.0.clone(),
+			}
+		)
+	}
+	
+	/// Statistics about the use and fragmentation of this heap.
+	#[inline(always)]
+	pub fn statistics(&self) -> CodeHeapStatistics
+	{
+		self.0.free_blocks.borrow().statistics()
+	}
+}
@@ -0,0 +1,59 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// An allocation from a `CodeHeap`.
+///
+/// Function pointers obtained from this allocation's instruction streams must not be called once it has been dropped.
+#[derive(Debug)]
+pub struct CodeHeapAllocation
+{
+	executable_anonymous_memory_map: ExecutableAnonymousMemoryMap,
+	offset: usize,
+	code_heap: Rc<CodeHeapInner>,
+}
+
+impl Drop for CodeHeapAllocation
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		self.code_heap.free_blocks.borrow_mut().free(self.offset, self.executable_anonymous_memory_map.length)
+	}
+}
+
+impl CodeHeapAllocation
+{
+	/// Get an assembler to this allocation.
+	///
+	/// As for `ExecutableAnonymousMemoryMap`, each instruction stream appends after the code of previously finished ones; an instruction stream that runs out of space fails with `AssemblerError::OutOfMemory`.
+	#[inline(always)]
+	pub fn instruction_stream(&mut self, instruction_stream_hints: &InstructionStreamHints) -> InstructionStream<'_>
+	{
+		self.executable_anonymous_memory_map.instruction_stream(instruction_stream_hints)
+	}
+	
+	/// Opts in to the GDB JIT compilation interface for this allocation; see `ExecutableAnonymousMemoryMap.enable_gdb_jit_interface()`.
+	///
+	/// Code is unregistered when this allocation is dropped.
+	#[cfg(feature = "gdb-jit-interface")]
+	#[inline(always)]
+	pub fn enable_gdb_jit_interface(&mut self)
+	{
+		self.executable_anonymous_memory_map.enable_gdb_jit_interface()
+	}
+	
+	/// The (executable) address of the start of this allocation.
+	#[inline(always)]
+	pub fn address(&self) -> InstructionPointer
+	{
+		self.executable_anonymous_memory_map.address as usize
+	}
+	
+	/// The length of this allocation, which may be more than was asked for.
+	#[inline(always)]
+	pub fn length(&self) -> usize
+	{
+		self.executable_anonymous_memory_map.length
+	}
+}
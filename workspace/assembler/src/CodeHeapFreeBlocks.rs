@@ -0,0 +1,109 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// First-fit free list of a `CodeHeap`, keyed by offset; adjacent free blocks are always merged.
+#[derive(Debug)]
+pub(crate) struct CodeHeapFreeBlocks
+{
+	free_blocks: BTreeMap<usize, usize>,
+	total_bytes: usize,
+	allocated_bytes: usize,
+	number_of_allocations: usize,
+}
+
+impl CodeHeapFreeBlocks
+{
+	const Granularity: usize = 16;
+	
+	#[inline(always)]
+	pub(crate) fn new(total_bytes: usize) -> Self
+	{
+		let mut free_blocks = BTreeMap::new();
+		free_blocks.insert(0, total_bytes);
+		
+		Self
+		{
+			free_blocks,
+			total_bytes,
+			allocated_bytes: 0,
+			number_of_allocations: 0,
+		}
+	}
+	
+	/// Returns `(offset, length)`.
+	///
+	/// `alignment` applies to `base_address + offset`.
+	#[inline(always)]
+	pub(crate) fn allocate(&mut self, base_address: usize, length: usize, alignment: usize) -> Option<(usize, usize)>
+	{
+		let length = Self::round_up(length.max(1), Self::Granularity);
+		
+		let (block_offset, block_length, offset) = self.free_blocks.iter().map(|(&block_offset, &block_length)|
+		{
+			let offset = Self::round_up(base_address + block_offset, alignment) - base_address;
+			(block_offset, block_length, offset)
+		}).find(|&(block_offset, block_length, offset)| offset + length <= block_offset + block_length)?;
+		
+		self.free_blocks.remove(&block_offset);
+		if offset > block_offset
+		{
+			self.free_blocks.insert(block_offset, offset - block_offset);
+		}
+		let end = offset + length;
+		let block_end = block_offset + block_length;
+		if block_end > end
+		{
+			self.free_blocks.insert(end, block_end - end);
+		}
+		
+		self.allocated_bytes += length;
+		self.number_of_allocations += 1;
+		Some((offset, length))
+	}
+	
+	#[inline(always)]
+	pub(crate) fn free(&mut self, mut offset: usize, length: usize)
+	{
+		self.allocated_bytes -= length;
+		self.number_of_allocations -= 1;
+		
+		let mut end = offset + length;
+		
+		if let Some((&previous_offset, &previous_length)) = self.free_blocks.range(.. offset).next_back()
+		{
+			if previous_offset + previous_length == offset
+			{
+				self.free_blocks.remove(&previous_offset);
+				offset = previous_offset;
+			}
+		}
+		
+		if let Some(next_length) = self.free_blocks.remove(&end)
+		{
+			end += next_length;
+		}
+		
+		self.free_blocks.insert(offset, end - offset);
+	}
+	
+	#[inline(always)]
+	pub(crate) fn statistics(&self) -> CodeHeapStatistics
+	{
+		CodeHeapStatistics
+		{
+			total_bytes: self.total_bytes,
+			allocated_bytes: self.allocated_bytes,
+			free_bytes: self.total_bytes - self.allocated_bytes,
+			number_of_allocations: self.number_of_allocations,
+			number_of_free_blocks: self.free_blocks.len(),
+			largest_free_block: self.free_blocks.values().cloned().max().unwrap_or(0),
+		}
+	}
+	
+	#[inline(always)]
+	fn round_up(value: usize, power_of_two: usize) -> usize
+	{
+		(value + power_of_two - 1) & !(power_of_two - 1)
+	}
+}
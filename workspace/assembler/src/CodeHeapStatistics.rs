@@ -0,0 +1,45 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Statistics about a `CodeHeap`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CodeHeapStatistics
+{
+	/// Size of the heap in bytes.
+	pub total_bytes: usize,
+	
+	/// Bytes in use by allocations.
+	pub allocated_bytes: usize,
+	
+	/// Bytes not in use by allocations, including those between allocations lost to alignment.
+	pub free_bytes: usize,
+	
+	/// Number of allocations in use.
+	pub number_of_allocations: usize,
+	
+	/// Number of (non-adjacent) free blocks.
+	pub number_of_free_blocks: usize,
+	
+	/// Size of the largest free block in bytes.
+	///
+	/// An allocation of up to this length with an alignment of at most 16 bytes will succeed; with a larger alignment, padding at the start of the block may leave too little of it.
+	pub largest_free_block: usize,
+}
+
+impl CodeHeapStatistics
+{
+	/// From `0.0` (all free bytes are in one block) to nearly `1.0` (free bytes are scattered in many small blocks).
+	#[inline(always)]
+	pub fn fragmentation(&self) -> f64
+	{
+		if self.free_bytes == 0
+		{
+			0.0
+		}
+		else
+		{
+			1.0 - (self.largest_free_block as f64) / (self.free_bytes as f64)
+		}
+	}
+}
@@ -0,0 +1,43 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Constants referenced by `RIP+displacement` memory operands, deduplicated by value and alignment.
+///
+/// Constants are emitted, aligned, when `InstructionStream.flush_constant_pool()` or `InstructionStream.finish()` is called; a constant that has already been emitted is reused rather than emitted again.
+#[derive(Debug, Default)]
+pub(crate) struct ConstantPool
+{
+	labels: BTreeMap<(Vec<u8>, usize), Label>,
+	unflushed: Vec<(Label, Vec<u8>, usize)>,
+}
+
+impl ConstantPool
+{
+	/// Returns the label of an existing constant with the same value and alignment, or calls `create_label` for a new one.
+	#[inline(always)]
+	pub(crate) fn constant(&mut self, bytes: &[u8], alignment: usize, create_label: impl FnOnce() -> Label) -> Label
+	{
+		debug_assert!(alignment.is_power_of_two(), "alignment `{}` is not a power of two", alignment);
+		
+		let key = (bytes.to_vec(), alignment);
+		if let Some(&label) = self.labels.get(&key)
+		{
+			return label
+		}
+		
+		let label = create_label();
+		self.unflushed.push((label, bytes.to_vec(), alignment));
+		self.labels.insert(key, label);
+		label
+	}
+	
+	/// Constants not yet emitted, most aligned first (to minimise padding), as `(label, bytes, alignment)`.
+	#[inline(always)]
+	pub(crate) fn take_unflushed(&mut self) -> Vec<(Label, Vec<u8>, usize)>
+	{
+		let mut unflushed = ::std::mem::take(&mut self.unflushed);
+		unflushed.sort_by_key(|&(_, _, alignment)| Reverse(alignment));
+		unflushed
+	}
+}
@@ -0,0 +1,105 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+extern "C"
+{
+	/// Provided by the unwinder; `libgcc_s` takes a whole `.eh_frame` section ending with a zero terminator, but LLVM's `libunwind` takes a single Frame Description Entry (FDE).
+	fn __register_frame(begin: *const u8);
+
+	fn __deregister_frame(begin: *const u8);
+}
+
+/// An `.eh_frame` section for finished code, registered with the unwinder so that panics, backtraces and profilers can unwind through generated functions.
+///
+/// On Apple platforms, where the unwinder is LLVM's `libunwind`, each FDE is registered separately; elsewhere, the unwinder is assumed to be `libgcc_s` and the whole section is registered at once.
+///
+/// Deregistered when dropped.
+#[derive(Debug)]
+pub(crate) struct EhFrameRegistration
+{
+	/// Held as `u64`s so that entries are aligned.
+	eh_frame: Box<[u64]>,
+	
+	/// Offsets of what was passed to `__register_frame()`.
+	registered_offsets: Vec<usize>,
+}
+
+impl Drop for EhFrameRegistration
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		for &offset in self.registered_offsets.iter()
+		{
+			unsafe { __deregister_frame(self.begin(offset)) }
+		}
+	}
+}
+
+impl EhFrameRegistration
+{
+	/// Registers `eh_frame`, which must contain at least one Frame Description Entry (FDE).
+	pub(crate) fn register(eh_frame: &[u8]) -> Self
+	{
+		let mut aligned = vec![0u64; eh_frame.len().div_ceil(8)].into_boxed_slice();
+		unsafe { copy_nonoverlapping(eh_frame.as_ptr(), aligned.as_mut_ptr() as *mut u8, eh_frame.len()) };
+		
+		let this = Self
+		{
+			eh_frame: aligned,
+			registered_offsets: Self::offsets_to_register(eh_frame),
+		};
+		
+		for &offset in this.registered_offsets.iter()
+		{
+			unsafe { __register_frame(this.begin(offset)) }
+		}
+		
+		this
+	}
+	
+	#[inline(always)]
+	fn begin(&self, offset: usize) -> *const u8
+	{
+		unsafe { (self.eh_frame.as_ptr() as *const u8).add(offset) }
+	}
+	
+	#[cfg(not(target_vendor = "apple"))]
+	#[inline(always)]
+	fn offsets_to_register(_eh_frame: &[u8]) -> Vec<usize>
+	{
+		vec![0]
+	}
+	
+	/// Entries start with a 32-bit length (of the rest of the entry) and a 32-bit CIE pointer, which is zero for a Common Information Entry (CIE); a zero length terminates the section.
+	#[cfg(target_vendor = "apple")]
+	#[inline(always)]
+	fn offsets_to_register(eh_frame: &[u8]) -> Vec<usize>
+	{
+		let u32_at = |offset: usize|
+		{
+			let mut value = [0; 4];
+			value.copy_from_slice(&eh_frame[offset .. offset + 4]);
+			u32::from_le_bytes(value) as usize
+		};
+		
+		let mut offsets = Vec::new();
+		let mut offset = 0;
+		while offset + 8 <= eh_frame.len()
+		{
+			let length = u32_at(offset);
+			if length == 0
+			{
+				break
+			}
+			
+			if u32_at(offset + 4) != 0
+			{
+				offsets.push(offset)
+			}
+			offset += 4 + length;
+		}
+		offsets
+	}
+}
@@ -0,0 +1,109 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Builds an ELF64 file for x86-64 with sections but no program headers, such as a relocatable object.
+#[derive(Debug)]
+pub(crate) struct ElfFile
+{
+	bytes: Vec<u8>,
+	section_headers: Vec<ElfSectionHeader>,
+	section_header_string_table: ElfStringTable,
+}
+
+impl ElfFile
+{
+	pub(crate) const ET_REL: u16 = 1;
+
+	const HeaderSize: usize = 64;
+
+	/// Creates a new instance with just the null section.
+	#[inline(always)]
+	pub(crate) fn new() -> Self
+	{
+		Self
+		{
+			bytes: vec![0; Self::HeaderSize],
+			section_headers: vec![ElfSectionHeader::default()],
+			section_header_string_table: ElfStringTable::default(),
+		}
+	}
+
+	/// Adds a section holding `contents`, returning the index of the section.
+	///
+	/// The name, offset and size (unless the section is `SHT_NOBITS`) of `section_header` are filled in.
+	#[inline(always)]
+	pub(crate) fn add_section(&mut self, name: &str, mut section_header: ElfSectionHeader, contents: &[u8]) -> u16
+	{
+		section_header.name = self.section_header_string_table.add(name);
+		section_header.offset = self.append_aligned(contents, section_header.alignment as usize);
+		if section_header.section_type != ElfSectionHeader::SHT_NOBITS
+		{
+			section_header.size = contents.len();
+		}
+		self.section_headers.push(section_header);
+		(self.section_headers.len() - 1) as u16
+	}
+
+	/// Adds the section header string table and section headers, then writes the ELF header.
+	pub(crate) fn finish(mut self, elf_type: u16) -> Vec<u8>
+	{
+		let name = self.section_header_string_table.add(".shstrtab");
+		let section_header_string_table = ::std::mem::take(&mut self.section_header_string_table);
+		let offset = self.append_aligned(&section_header_string_table.bytes, 1);
+		self.section_headers.push(ElfSectionHeader { name, section_type: ElfSectionHeader::SHT_STRTAB, offset, size: section_header_string_table.bytes.len(), alignment: 1, ..ElfSectionHeader::default() });
+		let section_header_string_table_index = (self.section_headers.len() - 1) as u16;
+
+		let section_headers_offset = self.append_aligned(&[], 8);
+		for section_header in self.section_headers.iter()
+		{
+			section_header.write(&mut self.bytes)
+		}
+
+		let number_of_sections = self.section_headers.len() as u16;
+		self.write_header(elf_type, section_headers_offset, number_of_sections, section_header_string_table_index);
+		self.bytes
+	}
+
+	#[inline(always)]
+	fn write_header(&mut self, elf_type: u16, section_headers_offset: u64, number_of_sections: u16, section_header_string_table_index: u16)
+	{
+		const ELFCLASS64: u8 = 2;
+		const ELFDATA2LSB: u8 = 1;
+		const EV_CURRENT: u8 = 1;
+		const ELFOSABI_NONE: u8 = 0;
+		const EM_X86_64: u16 = 62;
+
+		let mut header = Vec::with_capacity(Self::HeaderSize);
+		header.extend_from_slice(&[0x7F, b'E', b'L', b'F', ELFCLASS64, ELFDATA2LSB, EV_CURRENT, ELFOSABI_NONE, 0, 0, 0, 0, 0, 0, 0, 0]);
+		header.extend_from_slice(&elf_type.to_le_bytes());
+		header.extend_from_slice(&EM_X86_64.to_le_bytes());
+		header.extend_from_slice(&(EV_CURRENT as u32).to_le_bytes());
+		// Entry point.
+		header.extend_from_slice(&0u64.to_le_bytes());
+		// Program headers offset.
+		header.extend_from_slice(&0u64.to_le_bytes());
+		header.extend_from_slice(&section_headers_offset.to_le_bytes());
+		// Flags.
+		header.extend_from_slice(&0u32.to_le_bytes());
+		header.extend_from_slice(&(Self::HeaderSize as u16).to_le_bytes());
+		// Program header size and number of program headers.
+		header.extend_from_slice(&0u16.to_le_bytes());
+		header.extend_from_slice(&0u16.to_le_bytes());
+		header.extend_from_slice(&(ElfSectionHeader::Size as u16).to_le_bytes());
+		header.extend_from_slice(&number_of_sections.to_le_bytes());
+		header.extend_from_slice(&section_header_string_table_index.to_le_bytes());
+		self.bytes[.. Self::HeaderSize].copy_from_slice(&header)
+	}
+
+	/// Pads to `alignment` then appends `contents`, returning the offset of `contents`.
+	#[inline(always)]
+	fn append_aligned(&mut self, contents: &[u8], alignment: usize) -> u64
+	{
+		let alignment = alignment.max(1);
+		let offset = (self.bytes.len() + alignment - 1) & !(alignment - 1);
+		self.bytes.resize(offset, 0);
+		self.bytes.extend_from_slice(contents);
+		offset as u64
+	}
+}
@@ -0,0 +1,195 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Writes the code of a `FinishedCode` as an ELF64 relocatable object (a `.o` file) for x86-64, so that code can be assembled ahead of time and linked into a C or Rust binary with `ld`.
+///
+/// The object contains a `.text` section holding the encoded instructions and symbols for the labels named with `global_function()` and `local_function()`.
+///
+/// Locations the linker has to fix up are written as relocations in `.rela.text`:-
+///
+/// * Calls made with `call_function()` become `R_X86_64_PLT32` (or `R_X86_64_PC32`) relocations against the external function named for the absolute address called with `external_function()`;
+/// * Labels emitted with `emit_label()` become `R_X86_64_64` relocations against `.text`; when linking a position independent executable, these make the linker warn about text relocations.
+///
+/// Nothing else is relocated; code that embeds an absolute address (eg from `statically_relative_address()` or `jmp_Any64BitMemory_statically_relative_address()`) or uses a `RelativeAddress32Bit` to a location outside of the instruction stream will not work once linked.
+#[derive(Debug)]
+pub struct ElfRelocatableObject<'a, 'b>
+{
+	finished_code: &'b FinishedCode<'a>,
+	symbols: Vec<(String, Label, bool)>,
+	external_functions: BTreeMap<usize, String>,
+	calls_through_procedure_linkage_table: bool,
+}
+
+impl<'a, 'b> ElfRelocatableObject<'a, 'b>
+{
+	const TextSectionIndex: u16 = 1;
+
+	const SymbolTableSectionIndex: u32 = 3;
+
+	const StringTableSectionIndex: u32 = 4;
+
+	const R_X86_64_64: u32 = 1;
+
+	const R_X86_64_PC32: u32 = 2;
+
+	const R_X86_64_PLT32: u32 = 4;
+
+	const RelocationSize: usize = 24;
+
+	/// Creates a new instance for the code in `finished_code`.
+	#[inline(always)]
+	pub fn new(finished_code: &'b FinishedCode<'a>) -> Self
+	{
+		Self
+		{
+			finished_code,
+			symbols: Vec::new(),
+			external_functions: BTreeMap::new(),
+			calls_through_procedure_linkage_table: true,
+		}
+	}
+
+	/// Exports the location of `label` as a global function symbol called `name`.
+	#[inline(always)]
+	pub fn global_function(&mut self, name: impl Into<String>, label: Label) -> &mut Self
+	{
+		self.symbols.push((name.into(), label, true));
+		self
+	}
+
+	/// Names the location of `label` with a local function symbol called `name`; useful for debuggers and profilers.
+	#[inline(always)]
+	pub fn local_function(&mut self, name: impl Into<String>, label: Label) -> &mut Self
+	{
+		self.symbols.push((name.into(), label, false));
+		self
+	}
+
+	/// Calls made with `call_function()` to `absolute_address` are relocated to the external function `name`.
+	///
+	/// Every absolute address used with `call_function()` must be named.
+	#[inline(always)]
+	pub fn external_function(&mut self, name: impl Into<String>, absolute_address: impl FunctionPointer) -> &mut Self
+	{
+		self.external_functions.insert(absolute_address.absolute_virtual_address(), name.into());
+		self
+	}
+
+	/// By default, calls to external functions use `R_X86_64_PLT32` relocations, as modern compilers do; if `false`, `R_X86_64_PC32` relocations are used instead.
+	#[inline(always)]
+	pub fn calls_through_procedure_linkage_table(&mut self, calls_through_procedure_linkage_table: bool) -> &mut Self
+	{
+		self.calls_through_procedure_linkage_table = calls_through_procedure_linkage_table;
+		self
+	}
+
+	/// Writes the relocatable object to `writer`, eg a `File`.
+	#[inline(always)]
+	pub fn write(&self, writer: &mut impl Write) -> Result<(), ElfRelocatableObjectError>
+	{
+		let bytes = self.to_bytes()?;
+		writer.write_all(&bytes)?;
+		Ok(())
+	}
+
+	/// Creates the bytes of the relocatable object.
+	pub fn to_bytes(&self) -> Result<Vec<u8>, ElfRelocatableObjectError>
+	{
+		let mut text = self.finished_code.encoded_bytes().to_vec();
+		let mut string_table = ElfStringTable::default();
+		let mut symbol_table = Vec::new();
+		let mut symbol_names = HashSet::new();
+		let mut add_name = |name: &str| -> Result<u32, ElfRelocatableObjectError>
+		{
+			if name.is_empty() || name.contains('\0')
+			{
+				return Err(ElfRelocatableObjectError::InvalidSymbolName(name.to_string()))
+			}
+			if !symbol_names.insert(name.to_string())
+			{
+				return Err(ElfRelocatableObjectError::DuplicateSymbolName(name.to_string()))
+			}
+			Ok(string_table.add(name))
+		};
+
+		ElfSymbol::default().write(&mut symbol_table);
+		ElfSymbol { binding: ElfSymbol::STB_LOCAL, symbol_type: ElfSymbol::STT_SECTION, section_index: Self::TextSectionIndex, ..ElfSymbol::default() }.write(&mut symbol_table);
+		let text_section_symbol_index = 1;
+		let mut number_of_symbols: u32 = 2;
+
+		// Local symbols must precede global ones.
+		let mut first_global_symbol_index = number_of_symbols;
+		for is_global in [false, true].iter().cloned()
+		{
+			if is_global
+			{
+				first_global_symbol_index = number_of_symbols
+			}
+			for &(ref name, label, _) in self.symbols.iter().filter(|&&(_, _, global)| global == is_global)
+			{
+				let offset = match self.finished_code.label_offset(label)
+				{
+					None => return Err(ElfRelocatableObjectError::UnattachedLabel(name.clone(), label)),
+					Some(offset) => offset,
+				};
+				let binding = if is_global { ElfSymbol::STB_GLOBAL } else { ElfSymbol::STB_LOCAL };
+				ElfSymbol { name: add_name(name)?, binding, symbol_type: ElfSymbol::STT_FUNC, section_index: Self::TextSectionIndex, value: offset as u64, ..ElfSymbol::default() }.write(&mut symbol_table);
+				number_of_symbols += 1;
+			}
+		}
+
+		let mut external_function_symbol_indices = BTreeMap::new();
+		for (&absolute_address, name) in self.external_functions.iter()
+		{
+			ElfSymbol { name: add_name(name)?, binding: ElfSymbol::STB_GLOBAL, symbol_type: ElfSymbol::STT_NOTYPE, section_index: ElfSymbol::SHN_UNDEF, ..ElfSymbol::default() }.write(&mut symbol_table);
+			external_function_symbol_indices.insert(absolute_address, number_of_symbols);
+			number_of_symbols += 1;
+		}
+
+		let call_relocation_type = if self.calls_through_procedure_linkage_table
+		{
+			Self::R_X86_64_PLT32
+		}
+		else
+		{
+			Self::R_X86_64_PC32
+		};
+
+		let mut relocations = Vec::new();
+		for (absolute_address, offset) in self.finished_code.function_call_offsets()
+		{
+			let symbol_index = match external_function_symbol_indices.get(&absolute_address)
+			{
+				None => return Err(ElfRelocatableObjectError::UnnamedFunction(absolute_address, offset)),
+				Some(&symbol_index) => symbol_index,
+			};
+			text[offset .. offset + 4].copy_from_slice(&[0; 4]);
+			relocations.push((offset as u64, symbol_index, call_relocation_type, -4i64));
+		}
+		for (label, offset) in self.finished_code.emitted_label_offsets()
+		{
+			let target_offset = self.finished_code.label_offset(label).expect("try_finish() checks that emitted labels are attached");
+			text[offset .. offset + 8].copy_from_slice(&[0; 8]);
+			relocations.push((offset as u64, text_section_symbol_index, Self::R_X86_64_64, target_offset as i64));
+		}
+		relocations.sort_unstable_by_key(|&(offset, _, _, _)| offset);
+
+		let mut rela_text = Vec::with_capacity(relocations.len() * Self::RelocationSize);
+		for (offset, symbol_index, relocation_type, addend) in relocations
+		{
+			rela_text.extend_from_slice(&offset.to_le_bytes());
+			rela_text.extend_from_slice(&(((symbol_index as u64) << 32) | (relocation_type as u64)).to_le_bytes());
+			rela_text.extend_from_slice(&addend.to_le_bytes());
+		}
+
+		let mut elf_file = ElfFile::new();
+		elf_file.add_section(".text", ElfSectionHeader { section_type: ElfSectionHeader::SHT_PROGBITS, flags: ElfSectionHeader::SHF_ALLOC | ElfSectionHeader::SHF_EXECINSTR, alignment: 16, ..ElfSectionHeader::default() }, &text);
+		elf_file.add_section(".rela.text", ElfSectionHeader { section_type: ElfSectionHeader::SHT_RELA, flags: ElfSectionHeader::SHF_INFO_LINK, link: Self::SymbolTableSectionIndex, info: Self::TextSectionIndex as u32, alignment: 8, entry_size: Self::RelocationSize, ..ElfSectionHeader::default() }, &rela_text);
+		elf_file.add_section(".symtab", ElfSectionHeader { section_type: ElfSectionHeader::SHT_SYMTAB, link: Self::StringTableSectionIndex, info: first_global_symbol_index, alignment: 8, entry_size: ElfSymbol::Size, ..ElfSectionHeader::default() }, &symbol_table);
+		elf_file.add_section(".strtab", ElfSectionHeader { section_type: ElfSectionHeader::SHT_STRTAB, alignment: 1, ..ElfSectionHeader::default() }, &string_table.bytes);
+		// An empty `.note.GNU-stack` section tells the linker that this code does not need an executable stack.
+		elf_file.add_section(".note.GNU-stack", ElfSectionHeader { section_type: ElfSectionHeader::SHT_PROGBITS, alignment: 1, ..ElfSectionHeader::default() }, &[]);
+		Ok(elf_file.finish(ElfFile::ET_REL))
+	}
+}
@@ -0,0 +1,66 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Errors from writing an `ElfRelocatableObject`.
+///
+/// Offsets are in bytes from the start of the instruction stream.
+#[derive(Debug)]
+pub enum ElfRelocatableObjectError
+{
+	/// `call_function()` was used with the absolute address `usize` at offset `usize`, but no external function was named for that address.
+	UnnamedFunction(usize, usize),
+
+	/// The symbol `String` was given a `Label` which was never attached to a location.
+	UnattachedLabel(String, Label),
+
+	/// The symbol name `String` was empty or contained an ASCII NUL.
+	InvalidSymbolName(String),
+
+	/// The symbol name `String` was used more than once.
+	DuplicateSymbolName(String),
+
+	/// Writing out failed.
+	Io(io::Error),
+}
+
+impl Display for ElfRelocatableObjectError
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		use self::ElfRelocatableObjectError::*;
+
+		match *self
+		{
+			UnnamedFunction(absolute_address, offset) => write!(f, "call at offset {} is to function at 0x{:x}, which has not been named", offset, absolute_address),
+			UnattachedLabel(ref name, label) => write!(f, "symbol '{}' is for {:?}, which was never attached", name, label),
+			InvalidSymbolName(ref name) => write!(f, "symbol name '{}' is empty or contains a NUL", name.escape_default()),
+			DuplicateSymbolName(ref name) => write!(f, "symbol name '{}' is used more than once", name),
+			Io(ref error) => write!(f, "writing failed with '{}'", error),
+		}
+	}
+}
+
+impl Error for ElfRelocatableObjectError
+{
+	#[inline(always)]
+	fn source(&self) -> Option<&(dyn Error + 'static)>
+	{
+		use self::ElfRelocatableObjectError::*;
+
+		match *self
+		{
+			Io(ref error) => Some(error),
+			_ => None,
+		}
+	}
+}
+
+impl From<io::Error> for ElfRelocatableObjectError
+{
+	#[inline(always)]
+	fn from(error: io::Error) -> Self
+	{
+		ElfRelocatableObjectError::Io(error)
+	}
+}
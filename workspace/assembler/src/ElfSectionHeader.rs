@@ -0,0 +1,55 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// An ELF64 section header.
+#[derive(Debug, Default)]
+pub(crate) struct ElfSectionHeader
+{
+	pub(crate) name: u32,
+	pub(crate) section_type: u32,
+	pub(crate) flags: u64,
+	pub(crate) address: u64,
+	pub(crate) offset: u64,
+	pub(crate) size: usize,
+	pub(crate) link: u32,
+	pub(crate) info: u32,
+	pub(crate) alignment: u64,
+	pub(crate) entry_size: usize,
+}
+
+impl ElfSectionHeader
+{
+	pub(crate) const SHT_PROGBITS: u32 = 1;
+
+	pub(crate) const SHT_SYMTAB: u32 = 2;
+
+	pub(crate) const SHT_STRTAB: u32 = 3;
+
+	pub(crate) const SHT_RELA: u32 = 4;
+
+	pub(crate) const SHT_NOBITS: u32 = 8;
+
+	pub(crate) const SHF_ALLOC: u64 = 0x02;
+
+	pub(crate) const SHF_EXECINSTR: u64 = 0x04;
+
+	pub(crate) const SHF_INFO_LINK: u64 = 0x40;
+
+	pub(crate) const Size: usize = 64;
+
+	#[inline(always)]
+	pub(crate) fn write(&self, bytes: &mut Vec<u8>)
+	{
+		bytes.extend_from_slice(&self.name.to_le_bytes());
+		bytes.extend_from_slice(&self.section_type.to_le_bytes());
+		bytes.extend_from_slice(&self.flags.to_le_bytes());
+		bytes.extend_from_slice(&self.address.to_le_bytes());
+		bytes.extend_from_slice(&self.offset.to_le_bytes());
+		bytes.extend_from_slice(&(self.size as u64).to_le_bytes());
+		bytes.extend_from_slice(&self.link.to_le_bytes());
+		bytes.extend_from_slice(&self.info.to_le_bytes());
+		bytes.extend_from_slice(&self.alignment.to_le_bytes());
+		bytes.extend_from_slice(&(self.entry_size as u64).to_le_bytes());
+	}
+}
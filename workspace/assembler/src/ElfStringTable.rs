@@ -0,0 +1,35 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// An ELF string table (eg `.strtab` or `.shstrtab`); the first string is always empty.
+#[derive(Debug)]
+pub(crate) struct ElfStringTable
+{
+	pub(crate) bytes: Vec<u8>,
+}
+
+impl Default for ElfStringTable
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		Self
+		{
+			bytes: vec![0],
+		}
+	}
+}
+
+impl ElfStringTable
+{
+	/// Returns the index of `name`, which should not contain an ASCII NUL.
+	#[inline(always)]
+	pub(crate) fn add(&mut self, name: &str) -> u32
+	{
+		let index = self.bytes.len() as u32;
+		self.bytes.extend_from_slice(name.as_bytes());
+		self.bytes.push(0);
+		index
+	}
+}
@@ -0,0 +1,45 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// An ELF64 symbol table entry.
+#[derive(Debug, Default)]
+pub(crate) struct ElfSymbol
+{
+	pub(crate) name: u32,
+	pub(crate) binding: u8,
+	pub(crate) symbol_type: u8,
+	pub(crate) section_index: u16,
+	pub(crate) value: u64,
+	pub(crate) size: u64,
+}
+
+impl ElfSymbol
+{
+	pub(crate) const SHN_UNDEF: u16 = 0;
+
+	pub(crate) const STB_LOCAL: u8 = 0;
+
+	pub(crate) const STB_GLOBAL: u8 = 1;
+
+	pub(crate) const STT_NOTYPE: u8 = 0;
+
+	pub(crate) const STT_FUNC: u8 = 2;
+
+	pub(crate) const STT_SECTION: u8 = 3;
+
+	pub(crate) const Size: usize = 24;
+
+	#[inline(always)]
+	pub(crate) fn write(&self, bytes: &mut Vec<u8>)
+	{
+		const STV_DEFAULT: u8 = 0;
+
+		bytes.extend_from_slice(&self.name.to_le_bytes());
+		bytes.push((self.binding << 4) | self.symbol_type);
+		bytes.push(STV_DEFAULT);
+		bytes.extend_from_slice(&self.section_index.to_le_bytes());
+		bytes.extend_from_slice(&self.value.to_le_bytes());
+		bytes.extend_from_slice(&self.size.to_le_bytes());
+	}
+}
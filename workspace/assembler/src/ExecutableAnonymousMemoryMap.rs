@@ -3,12 +3,25 @@
 
 
 /// Represents an executable memory map that can be used to generate program code into.
+///
+/// If created with `new_dual_mapped()`, the memory is mapped twice, once writable and once executable, and is never both at the same address.
+///
+/// Each instruction stream obtained with `instruction_stream()` appends after the code of previously finished instruction streams, which remains executable; unless dual mapped, the page shared by the end of finished code and the start of the instruction stream is both writable and executable until it is finished.
+///
+/// If the kernel refuses to make that page both writable and executable (eg SELinux's `execmem`), nothing is written to the map and `InstructionStream.try_finish()` returns `AssemblerError::ProtectionFailed`; use `new_dual_mapped()` instead.
 #[derive(Debug)]
 pub struct ExecutableAnonymousMemoryMap
 {
 	address: *mut u8,
+	writable_address: *mut u8,
 	length: usize,
+	high_water_mark: usize,
 	ignore_mlock_failure: bool,
+	dual_mapping_file_descriptor: Option<c_int>,
+	owns_memory: bool,
+	#[cfg(feature = "gdb-jit-interface")]
+	gdb_jit_registrations: Option<Vec<GdbJitRegistration>>,
+	eh_frame_registrations: Vec<EhFrameRegistration>,
 }
 
 impl Drop for ExecutableAnonymousMemoryMap
@@ -16,7 +29,26 @@ impl Drop for ExecutableAnonymousMemoryMap
 	#[inline(always)]
 	fn drop(&mut self)
 	{
+		// Debuggers and unwinders must stop using code before it is unmapped.
+		#[cfg(feature = "gdb-jit-interface")]
+		self.gdb_jit_registrations.take();
+		self.eh_frame_registrations.clear();
+		
+		if !self.owns_memory
+		{
+			return
+		}
+		
 		unsafe { munmap(self.address as *mut _, self.length) };
+		
+		if let Some(file_descriptor) = self.dual_mapping_file_descriptor
+		{
+			unsafe
+			{
+				munmap(self.writable_address as *mut _, self.length);
+				close(file_descriptor);
+			}
+		}
 	}
 }
 
@@ -31,137 +63,422 @@ impl ExecutableAnonymousMemoryMap
 	/// On Linux, there are historic bugs in `mlock` which may require `ignore_mlock_failure` to be `true`.
 	///
 	/// Memory is created using an anonymous, shared mmap with no access rights (not even read) which is then locked (`mlock`'d).
-	#[cfg_attr(not(any(target_os = "android", target_os = "linux")), allow(unused_variables))]
+	///
+	/// Whilst an `InstructionStream` is writing, the memory is writable but not executable; code in it can not be run until `finish()` is called.
+	/// Unless dual mapped, the exception is the page shared with previously finished code, which is both writable and executable until then.
 	#[inline(always)]
 	pub fn new(length: usize, allocate_in_first_2Gb: bool, ignore_mlock_failure: bool) -> Result<Self, ExecutableAnonymousMemoryMapCreationError>
 	{
 		use self::ExecutableAnonymousMemoryMapCreationError::*;
 
-		const PageSize: usize = 4096;
 		const NoFileDescriptor: i32 = -1;
 		const NoOffset: i64 = 0;
 		
-		let aligned_length = if length < PageSize
-		{
-			PageSize
-		}
-		else
-		{
-			length.next_power_of_two()
-		};
-		
-		
-		let flags =
-		{
-			#[cfg(any(target_os = "android", target_os = "linux"))]
-			{
-				let mut flags = MAP_ANON | MAP_SHARED;
-				if allocate_in_first_2Gb
-				{
-					flags |= MAP_32BIT
-				}
-				flags
-			}
-			
-			#[cfg(not(any(target_os = "android", target_os = "linux")))]
-			{
-				MAP_ANON | MAP_SHARED
-			}
-		};
+		let aligned_length = Self::aligned_length(length);
 		
-		let result = unsafe { mmap(null_mut(), aligned_length, PROT_NONE, flags, NoFileDescriptor, NoOffset) };
+		let result = unsafe { mmap(null_mut(), aligned_length, PROT_NONE, Self::flags(MAP_ANON | MAP_SHARED, allocate_in_first_2Gb), NoFileDescriptor, NoOffset) };
 		if unlikely!(result == MAP_FAILED)
 		{
 			Err(MMapFailed(io::Error::last_os_error(), aligned_length))
 		}
 		else
 		{
-			let address = result;
-			let result = unsafe { mlock(address, length) };
-			if unlikely!(!ignore_mlock_failure && result != 0)
+			let address = result as *mut u8;
+			if let Err(error) = Self::mlock(address, length, ignore_mlock_failure, "new")
 			{
-				if likely!(result == -1)
-				{
-					return Err(MLockFailed(io::Error::last_os_error(), aligned_length))
-				}
-				else
-				{
-					panic!("Unexpected result code from mlock (new) '{}'", result)
-				}
+				unsafe { munmap(result, aligned_length) };
+				return Err(MLockFailed(error, aligned_length))
 			}
 
 			Ok
 			(
 				Self
 				{
-					address: address as *mut _,
+					address,
+					writable_address: address,
 					length,
+					high_water_mark: 0,
 					ignore_mlock_failure,
+					dual_mapping_file_descriptor: None,
+					owns_memory: true,
+					#[cfg(feature = "gdb-jit-interface")]
+					gdb_jit_registrations: None,
+					eh_frame_registrations: Vec::new(),
 				}
 			)
 		}
 	}
 	
+	/// Create a new instance which maps the same memory twice, once always writable (but not executable) and once always executable (but not writable), using a `memfd`.
+	///
+	/// Code already in the map can be run whilst an `InstructionStream` writes more, and kernels that refuse memory which has been both writable and executable (eg SELinux's `execmem`) are satisfied.
+	///
+	/// `length` is rounded up to the nearest power of two, and is floored at the smallest page size (4Kb).
+	///
+	/// `allocate_in_first_2Gb` and `ignore_mlock_failure` are as for `new()`; `allocate_in_first_2Gb` applies to the executable mapping.
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[inline(always)]
+	pub fn new_dual_mapped(length: usize, allocate_in_first_2Gb: bool, ignore_mlock_failure: bool) -> Result<Self, ExecutableAnonymousMemoryMapCreationError>
+	{
+		use self::ExecutableAnonymousMemoryMapCreationError::*;
+
+		const NoOffset: i64 = 0;
+		
+		let aligned_length = Self::aligned_length(length);
+		
+		let file_descriptor = unsafe { memfd_create(b"assembler\0".as_ptr() as *const c_char, MFD_CLOEXEC) };
+		if unlikely!(file_descriptor == -1)
+		{
+			return Err(MemfdCreateFailed(io::Error::last_os_error()))
+		}
+		
+		if unlikely!(unsafe { ftruncate(file_descriptor, aligned_length as off_t) } != 0)
+		{
+			let error = io::Error::last_os_error();
+			unsafe { close(file_descriptor) };
+			return Err(FTruncateFailed(error, aligned_length))
+		}
+		
+		let executable = unsafe { mmap(null_mut(), aligned_length, PROT_READ | PROT_EXEC, Self::flags(MAP_SHARED, allocate_in_first_2Gb), file_descriptor, NoOffset) };
+		if unlikely!(executable == MAP_FAILED)
+		{
+			let error = io::Error::last_os_error();
+			unsafe { close(file_descriptor) };
+			return Err(MMapFailed(error, aligned_length))
+		}
+		
+		let writable = unsafe { mmap(null_mut(), aligned_length, PROT_READ | PROT_WRITE, MAP_SHARED, file_descriptor, NoOffset) };
+		if unlikely!(writable == MAP_FAILED)
+		{
+			let error = io::Error::last_os_error();
+			unsafe
+			{
+				munmap(executable, aligned_length);
+				close(file_descriptor);
+			}
+			return Err(MMapFailed(error, aligned_length))
+		}
+		
+		let this = Self
+		{
+			address: executable as *mut u8,
+			writable_address: writable as *mut u8,
+			length: aligned_length,
+			high_water_mark: 0,
+			ignore_mlock_failure,
+			dual_mapping_file_descriptor: Some(file_descriptor),
+			owns_memory: true,
+			#[cfg(feature = "gdb-jit-interface")]
+			gdb_jit_registrations: None,
+			eh_frame_registrations: Vec::new(),
+		};
+		
+		if let Err(error) = Self::mlock(this.address, aligned_length, ignore_mlock_failure, "new_dual_mapped")
+		{
+			return Err(MLockFailed(error, aligned_length))
+		}
+		
+		Ok(this)
+	}
+	
+	/// A view of `length` bytes at `offset` into this (dual mapped) map, which does not own its memory and can not grow.
+	#[inline(always)]
+	pub(crate) fn sub_region(&self, offset: usize, length: usize) -> Self
+	{
+		debug_assert!(self.is_dual_mapped(), "Only dual mapped maps can have sub regions");
+		debug_assert!(offset + length <= self.length, "sub region is outside of map");
+		
+		Self
+		{
+			address: unsafe { self.address.add(offset) },
+			writable_address: unsafe { self.writable_address.add(offset) },
+			length,
+			high_water_mark: 0,
+			ignore_mlock_failure: self.ignore_mlock_failure,
+			dual_mapping_file_descriptor: self.dual_mapping_file_descriptor,
+			owns_memory: false,
+			#[cfg(feature = "gdb-jit-interface")]
+			gdb_jit_registrations: None,
+			eh_frame_registrations: Vec::new(),
+		}
+	}
+	
+	/// The number of bytes, from the start of the map, used by the code of finished instruction streams.
+	#[inline(always)]
+	pub fn high_water_mark(&self) -> usize
+	{
+		self.high_water_mark
+	}
+	
+	/// Opts in to the GDB JIT compilation interface: the code of each instruction stream finished from now on is registered with debuggers such as GDB (and LLDB), so that backtraces show the names given with `InstructionStream.name_function()` rather than `??`.
+	///
+	/// Code is unregistered when this map is dropped.
+	///
+	/// Requires the `gdb-jit-interface` feature.
+	#[cfg(feature = "gdb-jit-interface")]
+	#[inline(always)]
+	pub fn enable_gdb_jit_interface(&mut self)
+	{
+		if self.gdb_jit_registrations.is_none()
+		{
+			self.gdb_jit_registrations = Some(Vec::new())
+		}
+	}
+	
+	/// Registers finished code with the GDB JIT compilation interface, if enabled.
+	#[cfg(feature = "gdb-jit-interface")]
+	#[inline(always)]
+	pub(crate) fn register_with_gdb(&mut self, start_instruction_pointer: InstructionPointer, length: usize, functions: &[(String, InstructionPointer)])
+	{
+		if let Some(ref mut gdb_jit_registrations) = self.gdb_jit_registrations
+		{
+			gdb_jit_registrations.push(GdbJitRegistration::register(start_instruction_pointer, length, functions))
+		}
+	}
+	
+	/// Registers the `.eh_frame` section of finished code with the unwinder.
+	#[inline(always)]
+	pub(crate) fn register_eh_frame(&mut self, eh_frame: &[u8])
+	{
+		self.eh_frame_registrations.push(EhFrameRegistration::register(eh_frame))
+	}
+	
+	/// Is this map dual mapped (see `new_dual_mapped()`)?
+	#[inline(always)]
+	pub fn is_dual_mapped(&self) -> bool
+	{
+		self.dual_mapping_file_descriptor.is_some()
+	}
+	
+	const PageSize: usize = 4096;
+	
+	#[inline(always)]
+	fn aligned_length(length: usize) -> usize
+	{
+		if length < Self::PageSize
+		{
+			Self::PageSize
+		}
+		else
+		{
+			length.next_power_of_two()
+		}
+	}
+	
+	#[cfg_attr(not(any(target_os = "android", target_os = "linux")), allow(unused_variables))]
+	#[inline(always)]
+	fn flags(flags: i32, allocate_in_first_2Gb: bool) -> i32
+	{
+		#[cfg(any(target_os = "android", target_os = "linux"))]
+		{
+			if allocate_in_first_2Gb
+			{
+				flags | MAP_32BIT
+			}
+			else
+			{
+				flags
+			}
+		}
+		
+		#[cfg(not(any(target_os = "android", target_os = "linux")))]
+		{
+			flags
+		}
+	}
+	
+	#[inline(always)]
+	fn mlock(address: *mut u8, length: usize, ignore_mlock_failure: bool, caller: &'static str) -> io::Result<()>
+	{
+		let result = unsafe { mlock(address as *mut _, length) };
+		if unlikely!(!ignore_mlock_failure && result != 0)
+		{
+			if likely!(result == -1)
+			{
+				Err(io::Error::last_os_error())
+			}
+			else
+			{
+				panic!("Unexpected result code from mlock ({}) '{}'", caller, result)
+			}
+		}
+		else
+		{
+			Ok(())
+		}
+	}
+	
 	/// Get an assembler to this anonymous map.
 	#[inline(always)]
-	pub fn instruction_stream(&mut self, instruction_stream_hints: &InstructionStreamHints) -> InstructionStream
+	pub fn instruction_stream(&mut self, instruction_stream_hints: &InstructionStreamHints) -> InstructionStream<'_>
 	{
 		InstructionStream::new(self, &instruction_stream_hints)
 	}
 	
+	/// Offset at which the next instruction stream starts.
+	#[inline(always)]
+	pub(crate) fn start_of_unfinished_code(&self) -> usize
+	{
+		self.high_water_mark
+	}
+	
+	/// Only pages from the one holding the end of finished code change protection; does nothing if dual mapped.
+	///
+	/// If that page holds finished code, it stays executable as well as becoming writable, so that the finished code can still be run.
+	#[inline(always)]
+	pub(crate) fn make_writable(&mut self) -> io::Result<()>
+	{
+		if self.is_dual_mapped()
+		{
+			return Ok(())
+		}
+		
+		let start_of_unfinished_code = self.start_of_unfinished_code();
+		let start_of_page = start_of_unfinished_code & !(Self::PageSize - 1);
+		if start_of_page == start_of_unfinished_code
+		{
+			self.protect_from(start_of_page, PROT_WRITE)
+		}
+		else
+		{
+			self.mprotect(unsafe { self.address.add(start_of_page) }, Self::PageSize, PROT_READ | PROT_WRITE | PROT_EXEC)?;
+			self.protect_from(start_of_page + Self::PageSize, PROT_WRITE)
+		}
+	}
+	
+	/// Only pages from the one holding the end of previously finished code change protection; does nothing if dual mapped.
+	///
+	/// `end_of_code` is an offset from the start of the map and becomes the new high water mark; on failure, the high water mark is unchanged.
 	#[inline(always)]
-	pub(crate) fn make_writable(&mut self)
+	pub(crate) fn make_executable(&mut self, end_of_code: usize) -> io::Result<()>
 	{
-		self.mprotect(self.address, self.length, PROT_WRITE)
+		if !self.is_dual_mapped()
+		{
+			let start_of_page = self.start_of_unfinished_code() & !(Self::PageSize - 1);
+			self.protect_from(start_of_page, PROT_READ | PROT_EXEC)?;
+		}
+		self.high_water_mark = end_of_code;
+		Ok(())
 	}
 	
 	#[inline(always)]
-	pub(crate) fn make_executable(&mut self)
+	fn protect_from(&self, offset: usize, protection_flags: i32) -> io::Result<()>
 	{
-		self.mprotect(self.address, self.length, PROT_EXEC)
+		if offset < self.length
+		{
+			self.mprotect(unsafe { self.address.add(offset) }, self.length - offset, protection_flags)
+		}
+		else
+		{
+			Ok(())
+		}
 	}
 	
+	/// On failure, the map is left as it was.
 	#[cfg(any(target_os = "android", target_os = "linux"))]
 	#[inline(always)]
 	pub(crate) fn attempt_to_resize_in_place_whilst_writing(&mut self) -> io::Result<usize>
 	{
-		const NoFlags: i32 = 0;
+		if unlikely!(!self.owns_memory)
+		{
+			return Err(io::Error::new(io::ErrorKind::Other, "A sub region can not grow"))
+		}
+		
 		let old_length = self.length;
 		let new_length = self.length * 2;
-		let old_address = self.address;
-		let new_address = unsafe { mremap(old_address as *mut _, old_length, new_length, NoFlags) };
-		if unlikely!(new_address == MAP_FAILED)
+		
+		if let Some(file_descriptor) = self.dual_mapping_file_descriptor
 		{
-			Err(io::Error::last_os_error())
+			if unlikely!(unsafe { ftruncate(file_descriptor, new_length as off_t) } != 0)
+			{
+				return Err(io::Error::last_os_error())
+			}
+			
+			if let Err(error) = Self::remap_in_place(self.writable_address, old_length, new_length)
+			{
+				unsafe { ftruncate(file_descriptor, old_length as off_t) };
+				return Err(error)
+			}
+			
+			if let Err(error) = Self::remap_in_place(self.address, old_length, new_length)
+			{
+				self.undo_resize_in_place(old_length, new_length, false);
+				return Err(error)
+			}
 		}
 		else
 		{
-			debug_assert_eq!(new_address as *mut u8, old_address, "address has changed");
-			
-			let new_memory_address = unsafe { old_address.add(old_length) };
-			
-			let result = unsafe { mlock(new_memory_address as *mut _, old_length) };
-			if unlikely!(!self.ignore_mlock_failure && result != 0)
+			Self::remap_in_place(self.address, old_length, new_length)?;
+		}
+		
+		let new_memory_address = unsafe { self.address.add(old_length) };
+		if let Err(error) = Self::mlock(new_memory_address, old_length, self.ignore_mlock_failure, "attempt_to_resize_in_place_whilst_writing")
+		{
+			self.undo_resize_in_place(old_length, new_length, true);
+			return Err(error)
+		}
+		
+		if !self.is_dual_mapped()
+		{
+			if let Err(error) = self.mprotect(new_memory_address, old_length, PROT_WRITE)
 			{
-				if likely!(result == -1)
-				{
-					return Err(io::Error::last_os_error())
-				}
-				else
-				{
-					panic!("Unexpected result code from mlock (attempt_to_resize_in_place_whilst_writing) '{}'", result)
-				}
+				self.undo_resize_in_place(old_length, new_length, true);
+				return Err(error)
 			}
-
-			self.mprotect(new_memory_address, old_length, PROT_WRITE);
-			self.length = new_length;
-			Ok(new_length)
+		}
+		
+		self.length = new_length;
+		Ok(new_length)
+	}
+	
+	/// Undoes growing from `old_length` to `new_length`, in the reverse order.
+	///
+	/// Failures are ignored rather than reported, as the caller is already returning an error and nothing beyond `old_length` is used; the file of a dual mapping is only shrunk once nothing maps past its new end.
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[inline(always)]
+	fn undo_resize_in_place(&self, old_length: usize, new_length: usize, executable_mapping_has_grown: bool)
+	{
+		if executable_mapping_has_grown && Self::remap_in_place(self.address, new_length, old_length).is_err()
+		{
+			return
+		}
+		
+		if let Some(file_descriptor) = self.dual_mapping_file_descriptor
+		{
+			if Self::remap_in_place(self.writable_address, new_length, old_length).is_ok()
+			{
+				unsafe { ftruncate(file_descriptor, old_length as off_t) };
+			}
+		}
+	}
+	
+	#[cfg(any(target_os = "android", target_os = "linux"))]
+	#[inline(always)]
+	fn remap_in_place(address: *mut u8, old_length: usize, new_length: usize) -> io::Result<()>
+	{
+		const NoFlags: i32 = 0;
+		let new_address = unsafe { mremap(address as *mut _, old_length, new_length, NoFlags) };
+		if unlikely!(new_address == MAP_FAILED)
+		{
+			Err(io::Error::last_os_error())
+		}
+		else
+		{
+			debug_assert_eq!(new_address as *mut u8, address, "address has changed");
+			Ok(())
 		}
 	}
 	
 	#[inline(always)]
-	fn mprotect(&self, address: *mut u8, length: usize, protection_flags: i32)
+	fn mprotect(&self, address: *mut u8, length: usize, protection_flags: i32) -> io::Result<()>
 	{
-		unsafe { mprotect(address as *mut _, length, protection_flags) };
+		if unlikely!(unsafe { mprotect(address as *mut _, length, protection_flags) } != 0)
+		{
+			Err(io::Error::last_os_error())
+		}
+		else
+		{
+			Ok(())
+		}
 	}
 }
@@ -11,6 +11,12 @@ pub enum ExecutableAnonymousMemoryMapCreationError
 
 	/// mlock failed for `usize` aligned bytes.
 	MLockFailed(io::Error, usize),
+
+	/// memfd_create failed (dual mapped only).
+	MemfdCreateFailed(io::Error),
+
+	/// ftruncate of a memfd failed for `usize` aligned bytes (dual mapped only).
+	FTruncateFailed(io::Error, usize),
 }
 
 impl Display for ExecutableAnonymousMemoryMapCreationError
@@ -23,6 +29,8 @@ impl Display for ExecutableAnonymousMemoryMapCreationError
 		{
 			MMapFailed(ref error, bytes) => write!(f, "mmap of {} aligned bytes failed with '{}'", bytes, error),
 			MLockFailed(ref error, bytes) => write!(f, "mlock of {} aligned bytes failed with '{}'", bytes, error),
+			MemfdCreateFailed(ref error) => write!(f, "memfd_create failed with '{}'", error),
+			FTruncateFailed(ref error, bytes) => write!(f, "ftruncate of memfd to {} aligned bytes failed with '{}'", bytes, error),
 		}
 	}
 }
@@ -38,6 +46,8 @@ impl Error for ExecutableAnonymousMemoryMapCreationError
 		{
 			MMapFailed(ref error, _) => Some(error),
 			MLockFailed(ref error, _) => Some(error),
+			MemfdCreateFailed(ref error) => Some(error),
+			FTruncateFailed(ref error, _) => Some(error),
 		}
 	}
 }
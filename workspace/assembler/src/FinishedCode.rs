@@ -0,0 +1,179 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// The result of finishing an `InstructionStream`.
+///
+/// Knows where each attached label ended up, so that one instruction stream can have many entry points.
+///
+/// Labels should be those created by the instruction stream that was finished; labels created by another instance may resolve to nonsense.
+///
+/// To link the code into a binary ahead of time, write it out with `ElfRelocatableObject`.
+#[derive(Debug)]
+pub struct FinishedCode<'a>
+{
+	encoded_bytes: &'a [u8],
+	hints: InstructionStreamHints,
+	labelled_locations: LabelledLocations,
+	emitted_labels: Vec<(Label, InstructionPointer)>,
+	function_calls: Vec<(usize, InstructionPointer)>,
+	functions: Vec<(String, InstructionPointer)>,
+}
+
+impl<'a> FinishedCode<'a>
+{
+	#[inline(always)]
+	pub(crate) fn new(encoded_bytes: &'a [u8], hints: InstructionStreamHints, labelled_locations: LabelledLocations, emitted_labels: Vec<(Label, InstructionPointer)>, function_calls: Vec<(usize, InstructionPointer)>, functions: Vec<(String, InstructionPointer)>) -> Self
+	{
+		Self
+		{
+			encoded_bytes,
+			hints,
+			labelled_locations,
+			emitted_labels,
+			function_calls,
+			functions,
+		}
+	}
+	
+	/// A slice containing just the instructions encoded; useful for testing or for dumping to a file.
+	#[inline(always)]
+	pub fn encoded_bytes(&self) -> &'a [u8]
+	{
+		self.encoded_bytes
+	}
+	
+	/// Hints to use for the next instance.
+	#[inline(always)]
+	pub fn hints(&self) -> &InstructionStreamHints
+	{
+		&self.hints
+	}
+	
+	/// The location of the start of instructions.
+	#[inline(always)]
+	pub fn start_instruction_pointer(&self) -> InstructionPointer
+	{
+		self.encoded_bytes.as_ptr() as usize
+	}
+	
+	/// Decodes the instructions encoded, eg to log a listing with `disassemble().listing()`.
+	#[inline(always)]
+	pub fn disassemble(&self) -> Decoder<'a>
+	{
+		Decoder::new(self.encoded_bytes, self.start_instruction_pointer())
+	}
+	
+	/// The absolute address `label` was attached to, or `None` if it was never attached.
+	#[inline(always)]
+	pub fn label_address(&self, label: Label) -> Option<InstructionPointer>
+	{
+		self.labelled_locations.attached_instruction_pointer(label)
+	}
+	
+	/// The offset from the start of instructions that `label` was attached to, or `None` if it was never attached.
+	#[inline(always)]
+	pub fn label_offset(&self, label: Label) -> Option<usize>
+	{
+		self.label_address(label).map(|instruction_pointer| instruction_pointer - self.start_instruction_pointer())
+	}
+	
+	/// Each function named with `InstructionStream.name_function()`, in order of address, as `(name, address, size)`.
+	///
+	/// The size of each function is taken to be up to the next named function, or the end of instructions.
+	#[inline(always)]
+	pub fn functions<'b>(&'b self) -> impl Iterator<Item=(&'b str, InstructionPointer, usize)> + 'b
+	{
+		Self::sized_functions(&self.functions, self.start_instruction_pointer() + self.encoded_bytes.len())
+	}
+	
+	/// `functions` must be in order of address.
+	#[inline(always)]
+	pub(crate) fn sized_functions<'b>(functions: &'b [(String, InstructionPointer)], end_instruction_pointer: InstructionPointer) -> impl Iterator<Item=(&'b str, InstructionPointer, usize)> + 'b
+	{
+		functions.iter().enumerate().map(move |(index, &(ref name, instruction_pointer))|
+		{
+			let next_instruction_pointer = functions.get(index + 1).map(|&(_, next_instruction_pointer)| next_instruction_pointer).unwrap_or(end_instruction_pointer);
+			(&name[..], instruction_pointer, next_instruction_pointer - instruction_pointer)
+		})
+	}
+	
+	/// Each label emitted with `emit_label()`, and the offset from the start of instructions of the 64-bit slot holding its absolute address.
+	#[inline(always)]
+	pub(crate) fn emitted_label_offsets<'b>(&'b self) -> impl Iterator<Item=(Label, usize)> + 'b
+	{
+		let start_instruction_pointer = self.start_instruction_pointer();
+		self.emitted_labels.iter().map(move |&(label, instruction_pointer)| (label, instruction_pointer - start_instruction_pointer))
+	}
+	
+	/// Each absolute address called with `call_function()`, and the offset from the start of instructions of the 32-bit relative displacement of the call.
+	#[inline(always)]
+	pub(crate) fn function_call_offsets<'b>(&'b self) -> impl Iterator<Item=(usize, usize)> + 'b
+	{
+		let start_instruction_pointer = self.start_instruction_pointer();
+		self.function_calls.iter().map(move |&(absolute_address, instruction_pointer)| (absolute_address, instruction_pointer - start_instruction_pointer))
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes no arguments and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn nullary_function_pointer<R>(&self, label: Label) -> Option<unsafe extern "C" fn() -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes one argument of type `A` and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn unary_function_pointer<R, A>(&self, label: Label) -> Option<unsafe extern "C" fn(A) -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes two argument of types `A` and `B` and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn binary_function_pointer<R, A, B>(&self, label: Label) -> Option<unsafe extern "C" fn(A, B) -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes three argument of types `A`, `B` and `C` and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn ternary_function_pointer<R, A, B, C>(&self, label: Label) -> Option<unsafe extern "C" fn(A, B, C) -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes four argument of types `A`, `B`, `C` and `D` and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn quaternary_function_pointer<R, A, B, C, D>(&self, label: Label) -> Option<unsafe extern "C" fn(A, B, C, D) -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes five argument of types `A`, `B`, `C`, `D` and `E` and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn quinary_function_pointer<R, A, B, C, D, E>(&self, label: Label) -> Option<unsafe extern "C" fn(A, B, C, D, E) -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+	
+	/// Creates a function pointer to the location of `label` that takes six argument of types `A`, `B`, `C`, `D`, `E` and `F` and returns a result of type `R`.
+	///
+	/// Returns `None` if `label` was never attached.
+	#[inline(always)]
+	pub fn senary_function_pointer<R, A, B, C, D, E, F>(&self, label: Label) -> Option<unsafe extern "C" fn(A, B, C, D, E, F) -> R>
+	{
+		self.label_address(label).map(|instruction_pointer| unsafe { transmute(instruction_pointer) })
+	}
+}
@@ -0,0 +1,186 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// `struct jit_code_entry` of the GDB JIT compilation interface; see <https://sourceware.org/gdb/onlinedocs/gdb/JIT-Interface.html>.
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct jit_code_entry
+{
+	pub(crate) next_entry: *mut jit_code_entry,
+	pub(crate) prev_entry: *mut jit_code_entry,
+	pub(crate) symfile_addr: *const u8,
+	pub(crate) symfile_size: u64,
+}
+
+/// `struct jit_descriptor` of the GDB JIT compilation interface.
+#[repr(C)]
+#[derive(Debug)]
+pub(crate) struct jit_descriptor
+{
+	pub(crate) version: u32,
+	pub(crate) action_flag: u32,
+	pub(crate) relevant_entry: *mut jit_code_entry,
+	pub(crate) first_entry: *mut jit_code_entry,
+}
+
+/// GDB reads the list of registered code entries from here.
+#[no_mangle]
+pub(crate) static mut __jit_debug_descriptor: jit_descriptor = jit_descriptor
+{
+	version: 1,
+	action_flag: GdbJitRegistration::JIT_NOACTION,
+	relevant_entry: null_mut(),
+	first_entry: null_mut(),
+};
+
+/// GDB puts a breakpoint on this function to learn when `__jit_debug_descriptor` has changed.
+#[no_mangle]
+#[inline(never)]
+pub(crate) extern "C" fn __jit_debug_register_code()
+{
+	// Stops the call from being optimized away.
+	compiler_fence(Ordering::SeqCst)
+}
+
+/// Serializes changes to `__jit_debug_descriptor`.
+static GdbJitDescriptorLock: Mutex<()> = Mutex::new(());
+
+/// An in-memory ELF symbol file for finished code, registered with the GDB JIT compilation interface so that debuggers can name the frames of generated functions.
+///
+/// Unregistered when dropped.
+#[derive(Debug)]
+pub(crate) struct GdbJitRegistration
+{
+	code_entry: Box<jit_code_entry>,
+
+	/// Referred to by `code_entry`.
+	_symbol_file: Vec<u8>,
+}
+
+impl Drop for GdbJitRegistration
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		let _lock = Self::lock();
+
+		let code_entry = &mut *self.code_entry as *mut jit_code_entry;
+		unsafe
+		{
+			let descriptor = addr_of_mut!(__jit_debug_descriptor);
+			let next_entry = (*code_entry).next_entry;
+			let prev_entry = (*code_entry).prev_entry;
+			if prev_entry.is_null()
+			{
+				(*descriptor).first_entry = next_entry
+			}
+			else
+			{
+				(*prev_entry).next_entry = next_entry
+			}
+			if !next_entry.is_null()
+			{
+				(*next_entry).prev_entry = prev_entry
+			}
+
+			Self::notify(descriptor, code_entry, Self::JIT_UNREGISTER_FN)
+		}
+	}
+}
+
+impl GdbJitRegistration
+{
+	const JIT_NOACTION: u32 = 0;
+
+	const JIT_REGISTER_FN: u32 = 1;
+
+	const JIT_UNREGISTER_FN: u32 = 2;
+
+	/// Registers the code of `length` bytes at `start_instruction_pointer`, naming the functions at the instruction pointers in `functions`, which must be in order of address.
+	///
+	/// The size of each function is taken to be up to the next function, or the end of the code.
+	pub(crate) fn register(start_instruction_pointer: InstructionPointer, length: usize, functions: &[(String, InstructionPointer)]) -> Self
+	{
+		let symbol_file = Self::create_symbol_file(start_instruction_pointer, length, functions);
+
+		let mut this = Self
+		{
+			code_entry: Box::new
+			(
+				jit_code_entry
+				{
+					next_entry: null_mut(),
+					prev_entry: null_mut(),
+					symfile_addr: symbol_file.as_ptr(),
+					symfile_size: symbol_file.len() as u64,
+				}
+			),
+			_symbol_file: symbol_file,
+		};
+
+		let _lock = Self::lock();
+
+		let code_entry = &mut *this.code_entry as *mut jit_code_entry;
+		unsafe
+		{
+			let descriptor = addr_of_mut!(__jit_debug_descriptor);
+			let first_entry = (*descriptor).first_entry;
+			(*code_entry).next_entry = first_entry;
+			if !first_entry.is_null()
+			{
+				(*first_entry).prev_entry = code_entry
+			}
+			(*descriptor).first_entry = code_entry;
+
+			Self::notify(descriptor, code_entry, Self::JIT_REGISTER_FN)
+		}
+
+		this
+	}
+
+	#[inline(always)]
+	fn lock() -> MutexGuard<'static, ()>
+	{
+		GdbJitDescriptorLock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+	}
+
+	#[inline(always)]
+	unsafe fn notify(descriptor: *mut jit_descriptor, code_entry: *mut jit_code_entry, action_flag: u32)
+	{
+		(*descriptor).relevant_entry = code_entry;
+		(*descriptor).action_flag = action_flag;
+		__jit_debug_register_code();
+		(*descriptor).relevant_entry = null_mut();
+		(*descriptor).action_flag = Self::JIT_NOACTION;
+	}
+
+	/// A relocatable object whose `.text` section has no contents but is placed at the location of the code, as GDB only needs the symbols.
+	fn create_symbol_file(start_instruction_pointer: InstructionPointer, length: usize, functions: &[(String, InstructionPointer)]) -> Vec<u8>
+	{
+		const TextSectionIndex: u16 = 1;
+		const StringTableSectionIndex: u32 = 3;
+
+		let mut string_table = ElfStringTable::default();
+		let mut symbol_table = Vec::with_capacity((functions.len() + 1) * ElfSymbol::Size);
+		ElfSymbol::default().write(&mut symbol_table);
+		for (name, instruction_pointer, size) in FinishedCode::sized_functions(functions, start_instruction_pointer + length)
+		{
+			ElfSymbol
+			{
+				name: string_table.add(name),
+				binding: ElfSymbol::STB_GLOBAL,
+				symbol_type: ElfSymbol::STT_FUNC,
+				section_index: TextSectionIndex,
+				value: (instruction_pointer - start_instruction_pointer) as u64,
+				size: size as u64,
+			}.write(&mut symbol_table);
+		}
+
+		let mut elf_file = ElfFile::new();
+		elf_file.add_section(".text", ElfSectionHeader { section_type: ElfSectionHeader::SHT_NOBITS, flags: ElfSectionHeader::SHF_ALLOC | ElfSectionHeader::SHF_EXECINSTR, address: start_instruction_pointer as u64, size: length, alignment: 16, ..ElfSectionHeader::default() }, &[]);
+		elf_file.add_section(".symtab", ElfSectionHeader { section_type: ElfSectionHeader::SHT_SYMTAB, link: StringTableSectionIndex, info: 1, alignment: 8, entry_size: ElfSymbol::Size, ..ElfSectionHeader::default() }, &symbol_table);
+		elf_file.add_section(".strtab", ElfSectionHeader { section_type: ElfSectionHeader::SHT_STRTAB, alignment: 1, ..ElfSectionHeader::default() }, &string_table.bytes);
+		elf_file.finish(ElfFile::ET_REL)
+	}
+}
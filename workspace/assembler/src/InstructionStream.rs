@@ -6,11 +6,15 @@
 ///
 /// Has functions for writing x64 instructions, organized by mnemonic and the parameters they need.
 ///
-/// When finished, call `finish()`.
+/// When finished, call `finish()`; the resultant `FinishedCode` knows where labels ended up.
 ///
 /// When writing 8-bit `Jcc` (`JMP` and conditional `JMP` instructions), a `ShortJmpResult` is returned in error if the target effective address could be resolved and its displacement exceeds the size of an `i8`. In this case, the instruction stream is rolled back to point to just before where the instruction started to be emitted. Use this result to try to make a 8-bit `JMP` and then replace it with a 32-bit one if an error occurs.
 ///
-/// Note that unresolved labels (ie those yet to be attached to a location in the instruction stream) will not produce such an error. Instead a panic (in debug builds) or silent error will occur when `finish()` is called.
+/// Note that unresolved labels (ie those yet to be attached to a location in the instruction stream) will not produce such an error. Instead an `AssemblerError` is returned when `try_finish()` is called (or a panic occurs when `finish()` is called).
+///
+/// Other errors whilst emitting (a memory map that can not grow, a label attached twice or a near jump of more than 2Gb) are also recorded and returned by `try_finish()`; once one has occurred, further code is emitted but thrown away.
+///
+/// Alternatively, call `relax_jumps_on_finish()` and always use the 32-bit `Jcc` (`JMP` and conditional `JMP` instructions, eg `jmp_Label_1()`); these will be shrunk to 8-bit forms by `finish()` wherever possible.
 #[derive(Debug)]
 pub struct InstructionStream<'a>
 {
@@ -20,6 +24,19 @@ pub struct InstructionStream<'a>
 	instruction_pointers_to_replace_labels_with_8_bit_displacements: Vec<(Label, InstructionPointer)>,
 	instruction_pointers_to_replace_labels_with_32_bit_displacements: Vec<(Label, InstructionPointer)>,
 	emitted_labels: Vec<(Label, InstructionPointer)>,
+	pending_label_relative_memory_operand: Option<(Label, InstructionPointer)>,
+	label_relative_memory_operands: Vec<(Label, InstructionPointer, InstructionPointer)>,
+	emitted_relative_labels: Vec<(Label, Label, InstructionPointer)>,
+	constant_pool: ConstantPool,
+	function_calls: Vec<(usize, InstructionPointer)>,
+	function_names: Vec<(Label, String)>,
+	call_frame_information: CallFrameInformation,
+	branch_relaxation: Option<BranchRelaxation>,
+	pinned_instruction_pointer: Cell<InstructionPointer>,
+	landing_pads: Option<HashSet<Label>>,
+	function_pointer_entry_points: RefCell<Vec<InstructionPointer>>,
+	first_error: Option<AssemblerError>,
+	discarded: Vec<Box<[u8]>>,
 }
 
 impl<'a> InstructionStream<'a>
@@ -39,12 +56,15 @@ impl<'a> InstructionStream<'a>
 	/// `REX` prefix.
 	pub(crate) const REX: u8 = 0x40;
 	
+	/// `ENDBR64` instruction, read as a little-endian double word.
+	const ENDBR64: u32 = 0xFA1E0FF3;
+	
 	#[inline(always)]
 	pub(crate) fn new(executable_anonymous_memory_map: &'a mut ExecutableAnonymousMemoryMap, instruction_stream_hints: &InstructionStreamHints) -> Self
 	{
-		executable_anonymous_memory_map.make_writable();
+		let made_writable = executable_anonymous_memory_map.make_writable();
 		
-		Self
+		let mut this = Self
 		{
 			byte_emitter: ByteEmitter::new(executable_anonymous_memory_map),
 			executable_anonymous_memory_map,
@@ -52,7 +72,29 @@ impl<'a> InstructionStream<'a>
 			instruction_pointers_to_replace_labels_with_8_bit_displacements: Vec::with_capacity(instruction_stream_hints.number_of_8_bit_jumps),
 			instruction_pointers_to_replace_labels_with_32_bit_displacements: Vec::with_capacity(instruction_stream_hints.number_of_32_bit_jumps),
 			emitted_labels: Vec::with_capacity(instruction_stream_hints.number_of_emitted_labels),
+			pending_label_relative_memory_operand: None,
+			label_relative_memory_operands: Vec::new(),
+			emitted_relative_labels: Vec::new(),
+			constant_pool: ConstantPool::default(),
+			function_calls: Vec::new(),
+			function_names: Vec::new(),
+			call_frame_information: CallFrameInformation::default(),
+			branch_relaxation: None,
+			pinned_instruction_pointer: Cell::new(0),
+			landing_pads: None,
+			function_pointer_entry_points: RefCell::new(Vec::new()),
+			first_error: None,
+			discarded: Vec::new(),
+		};
+		
+		// The memory map can not be written to, so emit into a scratch buffer from the start.
+		if let Err(error) = made_writable
+		{
+			this.record_error(AssemblerError::ProtectionFailed(error));
+			this.emit_into_scratch(0)
 		}
+		
+		this
 	}
 	
 	#[cfg(any(target_os = "android", target_os = "linux"))]
@@ -60,7 +102,7 @@ impl<'a> InstructionStream<'a>
 	fn attempt_to_resize_in_place(&mut self) -> io::Result<()>
 	{
 		let new_length = self.executable_anonymous_memory_map.attempt_to_resize_in_place_whilst_writing()?;
-		self.byte_emitter.end_instruction_pointer += new_length;
+		self.byte_emitter.end_instruction_pointer = (self.executable_anonymous_memory_map.address as usize) + new_length;
 		Ok(())
 	}
 	
@@ -86,44 +128,158 @@ impl<'a> InstructionStream<'a>
 	
 	/// Resolves all remaining labels and makes code executable.
 	///
-	/// Will panic in debug builds if labels can not be resolved, 8-bit JMPs are too far away or 32-bit JMPs have displacements of more than 2Gb!
+	/// Panics if `try_finish()` would return an error.
+	///
+	/// Returns the instructions encoded, the locations of labels and hints to use for the next instance.
+	#[inline(always)]
+	pub fn finish(self) -> FinishedCode<'a>
+	{
+		match self.try_finish()
+		{
+			Ok(result) => result,
+			Err(error) => panic!("Could not finish instruction stream: {}", error),
+		}
+	}
+	
+	/// Resolves all remaining labels and makes code executable.
+	///
+	/// Returns an error if an error was recorded whilst emitting (including failing to make the memory map writable), labels can not be resolved, 8-bit JMPs are too far away, 32-bit JMPs have displacements of more than 2Gb or the memory map can not be made executable; in this case, code is not made executable.
 	///
-	/// Returns a slice containing just the instructions encoded; useful for testing or for dumping to a file; and hints to use for the next instance.
+	/// Returns the instructions encoded, the locations of labels and hints to use for the next instance.
 	#[inline(always)]
-	pub fn finish(mut self) -> (&'a [u8], InstructionStreamHints)
+	pub fn try_finish(mut self) -> Result<FinishedCode<'a>, AssemblerError>
 	{
+		if let Some(error) = self.first_error.take()
+		{
+			return Err(error)
+		}
+		
 		let hints = self.hints_for_next_instance();
 		
-		for (label, insert_at_instruction_pointer) in self.instruction_pointers_to_replace_labels_with_8_bit_displacements.iter()
+		let end_instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.end_procedure(end_instruction_pointer);
+		
+		self.check_function_pointer_landing_pads()?;
+		
+		if let Some(mut branch_relaxation) = self.branch_relaxation.take()
+		{
+			self.instruction_pointers_to_replace_labels_with_32_bit_displacements.extend(branch_relaxation.pin(self.pinned_instruction_pointer.get()));
+			let relocation = branch_relaxation.relax(&mut self.byte_emitter, &mut self.labelled_locations, &mut self.instruction_pointers_to_replace_labels_with_8_bit_displacements, &mut self.instruction_pointers_to_replace_labels_with_32_bit_displacements, &mut self.emitted_labels, &mut self.function_calls);
+			self.call_frame_information.relocate(|instruction_pointer| relocation.relocate(instruction_pointer));
+			for &mut (_, ref mut insert_at_instruction_pointer, ref mut end_instruction_pointer) in self.label_relative_memory_operands.iter_mut()
+			{
+				*insert_at_instruction_pointer = relocation.relocate(*insert_at_instruction_pointer);
+				*end_instruction_pointer = relocation.relocate(*end_instruction_pointer);
+			}
+			for &mut (_, _, ref mut insert_at_instruction_pointer) in self.emitted_relative_labels.iter_mut()
+			{
+				*insert_at_instruction_pointer = relocation.relocate(*insert_at_instruction_pointer)
+			}
+		}
+		
+		self.flush_constant_pool();
+		if let Some(error) = self.first_error.take()
 		{
-			let target_instruction_pointer = self.valid_target_instruction_pointer(*label);
+			return Err(error)
+		}
+		
+		for &(label, insert_at_instruction_pointer) in self.instruction_pointers_to_replace_labels_with_8_bit_displacements.iter()
+		{
+			let target_instruction_pointer = self.valid_target_instruction_pointer(label)?;
 			
-			let result = self.byte_emitter.insert_8_bit_effective_address_displacement(*insert_at_instruction_pointer, target_instruction_pointer);
+			if unlikely!(self.byte_emitter.insert_8_bit_effective_address_displacement(insert_at_instruction_pointer, target_instruction_pointer).is_err())
+			{
+				return Err(self.displacement_overflow(label, insert_at_instruction_pointer, 8))
+			}
+		}
+		
+		for &(label, insert_at_instruction_pointer) in self.instruction_pointers_to_replace_labels_with_32_bit_displacements.iter()
+		{
+			let target_instruction_pointer = self.valid_target_instruction_pointer(label)?;
 			
-			debug_assert!(result.is_ok(), "8-bit JMP for {:?} was too far", label)
+			if unlikely!(self.byte_emitter.insert_32_bit_effective_address_displacement(insert_at_instruction_pointer, target_instruction_pointer).is_err())
+			{
+				return Err(self.displacement_overflow(label, insert_at_instruction_pointer, 32))
+			}
 		}
 		
-		for (label, insert_at_instruction_pointer) in self.instruction_pointers_to_replace_labels_with_32_bit_displacements.iter()
+		for &(label, insert_at_instruction_pointer, end_instruction_pointer) in self.label_relative_memory_operands.iter()
 		{
-			let target_instruction_pointer = self.valid_target_instruction_pointer(*label);
+			let target_instruction_pointer = self.valid_target_instruction_pointer(label)?;
 			
-			let result = self.byte_emitter.insert_32_bit_effective_address_displacement(*insert_at_instruction_pointer, target_instruction_pointer);
+			if unlikely!(self.byte_emitter.insert_32_bit_relative_instruction_pointer_displacement(insert_at_instruction_pointer, end_instruction_pointer, target_instruction_pointer).is_err())
+			{
+				return Err(self.displacement_overflow(label, insert_at_instruction_pointer, 32))
+			}
+		}
+		
+		for &(table, label, insert_at_instruction_pointer) in self.emitted_relative_labels.iter()
+		{
+			let base_instruction_pointer = self.valid_target_instruction_pointer(table)?;
+			let target_instruction_pointer = self.valid_target_instruction_pointer(label)?;
 			
-			debug_assert!(result.is_ok(), "32-bit JMP for {:?} was too far", label)
+			if unlikely!(self.byte_emitter.insert_32_bit_relative_offset(insert_at_instruction_pointer, base_instruction_pointer, target_instruction_pointer).is_err())
+			{
+				return Err(self.displacement_overflow(label, insert_at_instruction_pointer, 32))
+			}
 		}
 		
-		for (label, insert_at_instruction_pointer) in self.emitted_labels.iter()
+		for &(label, insert_at_instruction_pointer) in self.emitted_labels.iter()
 		{
-			let target_instruction_pointer = self.valid_target_instruction_pointer(*label);
+			let target_instruction_pointer = self.valid_target_instruction_pointer(label)?;
 			
-			self.byte_emitter.emit_u64_at(target_instruction_pointer as u64, *insert_at_instruction_pointer)
+			self.byte_emitter.emit_u64_at(target_instruction_pointer as u64, insert_at_instruction_pointer)
 		}
 		
-		self.executable_anonymous_memory_map.make_executable();
+		let function_names = ::std::mem::take(&mut self.function_names);
+		let mut functions = Vec::with_capacity(function_names.len());
+		for (label, name) in function_names
+		{
+			let instruction_pointer = self.valid_target_instruction_pointer(label)?;
+			functions.push((name, instruction_pointer));
+		}
+		functions.sort_by_key(|&(_, instruction_pointer)| instruction_pointer);
+		
+		let end_of_code = self.instruction_pointer() - (self.executable_anonymous_memory_map.address as usize);
+		self.executable_anonymous_memory_map.make_executable(end_of_code).map_err(AssemblerError::ProtectionFailed)?;
 		
 		let length = self.instruction_pointer() - self.start_instruction_pointer();
+		#[cfg(feature = "gdb-jit-interface")]
+		{
+			let start_instruction_pointer = self.start_instruction_pointer();
+			self.executable_anonymous_memory_map.register_with_gdb(start_instruction_pointer, length, &functions);
+		}
+		if let Some(eh_frame) = self.call_frame_information.eh_frame()
+		{
+			self.executable_anonymous_memory_map.register_eh_frame(&eh_frame);
+		}
+		
 		let slice = unsafe { from_raw_parts(self.start_instruction_pointer() as *const u8, length) };
-		(slice, hints)
+		Ok(FinishedCode::new(slice, hints, self.labelled_locations, self.emitted_labels, self.function_calls, functions))
+	}
+	
+	/// The first error recorded whilst emitting, if any.
+	///
+	/// Once an error has been recorded, `try_finish()` will return it.
+	#[inline(always)]
+	pub fn error(&self) -> Option<&AssemblerError>
+	{
+		self.first_error.as_ref()
+	}
+	
+	#[inline(always)]
+	fn record_error(&mut self, error: AssemblerError)
+	{
+		if self.first_error.is_none()
+		{
+			self.first_error = Some(error)
+		}
+	}
+	
+	#[inline(always)]
+	fn displacement_overflow(&self, label: Label, insert_at_instruction_pointer: InstructionPointer, bits: u8) -> AssemblerError
+	{
+		AssemblerError::DisplacementOverflow(label, insert_at_instruction_pointer - self.start_instruction_pointer(), bits)
 	}
 	
 	#[inline(always)]
@@ -133,11 +289,54 @@ impl<'a> InstructionStream<'a>
 	}
 	
 	#[inline(always)]
-	fn valid_target_instruction_pointer(&self, label: Label) -> InstructionPointer
+	fn valid_target_instruction_pointer(&self, label: Label) -> Result<InstructionPointer, AssemblerError>
 	{
 		let target_instruction_pointer = self.target_instruction_pointer(label);
-		debug_assert!(target_instruction_pointer.is_valid(), "unresolved {:?}'", label);
-		target_instruction_pointer
+		if unlikely!(!target_instruction_pointer.is_valid())
+		{
+			return Err(AssemblerError::UnresolvedLabel(label))
+		}
+		Ok(target_instruction_pointer)
+	}
+	
+	/// Opts in to branch relaxation: near (32-bit displacement) `JMP` and `Jcc` to labels emitted from now on are recorded, and, when `finish()` is called, are shrunk to short (8-bit displacement) forms wherever the final distance allows.
+	///
+	/// Code after a shrunk jump is moved down to close the gap, and labels, emitted labels and calls made with `call_function()` are adjusted to match.
+	///
+	/// Code that relies on absolute locations in the instruction stream prevents jumps before it from being shrunk; this applies to:-
+	///
+	/// * `emit_alignment()`;
+	/// * `emit_fixed_size_block()` (jumps inside the block are not shrunk either);
+	/// * the `*_function_pointer()` methods;
+	/// * `statically_relative_address()` and `jmp_Any64BitMemory_statically_relative_address()`;
+	/// * `overwrite_last_32bit_displacement_with_relative_address_to()`.
+	///
+	/// To get a function pointer to code that should still be relaxed, attach a label instead.
+	///
+	/// Values obtained from `instruction_pointer()`, and instructions taking a `RelativeAddress32Bit`, are not adjusted.
+	///
+	/// Assembly is slower in this mode, as all label displacements are resolved by `finish()`.
+	#[inline(always)]
+	pub fn relax_jumps_on_finish(&mut self)
+	{
+		if self.branch_relaxation.is_none()
+		{
+			self.branch_relaxation = Some(BranchRelaxation::default())
+		}
+	}
+	
+	/// Opts in to emitting landing pads for Intel's Control-flow Enforcement Technology (CET) Indirect Branch Tracking (IBT): from now on, an `ENDBR64` is emitted when attaching a label previously passed to `emit_label()` or `emit_relative_label()` (for example, as a case of `jmp_relative_jump_table()`).
+	///
+	/// It is an error, recorded and returned by `try_finish()`, to pass an already attached label to `emit_label()` or `emit_relative_label()` unless its location starts with an `ENDBR64` (for example, one emitted using `endbr64()`).
+	///
+	/// The `*_function_pointer()` methods can not emit code, so use `endbr64()` immediately after creating a function pointer; `try_finish()` returns an error for any function pointer created from now on whose location does not start with an `ENDBR64`.
+	#[inline(always)]
+	pub fn emit_landing_pads_for_indirect_branch_tracking(&mut self)
+	{
+		if self.landing_pads.is_none()
+		{
+			self.landing_pads = Some(HashSet::new())
+		}
 	}
 	
 	/// Creates an unique label and uses it to label the current location.
@@ -162,62 +361,357 @@ impl<'a> InstructionStream<'a>
 	///
 	/// It is an error to use the same label to label more than one location (or to label the current location with the same label twice or more).
 	///
-	/// This only checked for in debug builds where it causes a runtime panic.
+	/// The error is recorded and returned by `try_finish()`; the label keeps its first location.
 	///
 	/// Labels should be created using `self.create_label()`; no checks are made for labels created with another instance and attached to this one.
 	#[inline(always)]
 	pub fn attach_label(&mut self, label: Label)
+	{
+		if let Err(error) = self.try_attach_label(label)
+		{
+			self.record_error(error)
+		}
+	}
+	
+	/// Labels the current location, returning an error immediately (and not recording it) if `label` has already been used to label.
+	#[inline(always)]
+	pub fn try_attach_label(&mut self, label: Label) -> Result<(), AssemblerError>
 	{
 		let instruction_pointer = self.instruction_pointer();
-		self.labelled_locations.set(label, instruction_pointer)
+		if likely!(self.labelled_locations.set(label, instruction_pointer))
+		{
+			if let Some(ref mut landing_pads) = self.landing_pads
+			{
+				if landing_pads.remove(&label)
+				{
+					self.endbr64()
+				}
+			}
+			Ok(())
+		}
+		else
+		{
+			Err(AssemblerError::LabelAlreadyAttached(label))
+		}
 	}
 	
-	/// Emits the 64-bit value of a label at the current location.
+	/// Names the function starting at `label`, for debuggers and profilers (see `ExecutableAnonymousMemoryMap.enable_gdb_jit_interface()`).
 	///
-	/// Typically used when build jump tables.
+	/// It is an error for `label` to be unattached when `try_finish()` is called.
+	#[inline(always)]
+	pub fn name_function(&mut self, label: Label, name: impl Into<String>)
+	{
+		self.function_names.push((label, name.into()))
+	}
+	
+	/// Emits the 64-bit absolute address of `label` at the current location.
 	///
-	/// It is an error to use the same label to label more than one location (or to label the current location with the same label twice or more).
+	/// Typically used when building jump tables.
 	///
-	/// This only checked for in debug builds where it causes a runtime panic.
+	/// The label does not need to be attached yet; it is an error, returned by `try_finish()`, for it to still be unattached then.
 	///
 	/// Labels should be created using `self.create_label()`; no checks are made for labels created with another instance and attached to this one.
 	#[inline(always)]
 	pub fn emit_label(&mut self, label: Label)
 	{
+		self.landing_pad(label);
+		
+		// Recorded even if the address is written now, so that `ElfRelocatableObject` can find every slot needing a relocation; `finish()` then writes the same address again.
+		let instruction_pointer = self.instruction_pointer();
+		self.emitted_labels.push((label, instruction_pointer));
+		
 		let target_instruction_pointer = self.target_instruction_pointer(label);
-		if target_instruction_pointer.is_valid()
+		if target_instruction_pointer.is_valid() && self.branch_relaxation.is_none()
 		{
 			self.emit_quad_word(target_instruction_pointer as u64)
 		}
 		else
 		{
-			let instruction_pointer = self.instruction_pointer();
-			self.emitted_labels.push((label, instruction_pointer));
 			self.skip_quad_word();
 		}
 	}
 	
+	/// Emits the 32-bit signed offset of `label` relative to the location of `table` at the current location.
+	///
+	/// Typically used when building position-independent jump tables (see `emit_relative_jump_table()`); it is an error for either label to be unattached when `try_finish()` is called.
+	#[inline(always)]
+	pub fn emit_relative_label(&mut self, table: Label, label: Label)
+	{
+		self.landing_pad(label);
+		
+		let insert_at_instruction_pointer = self.instruction_pointer();
+		self.skip_double_word();
+		
+		let base_instruction_pointer = self.target_instruction_pointer(table);
+		let target_instruction_pointer = self.target_instruction_pointer(label);
+		if base_instruction_pointer.is_valid() && target_instruction_pointer.is_valid() && self.branch_relaxation.is_none()
+		{
+			if unlikely!(self.byte_emitter.insert_32_bit_relative_offset(insert_at_instruction_pointer, base_instruction_pointer, target_instruction_pointer).is_err())
+			{
+				let error = self.displacement_overflow(label, insert_at_instruction_pointer, 32);
+				self.record_error(error)
+			}
+		}
+		else
+		{
+			self.emitted_relative_labels.push((table, label, insert_at_instruction_pointer))
+		}
+	}
+	
+	/// Emits a jump table at the current location, with an entry for each of `cases` holding its 32-bit signed offset relative to the start of the table, and returns a label for the start of the table.
+	///
+	/// Such a table is half the size of one made using `emit_label()` and position-independent; see `jmp_relative_jump_table()` to emit one with the code to dispatch using it.
+	#[inline(always)]
+	pub fn emit_relative_jump_table(&mut self, cases: &[Label]) -> Label
+	{
+		let table = self.create_and_attach_label();
+		for &case in cases
+		{
+			self.emit_relative_label(table, case)
+		}
+		table
+	}
+	
+	/// Jumps to `cases[index]`, using a jump table made by `emit_relative_jump_table()` emitted straight after the jump.
+	///
+	/// The sequence is:-
+	///
+	/// * `CMP index, cases.len()` and `JAE default`, if there is a `default` label (otherwise `index` must be less than `cases.len()`);
+	/// * `LEA scratch, [RIP+table]`;
+	/// * `MOVSXD index, DWORD [scratch+index*4]`;
+	/// * `ADD scratch, index`;
+	/// * `JMP scratch`.
+	///
+	/// `index` is treated as unsigned; both `index` and `scratch` are overwritten.
+	///
+	/// Panics if `index` and `scratch` are the same register.
+	#[inline(always)]
+	pub fn jmp_relative_jump_table(&mut self, index: Register64Bit, scratch: Register64Bit, cases: &[Label], default: Option<Label>)
+	{
+		assert_ne!(index, scratch, "index and scratch must be different registers");
+		debug_assert!(cases.len() <= i32::MAX as usize, "too many cases");
+		
+		if let Some(default) = default
+		{
+			self.cmp_Register64Bit_Immediate32Bit_shortest(index, Immediate32Bit(cases.len() as i32));
+			self.jae_Label_1(default);
+		}
+		
+		let table = self.create_label();
+		self.lea_Register64Bit_Any64BitMemory(scratch, Any64BitMemory::relative_instruction_pointer_relative_label(table));
+		self.movsxd_Register64Bit_Any32BitMemory(index, Any32BitMemory::base_64_index_64_scale(scratch, index, IndexScale::x4));
+		self.add_Register64Bit_Register64Bit(scratch, index);
+		self.jmp_Register64Bit(scratch);
+		
+		self.attach_label(table);
+		for &case in cases
+		{
+			self.emit_relative_label(table, case)
+		}
+	}
+	
+	/// Adds a 32-bit constant to the constant pool, returning a reference to it which can be used as a memory operand (eg `constant.into()` for an `Any32BitMemory`).
+	///
+	/// Constants are deduplicated; see `flush_constant_pool()`.
+	#[inline(always)]
+	pub fn constant_u32(&mut self, value: u32) -> ConstantRef
+	{
+		self.constant_bytes(&value.to_le_bytes(), 4)
+	}
+	
+	/// Adds a 64-bit constant to the constant pool, returning a reference to it which can be used as a memory operand (eg `constant.into()` for an `Any64BitMemory`).
+	///
+	/// Constants are deduplicated; see `flush_constant_pool()`.
+	#[inline(always)]
+	pub fn constant_u64(&mut self, value: u64) -> ConstantRef
+	{
+		self.constant_bytes(&value.to_le_bytes(), 8)
+	}
+	
+	/// Adds a 128-bit constant to the constant pool, returning a reference to it which can be used as a memory operand (eg `constant.into()` for an `Any128BitMemory`).
+	///
+	/// Constants are deduplicated; see `flush_constant_pool()`.
+	#[inline(always)]
+	pub fn constant_u128(&mut self, value: u128) -> ConstantRef
+	{
+		self.constant_bytes(&value.to_le_bytes(), 16)
+	}
+	
+	/// Adds a single-precision floating point constant to the constant pool, returning a reference to it which can be used as a memory operand (eg `constant.into()` for an `Any32BitMemory`).
+	///
+	/// Constants are deduplicated by their bits; see `flush_constant_pool()`.
+	#[inline(always)]
+	pub fn constant_f32(&mut self, value: f32) -> ConstantRef
+	{
+		self.constant_u32(value.to_bits())
+	}
+	
+	/// Adds a double-precision floating point constant to the constant pool, returning a reference to it which can be used as a memory operand (eg `constant.into()` for an `Any64BitMemory`).
+	///
+	/// Constants are deduplicated by their bits; see `flush_constant_pool()`.
+	#[inline(always)]
+	pub fn constant_f64(&mut self, value: f64) -> ConstantRef
+	{
+		self.constant_u64(value.to_bits())
+	}
+	
+	/// Adds a constant of any size (eg a 256-bit or 512-bit vector) to the constant pool, returning a reference to it which can be used as a memory operand.
+	///
+	/// `alignment` must be a power of two.
+	///
+	/// Constants with the same bytes and alignment are deduplicated; see `flush_constant_pool()`.
+	#[inline(always)]
+	pub fn constant_bytes(&mut self, bytes: &[u8], alignment: usize) -> ConstantRef
+	{
+		let labelled_locations = &mut self.labelled_locations;
+		ConstantRef(self.constant_pool.constant(bytes, alignment, || labelled_locations.create_label()))
+	}
+	
+	/// Emits, aligned, the constants added to the constant pool since it was last flushed, at the current location.
+	///
+	/// The constants are data, so the current location must not be reachable by execution (eg it follows an unconditional `JMP` or a `RET`).
+	///
+	/// `finish()` flushes any remaining constants after the code; an explicit flush is only needed to keep constants within 2Gb of the code that uses them, or close to it for caching.
+	///
+	/// Like `emit_alignment()`, this prevents jumps before the current location from being shrunk by branch relaxation.
+	#[inline(always)]
+	pub fn flush_constant_pool(&mut self)
+	{
+		for (label, bytes, alignment) in self.constant_pool.take_unflushed()
+		{
+			self.emit_alignment(alignment);
+			self.attach_label(label);
+			self.emit_bytes(&bytes);
+		}
+	}
+	
+	/// Starts a procedure (function) at the current location, for which call frame information (CFI) is recorded; ends any procedure already started.
+	///
+	/// Call frame information is recorded by `push_stack_frame()`, `pop_stack_frame_and_return()`, `push_callee_saved_register()`, `pop_callee_saved_register()`, `allocate_stack()` and `free_stack()`; other instructions that change `RSP` or save registers need a directive from `cfi()`.
+	///
+	/// When finished, an `.eh_frame` section is built from the call frame information of all procedures and registered with the unwinder, so that panics, backtraces and profilers can unwind through generated code; it is deregistered when the memory map is dropped.
+	#[inline(always)]
+	pub fn cfi_start_procedure(&mut self)
+	{
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.start_procedure(instruction_pointer)
+	}
+	
+	/// Ends the procedure started by `cfi_start_procedure()` at the current location.
+	///
+	/// `finish()` ends any procedure still started.
+	#[inline(always)]
+	pub fn cfi_end_procedure(&mut self)
+	{
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.end_procedure(instruction_pointer)
+	}
+	
+	/// Records a call frame information directive at the current location, ie after the last instruction emitted.
+	///
+	/// Ignored outside of a procedure started with `cfi_start_procedure()`.
+	#[inline(always)]
+	pub fn cfi(&mut self, instruction: CallFrameInstruction)
+	{
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.record(instruction_pointer, instruction)
+	}
+	
 	/// Emits a non-leaf function prologue suitable for both the System V Application Binary Interface for AMD64 and the Microsoft x64 Calling Convention.
+	///
+	/// Afterwards, the Canonical Frame Address (CFA) is `RBP + 16`.
 	#[inline(always)]
 	pub fn push_stack_frame(&mut self)
 	{
+		use self::CallFrameInstruction::DefineCanonicalFrameAddressRegister;
 		use self::Register64Bit::RBP;
 		use self::Register64Bit::RSP;
 		
-		self.push_Register64Bit_r64(RBP);
+		self.push_callee_saved_register(RBP);
 		self.mov_Register64Bit_Register64Bit_rm64_r64(RBP, RSP);
+		self.cfi(DefineCanonicalFrameAddressRegister(RBP));
 	}
 	
 	/// Emits a non-leaf function epilogue (which returns) suitable for both the System V Application Binary Interface for AMD64 and the Microsoft x64 Calling Convention.
+	///
+	/// Call frame information after the epilogue is as it was before it, so code after it (eg for another exit) need not be in a new procedure.
 	#[inline(always)]
 	pub fn pop_stack_frame_and_return(&mut self)
 	{
+		use self::CallFrameInstruction::DefineCanonicalFrameAddressRegister;
+		use self::CallFrameInstruction::RememberState;
+		use self::CallFrameInstruction::RestoreState;
 		use self::Register64Bit::RBP;
 		use self::Register64Bit::RSP;
 		
+		self.cfi(RememberState);
 		self.mov_Register64Bit_Register64Bit_rm64_r64(RSP, RBP);
-		self.pop_Register64Bit_r64(RBP);
+		self.cfi(DefineCanonicalFrameAddressRegister(RSP));
+		self.pop_callee_saved_register(RBP);
 		self.ret();
+		self.cfi(RestoreState);
+	}
+	
+	/// Emits `PUSH register`, recording where `register` has been saved.
+	#[inline(always)]
+	pub fn push_callee_saved_register(&mut self, register: Register64Bit)
+	{
+		self.push_Register64Bit_r64(register);
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.push(instruction_pointer, register)
+	}
+	
+	/// Emits `POP register`, recording that `register` has been restored.
+	#[inline(always)]
+	pub fn pop_callee_saved_register(&mut self, register: Register64Bit)
+	{
+		self.pop_Register64Bit_r64(register);
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.pop(instruction_pointer, register)
+	}
+	
+	/// Emits `SUB RSP, bytes`, using the shortest encoding, and records the change in stack depth.
+	///
+	/// Panics if `bytes` exceeds `i32::MAX`, as the immediate is sign-extended.
+	#[inline(always)]
+	pub fn allocate_stack(&mut self, bytes: u32)
+	{
+		use self::Register64Bit::RSP;
+		
+		assert!(bytes <= i32::MAX as u32, "Stack adjustments can not exceed i32::MAX bytes");
+		
+		if bytes <= i8::MAX as u32
+		{
+			self.sub_Register64Bit_Immediate8Bit(RSP, Immediate8Bit(bytes as i8))
+		}
+		else
+		{
+			self.sub_Register64Bit_Immediate32Bit(RSP, Immediate32Bit(bytes as i32))
+		}
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.adjust_stack(instruction_pointer, bytes as i64)
+	}
+	
+	/// Emits `ADD RSP, bytes`, using the shortest encoding, and records the change in stack depth.
+	///
+	/// Panics if `bytes` exceeds `i32::MAX`, as the immediate is sign-extended.
+	#[inline(always)]
+	pub fn free_stack(&mut self, bytes: u32)
+	{
+		use self::Register64Bit::RSP;
+		
+		assert!(bytes <= i32::MAX as u32, "Stack adjustments can not exceed i32::MAX bytes");
+		
+		if bytes <= i8::MAX as u32
+		{
+			self.add_Register64Bit_Immediate8Bit(RSP, Immediate8Bit(bytes as i8))
+		}
+		else
+		{
+			self.add_Register64Bit_Immediate32Bit(RSP, Immediate32Bit(bytes as i32))
+		}
+		let instruction_pointer = self.instruction_pointer();
+		self.call_frame_information.adjust_stack(instruction_pointer, -(bytes as i64))
 	}
 	
 	/// Zeroes the `RAX` register using the most efficient code (`XOR RAX, RAX`, although could just as easily be `SUB RAX, RAX`).
@@ -259,7 +753,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn nullary_function_pointer<R>(&self) -> unsafe extern "C" fn() -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Creates a function pointer to the current location that takes one argument of type `A` and returns a result of type `R`.
@@ -268,7 +762,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn unary_function_pointer<R, A>(&self) -> unsafe extern "C" fn(A) -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Creates a function pointer to the current location that takes two argument of types `A` and `B` and returns a result of type `R`.
@@ -277,7 +771,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn binary_function_pointer<R, A, B>(&self) -> unsafe extern "C" fn(A, B) -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Creates a function pointer to the current location that takes three argument of types `A`, `B` and `C` and returns a result of type `R`.
@@ -286,7 +780,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn ternary_function_pointer<R, A, B, C>(&self) -> unsafe extern "C" fn(A, B, C) -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Creates a function pointer to the current location that takes four argument of types `A`, `B`, `C` and `D` and returns a result of type `R`.
@@ -295,7 +789,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn quaternary_function_pointer<R, A, B, C, D>(&self) -> unsafe extern "C" fn(A, B, C, D) -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Creates a function pointer to the current location that takes five argument of types `A`, `B`, `C`, `D` and `E` and returns a result of type `R`.
@@ -304,7 +798,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn quinary_function_pointer<R, A, B, C, D, E>(&self) -> unsafe extern "C" fn(A, B, C, D, E) -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Creates a function pointer to the current location that takes six argument of types `A`, `B`, `C`, `D`, `E` and `F` and returns a result of type `R`.
@@ -313,7 +807,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn senary_function_pointer<R, A, B, C, D, E, F>(&self) -> unsafe extern "C" fn(A, B, C, D, E, F) -> R
 	{
-		unsafe { transmute(self.instruction_pointer()) }
+		unsafe { transmute(self.function_pointer_entry_point()) }
 	}
 	
 	/// Emits (pushes) a byte into the instruction stream at the current location.
@@ -699,6 +1193,8 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn emit_alignment(&mut self, alignment: usize)
 	{
+		self.pin_instruction_pointer();
+		
 		let offset = self.instruction_pointer() % alignment;
 		
 		if offset == 0
@@ -721,7 +1217,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	fn nop_2(&mut self)
 	{
-		self.emit_word(0x6690)
+		self.emit_bytes(&[0x66, 0x90])
 	}
 	
 	#[inline(always)]
@@ -733,7 +1229,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	fn nop_4(&mut self)
 	{
-		self.emit_double_word(0x0F1F4000)
+		self.emit_bytes(&[0x0F, 0x1F, 0x40, 0x00])
 	}
 	
 	#[inline(always)]
@@ -757,7 +1253,7 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	fn nop_8(&mut self)
 	{
-		self.emit_quad_word(0x0F1F840000000000)
+		self.emit_bytes(&[0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00])
 	}
 	
 	#[inline(always)]
@@ -766,19 +1262,38 @@ impl<'a> InstructionStream<'a>
 		self.emit_bytes(&[0x66, 0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00])
 	}
 	
+	/// Once an error has been recorded, emission continues into a scratch buffer that is thrown away; when it is full, emission starts again at its beginning.
 	#[inline(always)]
 	fn reserve_space(&mut self, length: usize)
 	{
-		let remaining_space = self.byte_emitter.remaining_space();
-		if unlikely!(remaining_space < length)
+		while unlikely!(self.byte_emitter.remaining_space() < length)
 		{
-			if self.attempt_to_resize_in_place().is_err()
+			if self.first_error.is_some()
 			{
-				panic!("There is no more space in the buffer and remap failed")
+				self.emit_into_scratch(length);
+				return
+			}
+			
+			if let Err(error) = self.attempt_to_resize_in_place()
+			{
+				self.record_error(AssemblerError::OutOfMemory(error, length));
 			}
 		}
 	}
 	
+	#[inline(always)]
+	fn emit_into_scratch(&mut self, length: usize)
+	{
+		// Scratch buffers are never freed before the stream is, as attached labels and bookmarks may still point into them.
+		if self.discarded.last().is_none_or(|scratch| scratch.len() < length)
+		{
+			const MinimumScratchLength: usize = 4096;
+			self.discarded.push(vec![0; length.max(MinimumScratchLength)].into_boxed_slice());
+		}
+		let scratch = self.discarded.last_mut().unwrap();
+		self.byte_emitter.discard(scratch);
+	}
+	
 	#[inline(always)]
 	fn reserve_space_for_instruction(&mut self)
 	{
@@ -786,6 +1301,69 @@ impl<'a> InstructionStream<'a>
 		self.reserve_space(MaximumOpcodeLength)
 	}
 	
+	/// Pins the current location and, if landing pads are being emitted, records it to be checked for an `ENDBR64` by `try_finish()`; returns the current location.
+	#[inline(always)]
+	fn function_pointer_entry_point(&self) -> InstructionPointer
+	{
+		self.pin_instruction_pointer();
+		
+		let entry_point = self.instruction_pointer();
+		if self.landing_pads.is_some()
+		{
+			self.function_pointer_entry_points.borrow_mut().push(entry_point)
+		}
+		entry_point
+	}
+	
+	#[inline(always)]
+	fn check_function_pointer_landing_pads(&self) -> Result<(), AssemblerError>
+	{
+		let end_instruction_pointer = self.instruction_pointer();
+		for &entry_point in self.function_pointer_entry_points.borrow().iter()
+		{
+			if unlikely!(entry_point + 4 > end_instruction_pointer || self.byte_emitter.read_u32_at(entry_point) != Self::ENDBR64)
+			{
+				return Err(AssemblerError::MissingFunctionPointerLandingPad(entry_point - self.byte_emitter.start_instruction_pointer))
+			}
+		}
+		Ok(())
+	}
+	
+	/// Emits `ENDBR64` at `label` when it is attached if it is an indirect branch target and landing pads are being emitted; if it is already attached, records an error unless there is already an `ENDBR64` there.
+	#[inline(always)]
+	fn landing_pad(&mut self, label: Label)
+	{
+		// Once an error has been recorded, labels may be attached in a scratch buffer other than the current one.
+		if self.landing_pads.is_none() || self.first_error.is_some()
+		{
+			return
+		}
+		
+		let target_instruction_pointer = self.target_instruction_pointer(label);
+		if target_instruction_pointer.is_valid()
+		{
+			if unlikely!(target_instruction_pointer + 4 > self.instruction_pointer() || self.byte_emitter.read_u32_at(target_instruction_pointer) != Self::ENDBR64)
+			{
+				self.record_error(AssemblerError::MissingLandingPad(label))
+			}
+		}
+		else if let Some(ref mut landing_pads) = self.landing_pads
+		{
+			landing_pads.insert(label);
+		}
+	}
+	
+	/// Prevents code before the current location from moving if branches are relaxed.
+	#[inline(always)]
+	fn pin_instruction_pointer(&self)
+	{
+		let instruction_pointer = self.instruction_pointer();
+		if instruction_pointer > self.pinned_instruction_pointer.get()
+		{
+			self.pinned_instruction_pointer.set(instruction_pointer)
+		}
+	}
+	
 	#[inline(always)]
 	fn bookmark(&mut self)
 	{
@@ -833,6 +1411,13 @@ impl<'a> InstructionStream<'a>
 		}
 	}
 	
+	// See Figure 2-11, Intel Manual Volume 2A Section 2-37 (May 2018).
+	#[inline(always)]
+	fn evex(&mut self, mm: u8, LL: u8, pp: u8, W: u8, vvvv: impl Register, rm: impl MemoryOrRegister, r: impl Register, masking: Masking)
+	{
+		rm.emit_evex_prefix(&mut self.byte_emitter, mm, LL, pp, W, vvvv, r, masking)
+	}
+	
 	#[inline(always)]
 	fn prefix_fwait(&mut self, byte: u8)
 	{
@@ -912,7 +1497,52 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	fn mod_rm_sib(&mut self, rm: impl MemoryOrRegister, reg: impl Register)
 	{
-		rm.emit_mod_rm_sib(&mut self.byte_emitter, reg)
+		rm.emit_mod_rm_sib(&mut self.byte_emitter, reg);
+		self.record_label_relative_memory_operand(rm.relative_instruction_pointer_label())
+	}
+	
+	/// `N` is the scale of the compressed 8-bit displacement (`disp8*N`) for the instruction's tuple type; see Intel Manual Volume 2A Section 2.6.5 (May 2018).
+	#[inline(always)]
+	fn mod_rm_sib_evex(&mut self, rm: impl MemoryOrRegister, reg: impl Register, N: u8)
+	{
+		rm.emit_mod_rm_sib_compressed_displacement(&mut self.byte_emitter, reg, N);
+		self.record_label_relative_memory_operand(rm.relative_instruction_pointer_label())
+	}
+	
+	/// A `RIP+displacement` memory operand relative to a label has its 32-bit displacement emitted last by `mod_rm_sib()`; it is relative to the end of the instruction, which is only known once any immediates have been emitted.
+	#[inline(always)]
+	fn record_label_relative_memory_operand(&mut self, label: Option<Label>)
+	{
+		if let Some(label) = label
+		{
+			const DisplacementSize: usize = 4;
+			let insert_at_instruction_pointer = self.instruction_pointer() - DisplacementSize;
+			self.pending_label_relative_memory_operand = Some((label, insert_at_instruction_pointer))
+		}
+	}
+	
+	/// Emitted at the end of every instruction with a memory operand; the displacement is fixed up now if the label has already been attached, otherwise by `finish()`.
+	#[inline(always)]
+	fn displacement_label_relative_memory_operand(&mut self)
+	{
+		if let Some((label, insert_at_instruction_pointer)) = self.pending_label_relative_memory_operand.take()
+		{
+			let end_instruction_pointer = self.instruction_pointer();
+			
+			let target_instruction_pointer = self.target_instruction_pointer(label);
+			if target_instruction_pointer.is_valid() && self.branch_relaxation.is_none()
+			{
+				if unlikely!(self.byte_emitter.insert_32_bit_relative_instruction_pointer_displacement(insert_at_instruction_pointer, end_instruction_pointer, target_instruction_pointer).is_err())
+				{
+					let error = self.displacement_overflow(label, insert_at_instruction_pointer, 32);
+					self.record_error(error)
+				}
+			}
+			else
+			{
+				self.label_relative_memory_operands.push((label, insert_at_instruction_pointer, end_instruction_pointer))
+			}
+		}
 	}
 	
 	#[inline(always)]
@@ -942,7 +1572,14 @@ impl<'a> InstructionStream<'a>
 		{
 			match self.byte_emitter.insert_8_bit_effective_address_displacement(insert_at_instruction_pointer, target_instruction_pointer)
 			{
-				Ok(()) => Ok(()),
+				Ok(()) =>
+				{
+					if self.branch_relaxation.is_some()
+					{
+						self.instruction_pointers_to_replace_labels_with_8_bit_displacements.push((label, insert_at_instruction_pointer));
+					}
+					Ok(())
+				}
 				Err(()) =>
 				{
 					self.reset_to_bookmark();
@@ -957,7 +1594,7 @@ impl<'a> InstructionStream<'a>
 		}
 	}
 	
-	/// Does not return an error if displacement would exceed 32 bits, but panics in debug builds.
+	/// Does not return an error if displacement would exceed 32 bits, but records it for `try_finish()`.
 	///
 	/// Errors are very unlikely indeed for such overly large displacements, are almost certainly a mistake and can not realistically be recovered from, in any event.
 	#[inline(always)]
@@ -966,11 +1603,21 @@ impl<'a> InstructionStream<'a>
 		let insert_at_instruction_pointer = self.instruction_pointer();
 		self.skip_double_word();
 		
+		if self.branch_relaxation.is_some()
+		{
+			self.instruction_pointers_to_replace_labels_with_32_bit_displacements.push((label, insert_at_instruction_pointer));
+			return
+		}
+		
 		let target_instruction_pointer = self.target_instruction_pointer(label);
 		
 		if target_instruction_pointer.is_valid()
 		{
-			self.byte_emitter.insert_32_bit_effective_address_displacement(insert_at_instruction_pointer, target_instruction_pointer).expect("32-bit JMP was too far")
+			if unlikely!(self.byte_emitter.insert_32_bit_effective_address_displacement(insert_at_instruction_pointer, target_instruction_pointer).is_err())
+			{
+				let error = self.displacement_overflow(label, insert_at_instruction_pointer, 32);
+				self.record_error(error)
+			}
 		}
 		else
 		{
@@ -978,6 +1625,21 @@ impl<'a> InstructionStream<'a>
 		}
 	}
 	
+	/// As for `displacement_label_32bit()`, but, if branches are relaxed, records a near `JMP` or `Jcc` whose opcode is `opcode_length` bytes long so that it can be shrunk to `short_opcode`.
+	#[inline(always)]
+	fn near_jump_displacement_label_32bit(&mut self, label: Label, opcode_length: usize, short_opcode: u8)
+	{
+		let insert_at_instruction_pointer = self.instruction_pointer();
+		if let Some(ref mut branch_relaxation) = self.branch_relaxation
+		{
+			branch_relaxation.record_near_jump(label, insert_at_instruction_pointer - opcode_length, insert_at_instruction_pointer, short_opcode);
+			self.skip_double_word();
+			return
+		}
+		
+		self.displacement_label_32bit(label)
+	}
+	
 	/// Calculates a relative address from an absolute address, such as a function pointer or static constant pointer.
 	///
 	/// **WARNING**: Be very careful using the resultant value for CALL, JMP, etc, as it would need correcting for the size of the emitted opcode sequence including displacement.
@@ -1011,6 +1673,8 @@ impl<'a> InstructionStream<'a>
 	{
 		const ArtificallyLargeDisplacementPlaceholder: Immediate32Bit = Immediate32Bit::Maximum;
 		
+		self.pin_instruction_pointer();
+		
 		self.bookmark();
 		
 		// Firstly, try to use absolute addressing within the first 2Gb.
@@ -1059,12 +1723,14 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn statically_relative_address<BM: BitMemory>(&self, array_location_in_memory: InstructionPointer, index_register: Register64Bit, scale: IndexScale, base_register_holding_start_of_instructions_pointer: Register64Bit) -> BM
 	{
+		self.pin_instruction_pointer();
+		
 		BM::statically_relative_address(self, array_location_in_memory, index_register, scale, base_register_holding_start_of_instructions_pointer)
 	}
 	
 	/// Use as follows with `Memory::relative_instruction_pointer_relative()`:-
 	///
-	/// ```
+	/// ```ignore
 	/// instruction_stream.vmovdqa_YMM_Any256BitMemory(ymm_register, Any256BitMemory::relative_instruction_pointer_relative());
 	/// instruction_stream.overwrite_last_displacement_with_relative_address_to(absolute_address);
 	/// ```
@@ -1073,6 +1739,8 @@ impl<'a> InstructionStream<'a>
 	#[inline(always)]
 	pub fn overwrite_last_32bit_displacement_with_relative_address_to(&mut self, location_in_memory: InstructionPointer)
 	{
+		self.pin_instruction_pointer();
+		
 		debug_assert!(location_in_memory <= ::std::isize::MAX as usize, "location_in_memory is larger than ::std::isize::MAX");
 		
 		let instruction_pointer = self.instruction_pointer();
@@ -1097,10 +1765,14 @@ impl<'a> InstructionStream<'a>
 		let scale = scale.into();
 		let desired_block_size = 1 << (scale as usize);
 		
+		self.pin_instruction_pointer();
+		
 		let start = self.instruction_pointer();
 		
 		let result = emit_instructions(self);
 		
+		self.pin_instruction_pointer();
+		
 		let block_size = self.instruction_pointer() - start;
 		debug_assert!(block_size <= desired_block_size, "block_size '{}' exceeds desired_block_size '{}' (scale '{})", block_size, desired_block_size, scale);
 		self.skip_bytes(desired_block_size - block_size);
@@ -1110,3 +1782,5 @@ impl<'a> InstructionStream<'a>
 }
 
 include!("InstructionStream.instructions.rs");
+include!("InstructionStream.shortest_encodings.rs");
+include!("InstructionStream.structured_control_flow.rs");
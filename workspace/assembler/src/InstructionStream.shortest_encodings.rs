@@ -0,0 +1,361 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Instructions with an immediate operand, emitted using the shortest encoding for the operands' values.
+///
+/// Each method emits the same instruction as its naive counterpart (the method without the `_shortest` suffix), but chooses, at run time, between:-
+///
+/// * the form taking a sign-extended 8-bit immediate, if the value fits;
+/// * the form with `RAX`, `EAX`, `AX` or `AL` as its implied first operand, if the register is the accumulator;
+/// * the general ModR/M form.
+impl<'a> InstructionStream<'a>
+{
+	/// Emits `ADC Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn adc_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::adc_RAX_Immediate32Bit, Some(Self::adc_Register64Bit_Immediate8Bit), Self::adc_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `ADC Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn adc_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::adc_EAX_Immediate32Bit, Some(Self::adc_Register32Bit_Immediate8Bit), Self::adc_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `ADC Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn adc_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::adc_AX_Immediate16Bit, Some(Self::adc_Register16Bit_Immediate8Bit), Self::adc_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `ADC Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn adc_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::adc_AL_Immediate8Bit, Self::adc_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `ADD Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn add_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::add_RAX_Immediate32Bit, Some(Self::add_Register64Bit_Immediate8Bit), Self::add_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `ADD Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn add_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::add_EAX_Immediate32Bit, Some(Self::add_Register32Bit_Immediate8Bit), Self::add_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `ADD Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn add_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::add_AX_Immediate16Bit, Some(Self::add_Register16Bit_Immediate8Bit), Self::add_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `ADD Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn add_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::add_AL_Immediate8Bit, Self::add_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `AND Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn and_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::and_RAX_Immediate32Bit, Some(Self::and_Register64Bit_Immediate8Bit), Self::and_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `AND Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn and_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::and_EAX_Immediate32Bit, Some(Self::and_Register32Bit_Immediate8Bit), Self::and_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `AND Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn and_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::and_AX_Immediate16Bit, Some(Self::and_Register16Bit_Immediate8Bit), Self::and_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `AND Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn and_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::and_AL_Immediate8Bit, Self::and_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `CMP Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn cmp_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::cmp_RAX_Immediate32Bit, Some(Self::cmp_Register64Bit_Immediate8Bit), Self::cmp_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `CMP Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn cmp_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::cmp_EAX_Immediate32Bit, Some(Self::cmp_Register32Bit_Immediate8Bit), Self::cmp_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `CMP Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn cmp_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::cmp_AX_Immediate16Bit, Some(Self::cmp_Register16Bit_Immediate8Bit), Self::cmp_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `CMP Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn cmp_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::cmp_AL_Immediate8Bit, Self::cmp_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `OR Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn or_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::or_RAX_Immediate32Bit, Some(Self::or_Register64Bit_Immediate8Bit), Self::or_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `OR Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn or_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::or_EAX_Immediate32Bit, Some(Self::or_Register32Bit_Immediate8Bit), Self::or_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `OR Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn or_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::or_AX_Immediate16Bit, Some(Self::or_Register16Bit_Immediate8Bit), Self::or_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `OR Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn or_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::or_AL_Immediate8Bit, Self::or_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `SBB Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sbb_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::sbb_RAX_Immediate32Bit, Some(Self::sbb_Register64Bit_Immediate8Bit), Self::sbb_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `SBB Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sbb_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::sbb_EAX_Immediate32Bit, Some(Self::sbb_Register32Bit_Immediate8Bit), Self::sbb_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `SBB Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sbb_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::sbb_AX_Immediate16Bit, Some(Self::sbb_Register16Bit_Immediate8Bit), Self::sbb_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `SBB Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sbb_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::sbb_AL_Immediate8Bit, Self::sbb_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `SUB Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sub_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::sub_RAX_Immediate32Bit, Some(Self::sub_Register64Bit_Immediate8Bit), Self::sub_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `SUB Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sub_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::sub_EAX_Immediate32Bit, Some(Self::sub_Register32Bit_Immediate8Bit), Self::sub_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `SUB Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sub_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::sub_AX_Immediate16Bit, Some(Self::sub_Register16Bit_Immediate8Bit), Self::sub_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `SUB Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn sub_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::sub_AL_Immediate8Bit, Self::sub_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `TEST Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn test_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::test_RAX_Immediate32Bit, None, Self::test_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `TEST Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn test_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::test_EAX_Immediate32Bit, None, Self::test_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `TEST Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn test_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::test_AX_Immediate16Bit, None, Self::test_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `TEST Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn test_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::test_AL_Immediate8Bit, Self::test_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits `XOR Register64Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn xor_Register64Bit_Immediate32Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register64Bit_Immediate32Bit(register, immediate, Self::xor_RAX_Immediate32Bit, Some(Self::xor_Register64Bit_Immediate8Bit), Self::xor_Register64Bit_Immediate32Bit)
+	}
+	
+	/// Emits `XOR Register32Bit, Immediate32Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn xor_Register32Bit_Immediate32Bit_shortest(&mut self, register: Register32Bit, immediate: Immediate32Bit)
+	{
+		self.shortest_Register32Bit_Immediate32Bit(register, immediate, Self::xor_EAX_Immediate32Bit, Some(Self::xor_Register32Bit_Immediate8Bit), Self::xor_Register32Bit_Immediate32Bit)
+	}
+	
+	/// Emits `XOR Register16Bit, Immediate16Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn xor_Register16Bit_Immediate16Bit_shortest(&mut self, register: Register16Bit, immediate: Immediate16Bit)
+	{
+		self.shortest_Register16Bit_Immediate16Bit(register, immediate, Self::xor_AX_Immediate16Bit, Some(Self::xor_Register16Bit_Immediate8Bit), Self::xor_Register16Bit_Immediate16Bit)
+	}
+	
+	/// Emits `XOR Register8Bit, Immediate8Bit` using the shortest encoding.
+	#[inline(always)]
+	pub fn xor_Register8Bit_Immediate8Bit_shortest(&mut self, register: Register8Bit, immediate: Immediate8Bit)
+	{
+		self.shortest_Register8Bit_Immediate8Bit(register, immediate, Self::xor_AL_Immediate8Bit, Self::xor_Register8Bit_Immediate8Bit)
+	}
+	
+	/// Emits the equivalent of `MOV register, immediate` (`MOVABS`) using the shortest encoding:-
+	///
+	/// * `XOR r32, r32` for zero, if `preserve_flags` is `false`, as, unlike `MOV`, `XOR` changes the flags;
+	/// * `MOV r32, imm32`, which zero-extends, for values which fit in an `u32`;
+	/// * `MOV r64, imm32`, which sign-extends, for values which fit in an `i32`;
+	/// * `MOV r64, imm64` otherwise.
+	#[inline(always)]
+	pub fn mov_Register64Bit_Immediate64Bit_shortest(&mut self, register: Register64Bit, immediate: Immediate64Bit, preserve_flags: bool)
+	{
+		let value = immediate.0;
+		let register_32: Register32Bit = register.into();
+		if value == 0 && !preserve_flags
+		{
+			self.xor_Register32Bit_Register32Bit(register_32, register_32)
+		}
+		else if value as u32 as i64 == value
+		{
+			self.mov_Register32Bit_Immediate32Bit(register_32, Immediate32Bit(value as u32 as i32))
+		}
+		else if value as i32 as i64 == value
+		{
+			self.mov_Register64Bit_Immediate32Bit(register, Immediate32Bit(value as i32))
+		}
+		else
+		{
+			self.mov_Register64Bit_Immediate64Bit(register, immediate)
+		}
+	}
+	
+	#[inline(always)]
+	fn shortest_Register64Bit_Immediate32Bit(&mut self, register: Register64Bit, immediate: Immediate32Bit, accumulator: fn(&mut Self, Immediate32Bit), sign_extended_8_bit: Option<fn(&mut Self, Register64Bit, Immediate8Bit)>, general: fn(&mut Self, Register64Bit, Immediate32Bit))
+	{
+		let value = immediate.0;
+		match sign_extended_8_bit
+		{
+			Some(sign_extended_8_bit) if value as i8 as i32 == value => sign_extended_8_bit(self, register, Immediate8Bit(value as i8)),
+			_ => if register == Register64Bit::RAX
+			{
+				accumulator(self, immediate)
+			}
+			else
+			{
+				general(self, register, immediate)
+			},
+		}
+	}
+	
+	#[inline(always)]
+	fn shortest_Register32Bit_Immediate32Bit(&mut self, register: Register32Bit, immediate: Immediate32Bit, accumulator: fn(&mut Self, Immediate32Bit), sign_extended_8_bit: Option<fn(&mut Self, Register32Bit, Immediate8Bit)>, general: fn(&mut Self, Register32Bit, Immediate32Bit))
+	{
+		let value = immediate.0;
+		match sign_extended_8_bit
+		{
+			Some(sign_extended_8_bit) if value as i8 as i32 == value => sign_extended_8_bit(self, register, Immediate8Bit(value as i8)),
+			_ => if register == Register32Bit::EAX
+			{
+				accumulator(self, immediate)
+			}
+			else
+			{
+				general(self, register, immediate)
+			},
+		}
+	}
+	
+	#[inline(always)]
+	fn shortest_Register16Bit_Immediate16Bit(&mut self, register: Register16Bit, immediate: Immediate16Bit, accumulator: fn(&mut Self, Immediate16Bit), sign_extended_8_bit: Option<fn(&mut Self, Register16Bit, Immediate8Bit)>, general: fn(&mut Self, Register16Bit, Immediate16Bit))
+	{
+		let value = immediate.0;
+		match sign_extended_8_bit
+		{
+			Some(sign_extended_8_bit) if value as i8 as i16 == value => sign_extended_8_bit(self, register, Immediate8Bit(value as i8)),
+			_ => if register == Register16Bit::AX
+			{
+				accumulator(self, immediate)
+			}
+			else
+			{
+				general(self, register, immediate)
+			},
+		}
+	}
+	
+	#[inline(always)]
+	fn shortest_Register8Bit_Immediate8Bit(&mut self, register: Register8Bit, immediate: Immediate8Bit, accumulator: fn(&mut Self, Immediate8Bit), general: fn(&mut Self, Register8Bit, Immediate8Bit))
+	{
+		if register == Register8Bit::AL
+		{
+			accumulator(self, immediate)
+		}
+		else
+		{
+			general(self, register, immediate)
+		}
+	}
+}
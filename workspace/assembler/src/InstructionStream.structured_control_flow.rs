@@ -0,0 +1,205 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Structured control flow (`if`, `while`, `switch` and the like) built from closures that emit code, with the labels created and attached automatically.
+///
+/// Conditions are closures that emit code setting the flags (eg a `CMP`) and return the `ConditionCode` that holds when the condition is true.
+///
+/// All jumps are near jumps, so are shrunk if `relax_jumps_on_finish()` is used.
+impl<'a> InstructionStream<'a>
+{
+	/// Emits `then` if `condition` holds.
+	#[inline(always)]
+	pub fn if_(&mut self, condition: impl FnOnce(&mut Self) -> ConditionCode, then: impl FnOnce(&mut Self))
+	{
+		let end = self.create_label();
+		
+		let condition_code = condition(self);
+		self.jcc(condition_code.invert(), end);
+		then(self);
+		
+		self.attach_label(end);
+	}
+	
+	/// Emits `then` if `condition` holds, otherwise `otherwise`.
+	#[inline(always)]
+	pub fn if_else(&mut self, condition: impl FnOnce(&mut Self) -> ConditionCode, then: impl FnOnce(&mut Self), otherwise: impl FnOnce(&mut Self))
+	{
+		let else_ = self.create_label();
+		let end = self.create_label();
+		
+		let condition_code = condition(self);
+		self.jcc(condition_code.invert(), else_);
+		then(self);
+		self.jmp_Label_1(end);
+		
+		self.attach_label(else_);
+		otherwise(self);
+		
+		self.attach_label(end);
+	}
+	
+	/// Emits `body` to be repeated whilst `condition` holds, testing `condition` before each iteration.
+	///
+	/// The condition is emitted after the body, so each iteration takes only one (conditional) jump.
+	#[inline(always)]
+	pub fn while_(&mut self, condition: impl FnOnce(&mut Self) -> ConditionCode, body: impl FnOnce(&mut Self, LoopLabels))
+	{
+		let loop_labels = self.loop_labels();
+		let top = self.create_label();
+		
+		self.jmp_Label_1(loop_labels.continue_label);
+		
+		self.attach_label(top);
+		body(self, loop_labels);
+		
+		self.attach_label(loop_labels.continue_label);
+		let condition_code = condition(self);
+		self.jcc(condition_code, top);
+		
+		self.attach_label(loop_labels.break_label);
+	}
+	
+	/// Emits `body` to be repeated whilst `condition` holds, testing `condition` after each iteration (so `body` runs at least once).
+	#[inline(always)]
+	pub fn do_while(&mut self, body: impl FnOnce(&mut Self, LoopLabels), condition: impl FnOnce(&mut Self) -> ConditionCode)
+	{
+		let loop_labels = self.loop_labels();
+		let top = self.create_and_attach_label();
+		
+		body(self, loop_labels);
+		
+		self.attach_label(loop_labels.continue_label);
+		let condition_code = condition(self);
+		self.jcc(condition_code, top);
+		
+		self.attach_label(loop_labels.break_label);
+	}
+	
+	/// Emits `body` to be repeated the (unsigned) number of times in `counter`, which is decremented after each iteration; `body` is not run if `counter` is zero.
+	///
+	/// `body` must not change `counter`.
+	#[inline(always)]
+	pub fn loop_n(&mut self, counter: Register64Bit, body: impl FnOnce(&mut Self, LoopLabels))
+	{
+		let loop_labels = self.loop_labels();
+		
+		self.test_Register64Bit_Register64Bit(counter, counter);
+		self.jcc(ConditionCode::Equal, loop_labels.break_label);
+		
+		let top = self.create_and_attach_label();
+		body(self, loop_labels);
+		
+		self.attach_label(loop_labels.continue_label);
+		self.sub_Register64Bit_Immediate32Bit_shortest(counter, Immediate32Bit(1));
+		self.jcc(ConditionCode::NotEqual, top);
+		
+		self.attach_label(loop_labels.break_label);
+	}
+	
+	/// Emits a `switch` on the signed value in `index`, running `case(self, position)` for the value at `position` in `values` and `default` for any other value; there is no fall through from one case to the next.
+	///
+	/// Dense values (at least 4, with no more than 3 entries in the range of values for each one) are dispatched using a jump table (see `jmp_relative_jump_table()`), otherwise using a binary tree of comparisons.
+	///
+	/// Both `index` and `scratch` are overwritten; `scratch` is only used for a jump table.
+	///
+	/// Panics if `values` contains duplicates.
+	#[inline(always)]
+	pub fn switch(&mut self, index: Register64Bit, scratch: Register64Bit, values: &[i32], mut case: impl FnMut(&mut Self, usize), default: impl FnOnce(&mut Self))
+	{
+		let default_label = self.create_label();
+		let end = self.create_label();
+		
+		let case_labels: Vec<Label> = values.iter().map(|_| self.create_label()).collect();
+		
+		let mut sorted_cases: Vec<(i32, Label)> = values.iter().cloned().zip(case_labels.iter().cloned()).collect();
+		sorted_cases.sort_by_key(|&(value, _)| value);
+		assert!(sorted_cases.windows(2).all(|pair| pair[0].0 != pair[1].0), "values contains duplicates");
+		
+		const MinimumNumberOfCasesForJumpTable: usize = 4;
+		const MaximumEntriesPerCase: i64 = 3;
+		let is_dense = match (sorted_cases.first(), sorted_cases.last())
+		{
+			(Some(&(minimum, _)), Some(&(maximum, _))) => sorted_cases.len() >= MinimumNumberOfCasesForJumpTable && (maximum as i64) - (minimum as i64) < (sorted_cases.len() as i64) * MaximumEntriesPerCase,
+			_ => false,
+		};
+		
+		if is_dense
+		{
+			let minimum = sorted_cases[0].0;
+			let maximum = sorted_cases[sorted_cases.len() - 1].0;
+			
+			let mut table = vec![default_label; ((maximum as i64) - (minimum as i64) + 1) as usize];
+			for &(value, label) in sorted_cases.iter()
+			{
+				table[((value as i64) - (minimum as i64)) as usize] = label
+			}
+			
+			if minimum != 0
+			{
+				self.sub_Register64Bit_Immediate32Bit_shortest(index, Immediate32Bit(minimum));
+			}
+			self.jmp_relative_jump_table(index, scratch, &table, Some(default_label));
+		}
+		else
+		{
+			self.compare_tree(index, &sorted_cases, default_label, true);
+		}
+		
+		self.attach_label(default_label);
+		default(self);
+		
+		for (position, &case_label) in case_labels.iter().enumerate()
+		{
+			self.jmp_Label_1(end);
+			self.attach_label(case_label);
+			case(self, position);
+		}
+		
+		self.attach_label(end);
+	}
+	
+	/// If `default_follows`, the code emitted last is immediately followed by `default_label`, so no jump to it is needed there.
+	#[inline(always)]
+	fn compare_tree(&mut self, index: Register64Bit, sorted_cases: &[(i32, Label)], default_label: Label, default_follows: bool)
+	{
+		const MaximumNumberOfCasesToCompareInTurn: usize = 3;
+		
+		if sorted_cases.len() <= MaximumNumberOfCasesToCompareInTurn
+		{
+			for &(value, label) in sorted_cases.iter()
+			{
+				self.cmp_Register64Bit_Immediate32Bit_shortest(index, Immediate32Bit(value));
+				self.jcc(ConditionCode::Equal, label);
+			}
+			if !default_follows
+			{
+				self.jmp_Label_1(default_label);
+			}
+			return
+		}
+		
+		let middle = sorted_cases.len() / 2;
+		let (value, label) = sorted_cases[middle];
+		let greater = self.create_label();
+		
+		self.cmp_Register64Bit_Immediate32Bit_shortest(index, Immediate32Bit(value));
+		self.jcc(ConditionCode::Equal, label);
+		self.jcc(ConditionCode::Greater, greater);
+		self.compare_tree(index, &sorted_cases[ .. middle], default_label, false);
+		
+		self.attach_label(greater);
+		self.compare_tree(index, &sorted_cases[middle + 1 .. ], default_label, default_follows);
+	}
+	
+	#[inline(always)]
+	fn loop_labels(&mut self) -> LoopLabels
+	{
+		LoopLabels
+		{
+			break_label: self.create_label(),
+			continue_label: self.create_label(),
+		}
+	}
+}
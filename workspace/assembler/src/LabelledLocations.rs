@@ -60,13 +60,18 @@ impl LabelledLocations
 		Label(label_index)
 	}
 	
+	/// Returns `false`, leaving the existing location in place, if `label` has already been used to label.
 	#[inline(always)]
-	pub(crate) fn set(&mut self, label: Label, instruction_pointer: InstructionPointer)
+	pub(crate) fn set(&mut self, label: Label, instruction_pointer: InstructionPointer) -> bool
 	{
 		let instruction_pointer_pointer = self.pointer_at_index(label.0);
 		
-		debug_assert_eq!(unsafe { *instruction_pointer_pointer }, Self::UnlabelledSentinel, "Label '{:?}' has already been used to label", label);
+		if unlikely!(unsafe { *instruction_pointer_pointer }.is_valid())
+		{
+			return false
+		}
 		unsafe { *instruction_pointer_pointer = instruction_pointer };
+		true
 	}
 	
 	#[inline(always)]
@@ -75,6 +80,41 @@ impl LabelledLocations
 		unsafe { *self.pointer_at_index(label.0) }
 	}
 	
+	/// `None` if `label` is unattached or was not created by this instance.
+	#[inline(always)]
+	pub(crate) fn attached_instruction_pointer(&self, label: Label) -> Option<InstructionPointer>
+	{
+		if unlikely!(label.0 >= self.next_label_index)
+		{
+			return None
+		}
+		
+		let instruction_pointer = self.potential_target_instruction_pointer(label);
+		if instruction_pointer.is_valid()
+		{
+			Some(instruction_pointer)
+		}
+		else
+		{
+			None
+		}
+	}
+	
+	/// Moves all attached labels using `relocate`.
+	#[inline(always)]
+	pub(crate) fn relocate(&mut self, relocate: impl Fn(InstructionPointer) -> InstructionPointer)
+	{
+		for index in 0 .. self.next_label_index
+		{
+			let instruction_pointer_pointer = self.pointer_at_index(index);
+			let instruction_pointer = unsafe { *instruction_pointer_pointer };
+			if instruction_pointer.is_valid()
+			{
+				unsafe { *instruction_pointer_pointer = relocate(instruction_pointer) }
+			}
+		}
+	}
+	
 	#[inline(always)]
 	fn resize(&mut self)
 	{
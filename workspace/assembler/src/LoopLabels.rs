@@ -0,0 +1,14 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// The labels of a loop emitted by `InstructionStream.while_()`, `InstructionStream.do_while()` or `InstructionStream.loop_n()`, for jumping out of it or on to its next iteration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LoopLabels
+{
+	/// Jump to this label to leave the loop (`break`).
+	pub break_label: Label,
+	
+	/// Jump to this label to test the loop's condition and start the next iteration if it holds (`continue`).
+	pub continue_label: Label,
+}
@@ -0,0 +1,34 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Errors from decoding.
+///
+/// Instruction pointers are those of the start of the instruction that could not be decoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DecodeError
+{
+	/// The bytes ended part way through an instruction.
+	UnexpectedEndOfCode(InstructionPointer),
+
+	/// The bytes are not an instruction that `InstructionStream` can emit.
+	UnrecognisedInstruction(InstructionPointer),
+}
+
+impl Display for DecodeError
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		use self::DecodeError::*;
+
+		match *self
+		{
+			UnexpectedEndOfCode(instruction_pointer) => write!(f, "code ends part way through the instruction at 0x{:x}", instruction_pointer),
+			UnrecognisedInstruction(instruction_pointer) => write!(f, "unrecognised instruction at 0x{:x}", instruction_pointer),
+		}
+	}
+}
+
+impl Error for DecodeError
+{
+}
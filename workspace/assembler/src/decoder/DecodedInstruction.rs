@@ -0,0 +1,74 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A decoded instruction.
+///
+/// Displays in Intel syntax, eg `vaddps zmm1{k1}{z}, zmm2, zmmword ptr [rax+0x40]` or `jne 0x7f0000001000,pt`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DecodedInstruction
+{
+	/// Location of the first byte of this instruction.
+	pub instruction_pointer: InstructionPointer,
+
+	/// Length in bytes, including all prefixes.
+	pub length: usize,
+
+	/// Lower case Intel mnemonic, including any `rep`, `repe` or `repne` prefix, eg `rep movsb`.
+	pub mnemonic: &'static str,
+
+	/// In Intel order, with the destination first.
+	pub operands: Vec<DecodedOperand>,
+
+	/// Masking of the destination of an `EVEX` encoded (AVX-512) instruction; `Masking::Unmasked` for all other instructions.
+	pub masking: Masking,
+
+	/// Branch hint of a conditional jump, if any.
+	pub branch_hint: Option<BranchHint>,
+}
+
+impl Display for DecodedInstruction
+{
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		f.write_str(self.mnemonic)?;
+
+		let mut first = true;
+		for operand in self.operands.iter()
+		{
+			f.write_str(if first { " " } else { ", " })?;
+			write!(f, "{}", operand)?;
+
+			if first
+			{
+				let opmask_register = self.masking.opmask_register();
+				if opmask_register != OpmaskRegister::K0
+				{
+					write!(f, "{{{}}}", DecodedRegister::OpmaskRegister(opmask_register))?;
+				}
+				if self.masking.is_zeroing()
+				{
+					f.write_str("{z}")?;
+				}
+				first = false;
+			}
+		}
+
+		match self.branch_hint
+		{
+			None => Ok(()),
+			Some(BranchHint::Taken) => f.write_str(",pt"),
+			Some(BranchHint::NotTaken) => f.write_str(",pn"),
+		}
+	}
+}
+
+impl DecodedInstruction
+{
+	/// Location of the first byte after this instruction.
+	#[inline(always)]
+	pub fn next_instruction_pointer(&self) -> InstructionPointer
+	{
+		self.instruction_pointer + self.length
+	}
+}
@@ -0,0 +1,217 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A decoded memory operand.
+///
+/// Displays in Intel syntax, eg `qword ptr fs:[rax+rcx*8+0x10]`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DecodedMemoryOperand
+{
+	/// Size of the memory referenced.
+	pub size: MemoryOperandSize,
+
+	/// Segment override, if any.
+	pub segment_register: Option<SegmentRegister>,
+
+	/// A 64-bit (or, with `address_override_for_32_bit`, 32-bit) general purpose register.
+	///
+	/// Always `None` if `relative_instruction_pointer_offset`.
+	pub base_register: Option<DecodedRegister>,
+
+	/// A general purpose register of the same size as `base_register`, or, for vector scaled index byte (VSIB) operands, an `XMM` or `YMM` register.
+	pub index_register: Option<(DecodedRegister, IndexScale)>,
+
+	/// Any compressed 8-bit displacement (`disp8*N`) is already scaled.
+	pub displacement: i32,
+
+	/// Addressing uses 32-bit registers.
+	pub address_override_for_32_bit: bool,
+
+	/// Addressing is relative to the end of the instruction (`RIP+disp32` or `EIP+disp32`).
+	pub relative_instruction_pointer_offset: bool,
+}
+
+impl Display for DecodedMemoryOperand
+{
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		write!(f, "{}", self.size)?;
+
+		if let Some(segment_register) = self.segment_register
+		{
+			write!(f, "{}:", DecodedRegister::SegmentRegister(segment_register))?;
+		}
+
+		f.write_char('[')?;
+
+		let mut has_register = true;
+		if self.relative_instruction_pointer_offset
+		{
+			f.write_str(if self.address_override_for_32_bit { "eip" } else { "rip" })?;
+		}
+		else if let Some(base_register) = self.base_register
+		{
+			write!(f, "{}", base_register)?;
+		}
+		else
+		{
+			has_register = false;
+		}
+
+		if let Some((index_register, scale)) = self.index_register
+		{
+			if has_register
+			{
+				f.write_char('+')?;
+			}
+			let scale: u8 = scale.into();
+			write!(f, "{}*{}", index_register, 1 << scale)?;
+			has_register = true;
+		}
+
+		let displacement = self.displacement;
+		if !has_register
+		{
+			write!(f, "0x{:x}", displacement as u32)?;
+		}
+		else if displacement < 0
+		{
+			write!(f, "-0x{:x}", (displacement as i64).abs())?;
+		}
+		else if displacement > 0
+		{
+			write!(f, "+0x{:x}", displacement)?;
+		}
+
+		f.write_char(']')
+	}
+}
+
+impl DecodedMemoryOperand
+{
+	/// The implicit memory operand of a string instruction or `xlat`.
+	#[inline(always)]
+	pub(crate) fn string_operand(base_register: Register64Bit, segment_register: Option<SegmentRegister>, size: MemoryOperandSize, address_override_for_32_bit: bool) -> Self
+	{
+		let base_register = if address_override_for_32_bit
+		{
+			DecodedRegister::Register32Bit(unsafe { transmute(base_register) })
+		}
+		else
+		{
+			DecodedRegister::Register64Bit(base_register)
+		};
+
+		Self
+		{
+			size,
+			segment_register,
+			base_register: Some(base_register),
+			index_register: None,
+			displacement: 0,
+			address_override_for_32_bit,
+			relative_instruction_pointer_offset: false,
+		}
+	}
+
+	/// Decodes any scaled index byte (SIB) and displacement following the ModR/M byte `mod_rm`, whose `mod` field is not `0b11`.
+	///
+	/// Returns `Ok(None)` if `kind` is a vector scaled index byte (VSIB) memory operand but there is no scaled index byte.
+	pub(crate) fn decode(bytes: &mut InstructionBytes, prefixes: &InstructionPrefixes, mod_rm: u8, kind: OperandKind, N: u8, segment_register: Option<SegmentRegister>, address_override_for_32_bit: bool) -> Result<Option<Self>, DecodeError>
+	{
+		const RegisterRspOrR12: u8 = 0b100;
+		const RegisterRbpOrR13: u8 = 0b101;
+
+		let general_purpose_register = |index: u8|
+		{
+			if address_override_for_32_bit
+			{
+				DecodedRegister::Register32Bit(unsafe { transmute(index) })
+			}
+			else
+			{
+				DecodedRegister::Register64Bit(unsafe { transmute(index) })
+			}
+		};
+
+		let mod_ = mod_rm >> 6;
+		let rm = mod_rm & 0x07;
+
+		let mut memory_operand = Self
+		{
+			size: kind.memory_operand_size(),
+			segment_register,
+			base_register: None,
+			index_register: None,
+			displacement: 0,
+			address_override_for_32_bit,
+			relative_instruction_pointer_offset: false,
+		};
+
+		let vector_sib_index = match kind
+		{
+			OperandKind::VectorSibMemory(vector_sib_index, _) => Some(vector_sib_index),
+			_ => None,
+		};
+
+		let has_base_register = if rm == RegisterRspOrR12
+		{
+			let sib = bytes.read_u8()?;
+
+			let scale = unsafe { transmute((sib >> 6) as u64) };
+			let index = prefixes.index_index(sib);
+			memory_operand.index_register = match vector_sib_index
+			{
+				Some(VectorSibIndex::XMMRegister) => Some((DecodedRegister::XMMRegister(unsafe { transmute(index) }), scale)),
+				Some(VectorSibIndex::YMMRegister) => Some((DecodedRegister::YMMRegister(unsafe { transmute(index) }), scale)),
+				None => if index == RegisterRspOrR12
+				{
+					None
+				}
+				else
+				{
+					Some((general_purpose_register(index), scale))
+				},
+			};
+
+			let has_base_register = !(mod_ == 0b00 && sib & 0x07 == RegisterRbpOrR13);
+			if has_base_register
+			{
+				memory_operand.base_register = Some(general_purpose_register(prefixes.base_index(sib)));
+			}
+			has_base_register
+		}
+		else if vector_sib_index.is_some()
+		{
+			return Ok(None)
+		}
+		else if mod_ == 0b00 && rm == RegisterRbpOrR13
+		{
+			memory_operand.relative_instruction_pointer_offset = true;
+			false
+		}
+		else
+		{
+			memory_operand.base_register = Some(general_purpose_register(prefixes.base_index(rm)));
+			true
+		};
+
+		memory_operand.displacement = match mod_
+		{
+			0b00 => if has_base_register
+			{
+				0
+			}
+			else
+			{
+				bytes.read_u32()? as i32
+			},
+			0b01 => (bytes.read_u8()? as i8 as i32) * (N as i32),
+			_ => bytes.read_u32()? as i32,
+		};
+
+		Ok(Some(memory_operand))
+	}
+}
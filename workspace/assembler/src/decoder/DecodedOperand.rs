@@ -0,0 +1,74 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A decoded operand.
+///
+/// Immediates display as hexadecimal of their encoded width, so `Immediate8Bit(-1)` displays as `0xff`; only sign extended 8-bit immediates display as signed values, so `SignExtendedImmediate8Bit(-1)` displays as `-0x1`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DecodedOperand
+{
+	/// A register, including implicit registers such as the `AL` of `add al, imm8`.
+	Register(DecodedRegister),
+
+	/// A memory operand, including the implicit memory operands of string instructions.
+	Memory(DecodedMemoryOperand),
+
+	/// An 8-bit immediate, including the implicit `1` of shifts and rotates such as `shl al, 1`.
+	Immediate8Bit(Immediate8Bit),
+
+	/// An 8-bit immediate which is sign extended to the operand size, such as that of `add rax, -0x2`.
+	SignExtendedImmediate8Bit(Immediate8Bit),
+
+	/// A 16-bit immediate.
+	Immediate16Bit(Immediate16Bit),
+
+	/// A 32-bit immediate.
+	Immediate32Bit(Immediate32Bit),
+
+	/// A 64-bit immediate.
+	Immediate64Bit(Immediate64Bit),
+
+	/// The absolute target of a relative jump or call (an 8-bit or 32-bit displacement from the end of the instruction).
+	BranchTarget(InstructionPointer),
+
+	/// The absolute memory offset (`moffs`) of a `mov` to or from `AL`, `AX`, `EAX` or `RAX`.
+	MemoryOffset(MemoryOperandSize, Option<SegmentRegister>, u64),
+}
+
+impl Display for DecodedOperand
+{
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		use self::DecodedOperand::*;
+
+		match *self
+		{
+			Register(register) => write!(f, "{}", register),
+			Memory(memory_operand) => write!(f, "{}", memory_operand),
+			Immediate8Bit(immediate) => write!(f, "0x{:x}", immediate.0 as u8),
+			SignExtendedImmediate8Bit(immediate) => if immediate.0 < 0
+			{
+				write!(f, "-0x{:x}", -(immediate.0 as i16))
+			}
+			else
+			{
+				write!(f, "0x{:x}", immediate.0)
+			},
+			Immediate16Bit(immediate) => write!(f, "0x{:x}", immediate.0 as u16),
+			Immediate32Bit(immediate) => write!(f, "0x{:x}", immediate.0 as u32),
+			Immediate64Bit(immediate) => write!(f, "0x{:x}", immediate.0 as u64),
+			BranchTarget(instruction_pointer) => write!(f, "0x{:x}", instruction_pointer),
+			MemoryOffset(size, segment_register, offset) =>
+			{
+				write!(f, "{}", size)?;
+				if let Some(segment_register) = segment_register
+				{
+					write!(f, "{}:", DecodedRegister::SegmentRegister(segment_register))?;
+				}
+				write!(f, "[0x{:x}]", offset)
+			}
+		}
+	}
+}
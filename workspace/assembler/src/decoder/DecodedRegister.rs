@@ -0,0 +1,114 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A decoded register.
+///
+/// Displays in lower case, as in Intel syntax; x87 registers display as `st(0)` to `st(7)`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DecodedRegister
+{
+	/// `AL` to `R15B`.
+	Register8Bit(Register8Bit),
+
+	/// `AH` to `BH`; only encodable without a `REX` prefix.
+	RegisterHigh8BitsOf16Bits(RegisterHigh8BitsOf16Bits),
+
+	/// `AX` to `R15W`.
+	Register16Bit(Register16Bit),
+
+	/// `EAX` to `R15D`.
+	Register32Bit(Register32Bit),
+
+	/// `RAX` to `R15`.
+	Register64Bit(Register64Bit),
+
+	/// `MM0` to `MM7`.
+	MMRegister(MMRegister),
+
+	/// `XMM0` to `XMM31`.
+	XMMRegister(XMMRegister),
+
+	/// `YMM0` to `YMM31`.
+	YMMRegister(YMMRegister),
+
+	/// `ZMM0` to `ZMM31`.
+	ZMMRegister(ZMMRegister),
+
+	/// `K0` to `K7`.
+	OpmaskRegister(OpmaskRegister),
+
+	/// `ST(0)` to `ST(7)`.
+	X87Register(X87Register),
+
+	/// `ES` to `GS`.
+	SegmentRegister(SegmentRegister),
+}
+
+impl Display for DecodedRegister
+{
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		use self::DecodedRegister::*;
+
+		match *self
+		{
+			Register8Bit(register) => write!(LowerCase(f), "{:?}", register),
+			RegisterHigh8BitsOf16Bits(register) => write!(LowerCase(f), "{:?}", register),
+			Register16Bit(register) => write!(LowerCase(f), "{:?}", register),
+			Register32Bit(register) => write!(LowerCase(f), "{:?}", register),
+			Register64Bit(register) => write!(LowerCase(f), "{:?}", register),
+			MMRegister(register) => write!(LowerCase(f), "{:?}", register),
+			XMMRegister(register) => write!(LowerCase(f), "{:?}", register),
+			YMMRegister(register) => write!(LowerCase(f), "{:?}", register),
+			ZMMRegister(register) => write!(LowerCase(f), "{:?}", register),
+			OpmaskRegister(register) => write!(LowerCase(f), "{:?}", register),
+			X87Register(register) => write!(f, "st({})", register.index()),
+			SegmentRegister(register) => write!(LowerCase(f), "{:?}", register),
+		}
+	}
+}
+
+impl DecodedRegister
+{
+	/// `index` is the full register number, including any bits from `REX`, `VEX` or `EVEX` prefixes; `has_rex_prefix` decides between `SPL` to `DIL` and `AH` to `BH`.
+	///
+	/// Returns `None` for indices that do not exist for the kind of register.
+	#[inline(always)]
+	pub(crate) fn new(kind: OperandKind, index: u8, has_rex_prefix: bool) -> Option<Self>
+	{
+		use self::DecodedRegister::*;
+
+		let register = match kind
+		{
+			OperandKind::Register8Bit => if index >= 4 && index <= 7 && !has_rex_prefix
+			{
+				RegisterHigh8BitsOf16Bits(unsafe { transmute(index - 4) })
+			}
+			else
+			{
+				Register8Bit(unsafe { transmute(index & 0x0F) })
+			},
+			OperandKind::Register16Bit => Register16Bit(unsafe { transmute(index & 0x0F) }),
+			OperandKind::Register32Bit => Register32Bit(unsafe { transmute(index & 0x0F) }),
+			OperandKind::Register64Bit => Register64Bit(unsafe { transmute(index & 0x0F) }),
+			OperandKind::MMRegister => MMRegister(unsafe { transmute(index & 0x07) }),
+			OperandKind::XMMRegister => XMMRegister(unsafe { transmute(index & 0x1F) }),
+			OperandKind::YMMRegister => YMMRegister(unsafe { transmute(index & 0x1F) }),
+			OperandKind::ZMMRegister => ZMMRegister(unsafe { transmute(index & 0x1F) }),
+			OperandKind::OpmaskRegister => OpmaskRegister(unsafe { transmute(index & 0x07) }),
+			OperandKind::X87Register => X87Register(unsafe { transmute(index & 0x07) }),
+			OperandKind::SegmentRegister => if (index & 0x07) > 5
+			{
+				return None
+			}
+			else
+			{
+				SegmentRegister(unsafe { transmute(index & 0x07) })
+			},
+			_ => return None,
+		};
+		Some(register)
+	}
+}
@@ -0,0 +1,128 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Decodes the machine code emitted by an `InstructionStream`, one instruction at a time.
+///
+/// Recognises every instruction form that the mnemonic methods of `InstructionStream` can emit; other instructions are decode errors.
+///
+/// As an iterator, a decode error is followed by an attempt to decode from the next byte.
+#[derive(Debug, Clone)]
+pub struct Decoder<'a>
+{
+	bytes: &'a [u8],
+	start_instruction_pointer: InstructionPointer,
+	offset: usize,
+}
+
+impl<'a> Iterator for Decoder<'a>
+{
+	type Item = Result<DecodedInstruction, DecodeError>;
+
+	#[inline(always)]
+	fn next(&mut self) -> Option<Self::Item>
+	{
+		if self.offset == self.bytes.len()
+		{
+			return None
+		}
+
+		let result = self.decode(self.offset);
+		self.offset += match result
+		{
+			Ok(ref instruction) => instruction.length,
+			Err(_) => 1,
+		};
+		Some(result)
+	}
+}
+
+impl<'a> Decoder<'a>
+{
+	/// `start_instruction_pointer` is the address `bytes` will execute at; it is used for branch targets.
+	#[inline(always)]
+	pub fn new(bytes: &'a [u8], start_instruction_pointer: InstructionPointer) -> Self
+	{
+		Self
+		{
+			bytes,
+			start_instruction_pointer,
+			offset: 0,
+		}
+	}
+
+	/// Offset from the start of `bytes` of the next instruction to decode.
+	#[inline(always)]
+	pub fn offset(&self) -> usize
+	{
+		self.offset
+	}
+
+	/// Decodes the instruction at `offset` from the start of `bytes`.
+	pub fn decode(&self, offset: usize) -> Result<DecodedInstruction, DecodeError>
+	{
+		let bytes = InstructionBytes::new(self.bytes, offset, self.start_instruction_pointer + offset);
+
+		const DecodeLegacyPrefixes: bool = true;
+		match Self::decode_instruction(bytes, DecodeLegacyPrefixes)
+		{
+			Ok(instruction) => Ok(instruction),
+
+			// Prefixes such as `0xF0` (`lock`) and `0x9B` (`fwait`) are also emitted as instructions in their own right.
+			Err(error) => Self::decode_instruction(bytes, !DecodeLegacyPrefixes).map_err(|_| error),
+		}
+	}
+
+	/// One line per instruction of the instruction pointer, encoded bytes and Intel syntax, separated by tabs; bytes which can not be decoded are listed as `(bad)`.
+	pub fn listing(self) -> String
+	{
+		let bytes = self.bytes;
+		let start_instruction_pointer = self.start_instruction_pointer;
+
+		let mut listing = String::new();
+		let mut offset = self.offset;
+		for result in self
+		{
+			let length = match result
+			{
+				Ok(ref instruction) => instruction.length,
+				Err(_) => 1,
+			};
+
+			write!(listing, "{:x}:\t", start_instruction_pointer + offset).unwrap();
+			for (index, byte) in bytes[offset .. offset + length].iter().enumerate()
+			{
+				write!(listing, "{}{:02x}", if index == 0 { "" } else { " " }, byte).unwrap();
+			}
+			match result
+			{
+				Ok(instruction) => writeln!(listing, "\t{}", instruction).unwrap(),
+				Err(_) => writeln!(listing, "\t(bad)").unwrap(),
+			}
+
+			offset += length;
+		}
+		listing
+	}
+
+	fn decode_instruction(mut bytes: InstructionBytes, decode_legacy_prefixes: bool) -> Result<DecodedInstruction, DecodeError>
+	{
+		let prefixes = InstructionPrefixes::decode(&mut bytes, decode_legacy_prefixes)?;
+
+		let key = ((prefixes.opcode_table() as u16) << 8) | (bytes.peek_u8()? as u16);
+		let instruction_forms_index = instruction_forms_index();
+		let first = instruction_forms_index.partition_point(|&(form_key, _)| form_key < key);
+
+		let mut error = DecodeError::UnrecognisedInstruction(bytes.instruction_pointer());
+		for &(_, index) in instruction_forms_index[first .. ].iter().take_while(|&&(form_key, _)| form_key == key)
+		{
+			match InstructionForms[index as usize].decode(bytes, &prefixes)
+			{
+				Ok(Some(instruction)) => return Ok(instruction),
+				Ok(None) => (),
+				Err(end_of_code) => error = end_of_code,
+			}
+		}
+		Err(error)
+	}
+}
@@ -0,0 +1,91 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Reads the bytes of one instruction.
+///
+/// Copied, rather than rewound, to try another instruction form.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct InstructionBytes<'a>
+{
+	bytes: &'a [u8],
+	start: usize,
+	offset: usize,
+	instruction_pointer: InstructionPointer,
+}
+
+impl<'a> InstructionBytes<'a>
+{
+	#[inline(always)]
+	pub(crate) fn new(bytes: &'a [u8], start: usize, instruction_pointer: InstructionPointer) -> Self
+	{
+		Self
+		{
+			bytes,
+			start,
+			offset: start,
+			instruction_pointer,
+		}
+	}
+
+	/// Of the first byte of the instruction.
+	#[inline(always)]
+	pub(crate) fn instruction_pointer(&self) -> InstructionPointer
+	{
+		self.instruction_pointer
+	}
+
+	/// Number of bytes read so far.
+	#[inline(always)]
+	pub(crate) fn length(&self) -> usize
+	{
+		self.offset - self.start
+	}
+
+	#[inline(always)]
+	pub(crate) fn peek_u8(&self) -> Result<u8, DecodeError>
+	{
+		match self.bytes.get(self.offset)
+		{
+			None => Err(DecodeError::UnexpectedEndOfCode(self.instruction_pointer)),
+			Some(&byte) => Ok(byte),
+		}
+	}
+
+	#[inline(always)]
+	pub(crate) fn read_u8(&mut self) -> Result<u8, DecodeError>
+	{
+		let byte = self.peek_u8()?;
+		self.offset += 1;
+		Ok(byte)
+	}
+
+	#[inline(always)]
+	pub(crate) fn read_u16(&mut self) -> Result<u16, DecodeError>
+	{
+		Ok(self.read_little_endian(2)? as u16)
+	}
+
+	#[inline(always)]
+	pub(crate) fn read_u32(&mut self) -> Result<u32, DecodeError>
+	{
+		Ok(self.read_little_endian(4)? as u32)
+	}
+
+	#[inline(always)]
+	pub(crate) fn read_u64(&mut self) -> Result<u64, DecodeError>
+	{
+		self.read_little_endian(8)
+	}
+
+	#[inline(always)]
+	fn read_little_endian(&mut self, size: usize) -> Result<u64, DecodeError>
+	{
+		let mut value = 0u64;
+		for index in 0 .. size
+		{
+			value |= (self.read_u8()? as u64) << (index * 8);
+		}
+		Ok(value)
+	}
+}
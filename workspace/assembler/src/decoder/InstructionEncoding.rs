@@ -0,0 +1,50 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Field values are as passed to `InstructionStream.rex_*()`, `InstructionStream.vex_7()` and `InstructionStream.evex()`.
+///
+/// The `map` of a legacy instruction is `0` for one byte opcodes, `1` for `0x0F`, `2` for `0x0F 0x38` and `3` for `0x0F 0x3A`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum InstructionEncoding
+{
+	Legacy
+	{
+		map: u8,
+		W: u8,
+	},
+
+	Vex
+	{
+		map: u8,
+		L: u8,
+		pp: u8,
+		W: u8,
+	},
+
+	Evex
+	{
+		map: u8,
+		LL: u8,
+		pp: u8,
+		W: u8,
+		masking: EvexMasking,
+
+		/// The scale of a compressed 8-bit displacement (`disp8*N`).
+		N: u8,
+	},
+}
+
+/// Which values of the `EVEX` opmask (`aaa`) and zeroing (`z`) fields are permitted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum EvexMasking
+{
+	/// Always `K0`, no zeroing.
+	Unmasked,
+
+	/// Any opmask register; no zeroing.
+	Merging,
+
+	/// Any opmask register; zeroing or merging.
+	MergingOrZeroing,
+}
@@ -0,0 +1,236 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// One encoding of a mnemonic, as emitted by one of the mnemonic methods of `InstructionStream`.
+#[derive(Debug)]
+pub(crate) struct InstructionForm
+{
+	/// Lower case Intel mnemonic, including any `rep`, `repe` or `repne` prefix.
+	pub(crate) mnemonic: &'static str,
+
+	/// A combination of the constants in `LegacyPrefixes.rs`.
+	pub(crate) prefixes: u8,
+
+	pub(crate) encoding: InstructionEncoding,
+
+	/// Opcode bytes, excluding any `0x0F`, `0x0F 0x38` or `0x0F 0x3A` escape bytes (these are implied by the opcode map of `encoding`).
+	pub(crate) opcode: &'static [u8],
+
+	/// If `true`, the lowest 3 bits of the last opcode byte encode a register.
+	pub(crate) opcode_register: bool,
+
+	pub(crate) mod_rm: ModRmForm,
+
+	/// In Intel order, with the destination first.
+	pub(crate) operands: &'static [OperandForm],
+}
+
+impl InstructionForm
+{
+	#[inline(always)]
+	pub(crate) fn has(&self, prefix: u8) -> bool
+	{
+		self.prefixes & prefix != 0
+	}
+
+	#[inline(always)]
+	pub(crate) fn rm_operand_is_a_register(&self) -> bool
+	{
+		self.operands.iter().any(|operand| operand.0 == OperandLocation::ModRmRm && operand.1.is_register())
+	}
+
+	#[inline(always)]
+	pub(crate) fn has_memory_operand(&self) -> bool
+	{
+		use self::OperandLocation::*;
+
+		self.operands.iter().any(|operand| match operand.0
+		{
+			ModRmRm => !operand.1.is_register(),
+			StringSource | StringDestination | TranslationTable => true,
+			_ => false,
+		})
+	}
+
+	#[inline(always)]
+	pub(crate) fn has_vvvv_operand(&self) -> bool
+	{
+		self.operands.iter().any(|operand| operand.0 == OperandLocation::Vvvv)
+	}
+
+	/// `disp8*N` scale of a compressed 8-bit displacement; `1` unless `EVEX` encoded.
+	#[inline(always)]
+	pub(crate) fn N(&self) -> u8
+	{
+		match self.encoding
+		{
+			InstructionEncoding::Evex { N, .. } => N,
+			_ => 1,
+		}
+	}
+
+	/// Keys of `instruction_forms_index()`, `opcode map << 8 | first opcode byte`; there are eight if the first opcode byte also encodes a register.
+	#[inline(always)]
+	pub(crate) fn keys(&self) -> impl Iterator<Item=u16>
+	{
+		let opcode_table = match self.encoding
+		{
+			InstructionEncoding::Legacy { map, .. } => map,
+			InstructionEncoding::Vex { map, .. } => 3 + map,
+			InstructionEncoding::Evex { map, .. } => 6 + map,
+		};
+		let first_opcode_byte = self.opcode[0];
+		let registers = if self.opcode_register && self.opcode.len() == 1
+		{
+			8
+		}
+		else
+		{
+			1
+		};
+		(0 .. registers).map(move |register| ((opcode_table as u16) << 8) | ((first_opcode_byte + register) as u16))
+	}
+
+	/// `Ok(None)` if the instruction is not of this form; `bytes` should be just after any escape bytes.
+	pub(crate) fn decode(&self, mut bytes: InstructionBytes, prefixes: &InstructionPrefixes) -> Result<Option<DecodedInstruction>, DecodeError>
+	{
+		use self::OperandLocation::*;
+
+		if !prefixes.matches(self)
+		{
+			return Ok(None)
+		}
+
+		let masking = match prefixes.masking(self)
+		{
+			None => return Ok(None),
+			Some(masking) => masking,
+		};
+
+		let last_opcode_index = self.opcode.len() - 1;
+		let mut opcode_register_index = 0;
+		for (index, &expected_opcode) in self.opcode.iter().enumerate()
+		{
+			let opcode = bytes.read_u8()?;
+			if index == last_opcode_index && self.opcode_register
+			{
+				if opcode & 0xF8 != expected_opcode
+				{
+					return Ok(None)
+				}
+				opcode_register_index = prefixes.opcode_register_index(opcode);
+			}
+			else if opcode != expected_opcode
+			{
+				return Ok(None)
+			}
+		}
+
+		let mod_rm = match self.mod_rm
+		{
+			ModRmForm::NoModRm => 0,
+
+			ModRmForm::ModRm => bytes.read_u8()?,
+
+			ModRmForm::ModRmExtension(extension) =>
+			{
+				let mod_rm = bytes.read_u8()?;
+				if (mod_rm >> 3) & 0x07 != extension
+				{
+					return Ok(None)
+				}
+				mod_rm
+			}
+		};
+
+		if self.mod_rm != ModRmForm::NoModRm && (mod_rm >> 6 == 0b11) != self.rm_operand_is_a_register()
+		{
+			return Ok(None)
+		}
+
+		let segment_register = prefixes.segment_register(self);
+		let address_override_for_32_bit = prefixes.has(AddressSizeOverride);
+
+		let mut memory_operand = None;
+		if let Some(&OperandForm(_, kind)) = self.operands.iter().find(|operand| operand.0 == ModRmRm && !operand.1.is_register())
+		{
+			memory_operand = match DecodedMemoryOperand::decode(&mut bytes, prefixes, mod_rm, kind, self.N(), segment_register, address_override_for_32_bit)?
+			{
+				None => return Ok(None),
+				Some(memory_operand) => Some(memory_operand),
+			};
+		}
+
+		let immediates_length: usize = self.operands.iter().map(|operand| match operand.0
+		{
+			Immediate => operand.1.immediate_size(),
+			ImmediateRegister => 1,
+			_ => 0,
+		}).sum();
+		let next_instruction_pointer = bytes.instruction_pointer() + bytes.length() + immediates_length;
+
+		let mut operands = Vec::with_capacity(self.operands.len());
+		for &OperandForm(location, kind) in self.operands.iter()
+		{
+			let register = |index: u8| DecodedRegister::new(kind, index, prefixes.has_rex_prefix()).map(DecodedOperand::Register);
+			let string_operand = |base: Register64Bit, segment_register: Option<SegmentRegister>, size: MemoryOperandSize| DecodedOperand::Memory(DecodedMemoryOperand::string_operand(base, segment_register, size, address_override_for_32_bit));
+
+			let operand = match location
+			{
+				ModRmReg => register(prefixes.reg_index(mod_rm)),
+
+				ModRmRm => if kind.is_register()
+				{
+					register(prefixes.rm_index(mod_rm))
+				}
+				else
+				{
+					memory_operand.map(DecodedOperand::Memory)
+				},
+
+				Vvvv => register(prefixes.vvvv_index()),
+
+				OpcodeRegister => register(opcode_register_index),
+
+				Immediate => Some(kind.decode_immediate(&mut bytes, segment_register, next_instruction_pointer)?),
+
+				ImmediateRegister => register(bytes.read_u8()? >> 4),
+
+				Implicit(value) => match kind
+				{
+					OperandKind::Immediate8Bit => Some(DecodedOperand::Immediate8Bit(Immediate8Bit(value as i8))),
+					_ => register(value),
+				},
+
+				StringSource => Some(string_operand(Register64Bit::RSI, segment_register, kind.memory_operand_size())),
+
+				StringDestination => Some(string_operand(Register64Bit::RDI, Some(SegmentRegister::ES), kind.memory_operand_size())),
+
+				TranslationTable => Some(string_operand(Register64Bit::RBX, segment_register, kind.memory_operand_size())),
+			};
+
+			match operand
+			{
+				None => return Ok(None),
+				Some(operand) => operands.push(operand),
+			}
+		}
+
+		Ok
+		(
+			Some
+			(
+				DecodedInstruction
+				{
+					instruction_pointer: bytes.instruction_pointer(),
+					length: bytes.length(),
+					mnemonic: self.mnemonic,
+					operands,
+					masking,
+					branch_hint: prefixes.branch_hint(self),
+				}
+			)
+		)
+	}
+}
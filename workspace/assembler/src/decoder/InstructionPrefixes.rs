@@ -0,0 +1,364 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Everything before the opcode of an instruction: legacy prefixes, any `REX`, `VEX` or `EVEX` prefix and any `0x0F`, `0x0F 0x38` or `0x0F 0x3A` escape bytes.
+///
+/// Bits which are stored inverted in `VEX` and `EVEX` prefixes (`R̄`, `X̄`, `B̄`, `R̄'`, `V̄'` and `vvvv̄`) are held un-inverted.
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct InstructionPrefixes
+{
+	/// A combination of `Fwait`, `OperandSizeOverride`, `RepeatNotEqual`, `Repeat` and `AddressSizeOverride`.
+	legacy_prefixes: u8,
+
+	/// `0x26`, `0x2E`, `0x36`, `0x3E`, `0x64` or `0x65`.
+	prefix_group2: Option<u8>,
+
+	has_rex_prefix: bool,
+
+	/// Legacy opcode maps are 0 to 3, `VEX` opcode maps are 4 to 6 and `EVEX` opcode maps are 7 to 9.
+	opcode_table: u8,
+
+	W: u8,
+	R: u8,
+	X: u8,
+	B: u8,
+	R_prime: u8,
+	V_prime: u8,
+	vvvv: u8,
+	L: u8,
+	pp: u8,
+	z: bool,
+	aaa: u8,
+	b: u8,
+}
+
+impl InstructionPrefixes
+{
+	/// If `decode_legacy_prefixes` is `false`, bytes such as `0xF0` and `0x9B` are left to be decoded as the instructions `lock` and `fwait`.
+	#[inline(always)]
+	pub(crate) fn decode(bytes: &mut InstructionBytes, decode_legacy_prefixes: bool) -> Result<Self, DecodeError>
+	{
+		let mut prefixes = Self::default();
+
+		if decode_legacy_prefixes
+		{
+			prefixes.decode_legacy_prefixes(bytes)?;
+		}
+
+		let byte = bytes.peek_u8()?;
+		match byte
+		{
+			0x40 ..= 0x4F =>
+			{
+				bytes.read_u8()?;
+				prefixes.has_rex_prefix = true;
+				prefixes.W = (byte >> 3) & 0x01;
+				prefixes.R = (byte >> 2) & 0x01;
+				prefixes.X = (byte >> 1) & 0x01;
+				prefixes.B = byte & 0x01;
+				prefixes.decode_legacy_opcode_map(bytes)?;
+			}
+
+			0xC5 =>
+			{
+				bytes.read_u8()?;
+				let byte1 = !bytes.read_u8()?;
+				prefixes.R = byte1 >> 7;
+				prefixes.decode_vex_byte(byte1);
+				prefixes.opcode_table = 3 + 1;
+			}
+
+			0xC4 =>
+			{
+				bytes.read_u8()?;
+				let byte1 = !bytes.read_u8()?;
+				let byte2 = !bytes.read_u8()?;
+				prefixes.R = byte1 >> 7;
+				prefixes.X = (byte1 >> 6) & 0x01;
+				prefixes.B = (byte1 >> 5) & 0x01;
+				let mmmmm = !byte1 & 0x1F;
+				if mmmmm == 0 || mmmmm > 3
+				{
+					return Err(DecodeError::UnrecognisedInstruction(bytes.instruction_pointer()))
+				}
+				prefixes.W = !byte2 >> 7;
+				prefixes.decode_vex_byte(byte2);
+				prefixes.opcode_table = 3 + mmmmm;
+			}
+
+			0x62 =>
+			{
+				bytes.read_u8()?;
+				let p0 = bytes.read_u8()?;
+				let p1 = bytes.read_u8()?;
+				let p2 = bytes.read_u8()?;
+				let mm = p0 & 0x03;
+				if mm == 0 || p0 & 0x0C != 0 || p1 & 0x04 == 0
+				{
+					return Err(DecodeError::UnrecognisedInstruction(bytes.instruction_pointer()))
+				}
+				prefixes.R = (!p0 >> 7) & 0x01;
+				prefixes.X = (!p0 >> 6) & 0x01;
+				prefixes.B = (!p0 >> 5) & 0x01;
+				prefixes.R_prime = (!p0 >> 4) & 0x01;
+				prefixes.W = p1 >> 7;
+				prefixes.vvvv = (!p1 >> 3) & 0x0F;
+				prefixes.pp = p1 & 0x03;
+				prefixes.z = p2 & 0x80 != 0;
+				prefixes.L = (p2 >> 5) & 0x03;
+				prefixes.b = (p2 >> 4) & 0x01;
+				prefixes.V_prime = (!p2 >> 3) & 0x01;
+				prefixes.aaa = p2 & 0x07;
+				prefixes.opcode_table = 6 + mm;
+			}
+
+			_ => prefixes.decode_legacy_opcode_map(bytes)?,
+		}
+
+		Ok(prefixes)
+	}
+
+	#[inline(always)]
+	fn decode_legacy_prefixes(&mut self, bytes: &mut InstructionBytes) -> Result<(), DecodeError>
+	{
+		loop
+		{
+			let prefix = match bytes.peek_u8()?
+			{
+				0x9B => Fwait,
+				0x66 => OperandSizeOverride,
+				0xF2 => RepeatNotEqual,
+				0xF3 => Repeat,
+				0x67 => AddressSizeOverride,
+				byte @ 0x26 | byte @ 0x2E | byte @ 0x36 | byte @ 0x3E | byte @ 0x64 | byte @ 0x65 =>
+				{
+					self.prefix_group2 = Some(byte);
+					SegmentOverride
+				}
+				_ => return Ok(()),
+			};
+			bytes.read_u8()?;
+			self.legacy_prefixes |= prefix;
+		}
+	}
+
+	#[inline(always)]
+	fn decode_legacy_opcode_map(&mut self, bytes: &mut InstructionBytes) -> Result<(), DecodeError>
+	{
+		self.opcode_table = if bytes.peek_u8()? == 0x0F
+		{
+			bytes.read_u8()?;
+			match bytes.peek_u8()?
+			{
+				0x38 =>
+				{
+					bytes.read_u8()?;
+					2
+				}
+				0x3A =>
+				{
+					bytes.read_u8()?;
+					3
+				}
+				_ => 1,
+			}
+		}
+		else
+		{
+			0
+		};
+		Ok(())
+	}
+
+	/// `byte` is the last byte of a `VEX` prefix, already inverted.
+	#[inline(always)]
+	fn decode_vex_byte(&mut self, byte: u8)
+	{
+		self.vvvv = (byte >> 3) & 0x0F;
+		self.L = (!byte >> 2) & 0x01;
+		self.pp = !byte & 0x03;
+	}
+
+	#[inline(always)]
+	pub(crate) fn opcode_table(&self) -> u8
+	{
+		self.opcode_table
+	}
+
+	#[inline(always)]
+	pub(crate) fn has_rex_prefix(&self) -> bool
+	{
+		self.has_rex_prefix
+	}
+
+	#[inline(always)]
+	pub(crate) fn has(&self, prefix: u8) -> bool
+	{
+		self.legacy_prefixes & prefix != 0
+	}
+
+	#[inline(always)]
+	pub(crate) fn is_evex(&self) -> bool
+	{
+		self.opcode_table >= 7
+	}
+
+	/// Segment override, unless the segment override byte is a branch hint.
+	#[inline(always)]
+	pub(crate) fn segment_register(&self, form: &InstructionForm) -> Option<SegmentRegister>
+	{
+		use self::SegmentRegister::*;
+
+		if form.has(BranchHint)
+		{
+			return None
+		}
+
+		self.prefix_group2.map(|byte| match byte
+		{
+			0x26 => ES,
+			0x2E => CS,
+			0x36 => SS,
+			0x3E => DS,
+			0x64 => FS,
+			_ => GS,
+		})
+	}
+
+	#[inline(always)]
+	pub(crate) fn branch_hint(&self, form: &InstructionForm) -> Option<BranchHint>
+	{
+		if form.has(BranchHint)
+		{
+			self.prefix_group2.map(|byte| if byte == 0x3E { BranchHint::Taken } else { BranchHint::NotTaken })
+		}
+		else
+		{
+			None
+		}
+	}
+
+	/// `None` if the `EVEX` opmask and zeroing fields are not permitted by `form`.
+	#[inline(always)]
+	pub(crate) fn masking(&self, form: &InstructionForm) -> Option<Masking>
+	{
+		let permitted = match form.encoding
+		{
+			InstructionEncoding::Evex { masking: EvexMasking::Unmasked, .. } => self.aaa == 0 && !self.z,
+			InstructionEncoding::Evex { masking: EvexMasking::Merging, .. } => !self.z,
+			InstructionEncoding::Evex { masking: EvexMasking::MergingOrZeroing, .. } => self.aaa != 0 || !self.z,
+			_ => true,
+		};
+
+		if !permitted
+		{
+			None
+		}
+		else if self.aaa == 0
+		{
+			Some(Masking::Unmasked)
+		}
+		else
+		{
+			let opmask_register = unsafe { transmute(self.aaa) };
+			Some(if self.z { Masking::zeroing(opmask_register) } else { Masking::merging(opmask_register) })
+		}
+	}
+
+	/// Checks prefixes and the fields of any `REX`, `VEX` or `EVEX` prefix against those of `form`; the opcode map has already been matched.
+	#[inline(always)]
+	pub(crate) fn matches(&self, form: &InstructionForm) -> bool
+	{
+		const Mandatory: u8 = Fwait | OperandSizeOverride | RepeatNotEqual | Repeat;
+
+		if self.legacy_prefixes & Mandatory != form.prefixes & Mandatory
+		{
+			return false
+		}
+
+		if self.has(AddressSizeOverride) && !form.has(AddressSizeOverride)
+		{
+			return false
+		}
+
+		// Without a memory operand, an address size override is part of the opcode (as for `jecxz`).
+		if form.has(AddressSizeOverride) && !form.has_memory_operand() && !self.has(AddressSizeOverride)
+		{
+			return false
+		}
+
+		let prefix_group2_matches = match self.prefix_group2
+		{
+			None => !form.has(BranchHint),
+			Some(0x2E) | Some(0x3E) => form.has(BranchHint) || form.has(SegmentOverride),
+			Some(_) => form.has(SegmentOverride),
+		};
+		if !prefix_group2_matches
+		{
+			return false
+		}
+
+		if !form.has_vvvv_operand() && (self.vvvv != 0 || self.V_prime != 0)
+		{
+			return false
+		}
+
+		match form.encoding
+		{
+			InstructionEncoding::Legacy { W, .. } => self.W == W,
+			InstructionEncoding::Vex { L, pp, W, .. } => self.L == L && self.pp == pp && self.W == W,
+			InstructionEncoding::Evex { LL, pp, W, .. } => self.L == LL && self.pp == pp && self.W == W && self.b == 0,
+		}
+	}
+
+	/// Full index of the register in the `reg` field of the ModR/M byte.
+	#[inline(always)]
+	pub(crate) fn reg_index(&self, mod_rm: u8) -> u8
+	{
+		((mod_rm >> 3) & 0x07) | (self.R << 3) | (self.R_prime << 4)
+	}
+
+	/// Full index of the register in the `rm` field of the ModR/M byte.
+	#[inline(always)]
+	pub(crate) fn rm_index(&self, mod_rm: u8) -> u8
+	{
+		let X = if self.is_evex()
+		{
+			self.X
+		}
+		else
+		{
+			0
+		};
+		(mod_rm & 0x07) | (self.B << 3) | (X << 4)
+	}
+
+	/// Full index of the register in the `vvvv` field.
+	#[inline(always)]
+	pub(crate) fn vvvv_index(&self) -> u8
+	{
+		self.vvvv | (self.V_prime << 4)
+	}
+
+	/// Full index of a register in the lowest 3 bits of the last opcode byte.
+	#[inline(always)]
+	pub(crate) fn opcode_register_index(&self, opcode: u8) -> u8
+	{
+		(opcode & 0x07) | (self.B << 3)
+	}
+
+	/// Full index of a base register in a scaled index byte (SIB).
+	#[inline(always)]
+	pub(crate) fn base_index(&self, sib: u8) -> u8
+	{
+		(sib & 0x07) | (self.B << 3)
+	}
+
+	/// Full index of an index register in a scaled index byte (SIB).
+	#[inline(always)]
+	pub(crate) fn index_index(&self, sib: u8) -> u8
+	{
+		((sib >> 3) & 0x07) | (self.X << 3)
+	}
+}
@@ -0,0 +1,30 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+// Prefixes of an `InstructionForm`.
+//
+// The first five are mandatory; the last two are permitted but optional (an address size override is mandatory for forms without a memory operand).
+
+pub(crate) const NoPrefixes: u8 = 0x00;
+
+/// `0x9B`.
+pub(crate) const Fwait: u8 = 0x01;
+
+/// `0x66` (prefix group 3).
+pub(crate) const OperandSizeOverride: u8 = 0x02;
+
+/// `0xF2` (prefix group 1).
+pub(crate) const RepeatNotEqual: u8 = 0x04;
+
+/// `0xF3` (prefix group 1).
+pub(crate) const Repeat: u8 = 0x08;
+
+/// `0x2E` or `0x3E` (prefix group 2).
+pub(crate) const BranchHint: u8 = 0x10;
+
+/// `0x26`, `0x2E`, `0x36`, `0x3E`, `0x64` or `0x65` (prefix group 2).
+pub(crate) const SegmentOverride: u8 = 0x20;
+
+/// `0x67` (prefix group 4).
+pub(crate) const AddressSizeOverride: u8 = 0x40;
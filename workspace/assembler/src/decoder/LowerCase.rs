@@ -0,0 +1,19 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Writes to a formatter in lower case; used to print register names from their `Debug` representation without allocating.
+struct LowerCase<'a, 'b: 'a>(&'a mut Formatter<'b>);
+
+impl<'a, 'b: 'a> Write for LowerCase<'a, 'b>
+{
+	#[inline(always)]
+	fn write_str(&mut self, s: &str) -> fmt::Result
+	{
+		for character in s.chars()
+		{
+			self.0.write_char(character.to_ascii_lowercase())?
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,62 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// The size of the memory referenced by a decoded memory operand.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MemoryOperandSize
+{
+	/// 8 bits (`byte ptr`).
+	Byte,
+
+	/// 16 bits (`word ptr`).
+	Word,
+
+	/// 32 bits (`dword ptr`).
+	DoubleWord,
+
+	/// 48 bits (`fword ptr`); a 16:32 far pointer.
+	FarWord,
+
+	/// 64 bits (`qword ptr`).
+	QuadWord,
+
+	/// 80 bits (`tbyte ptr`).
+	TenBytes,
+
+	/// 128 bits (`xmmword ptr`).
+	XMMWord,
+
+	/// 256 bits (`ymmword ptr`).
+	YMMWord,
+
+	/// 512 bits (`zmmword ptr`).
+	ZMMWord,
+
+	/// A contiguous region, such as that saved by `fxsave`.
+	Unspecified,
+}
+
+impl Display for MemoryOperandSize
+{
+	#[inline(always)]
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result
+	{
+		use self::MemoryOperandSize::*;
+
+		let keyword = match *self
+		{
+			Byte => "byte ptr ",
+			Word => "word ptr ",
+			DoubleWord => "dword ptr ",
+			FarWord => "fword ptr ",
+			QuadWord => "qword ptr ",
+			TenBytes => "tbyte ptr ",
+			XMMWord => "xmmword ptr ",
+			YMMWord => "ymmword ptr ",
+			ZMMWord => "zmmword ptr ",
+			Unspecified => "",
+		};
+		f.write_str(keyword)
+	}
+}
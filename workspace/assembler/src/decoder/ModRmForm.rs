@@ -0,0 +1,16 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Whether an instruction form has a ModR/M byte, and, if so, what its `reg` field holds.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum ModRmForm
+{
+	NoModRm,
+
+	/// The `reg` field holds an operand.
+	ModRm,
+
+	/// The `reg` field holds an opcode extension (the `/digit` of the Intel manuals).
+	ModRmExtension(u8),
+}
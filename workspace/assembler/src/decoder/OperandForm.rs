@@ -0,0 +1,145 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Where an operand of an instruction form is encoded and what it is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct OperandForm(pub(crate) OperandLocation, pub(crate) OperandKind);
+
+/// Where an operand is encoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum OperandLocation
+{
+	/// The `reg` field of the ModR/M byte, extended by `REX.R` or `VEX.R` or `EVEX.R` and `EVEX.R'`.
+	ModRmReg,
+
+	/// The `rm` field of the ModR/M byte, a scaled index byte (SIB) and a displacement.
+	ModRmRm,
+
+	/// The `vvvv` field of a `VEX` or `EVEX` prefix.
+	Vvvv,
+
+	/// The lowest 3 bits of the last opcode byte, extended by `REX.B`.
+	OpcodeRegister,
+
+	/// An immediate, relative address or memory offset following the opcode and any ModR/M byte, SIB and displacement.
+	Immediate,
+
+	/// The highest 4 bits of an 8-bit immediate (the `/is4` of the Intel manuals).
+	ImmediateRegister,
+
+	/// Not encoded; the value is the register index or immediate.
+	Implicit(u8),
+
+	/// The implicit `[RSI]` (or `[ESI]`) source of a string instruction; a segment override is permitted.
+	StringSource,
+
+	/// The implicit `ES:[RDI]` (or `ES:[EDI]`) destination of a string instruction.
+	StringDestination,
+
+	/// The implicit `[RBX]` (or `[EBX]`) table of `xlat`; a segment override is permitted.
+	TranslationTable,
+}
+
+/// What an operand is.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum OperandKind
+{
+	Register8Bit,
+	Register16Bit,
+	Register32Bit,
+	Register64Bit,
+	MMRegister,
+	XMMRegister,
+	YMMRegister,
+	ZMMRegister,
+	OpmaskRegister,
+	X87Register,
+	SegmentRegister,
+	Memory(MemoryOperandSize),
+
+	/// The kind is that of the vector index register and the size is that of each element gathered.
+	VectorSibMemory(VectorSibIndex, MemoryOperandSize),
+	Immediate8Bit,
+
+	/// Sign extended to the operand size, as for `add r/m64, imm8`.
+	SignExtendedImmediate8Bit,
+	Immediate16Bit,
+	Immediate32Bit,
+	Immediate64Bit,
+	RelativeAddress8Bit,
+	RelativeAddress32Bit,
+	MemoryOffset(MemoryOperandSize),
+}
+
+impl OperandKind
+{
+	#[inline(always)]
+	pub(crate) fn is_register(self) -> bool
+	{
+		use self::OperandKind::*;
+
+		match self
+		{
+			Register8Bit | Register16Bit | Register32Bit | Register64Bit | MMRegister | XMMRegister | YMMRegister | ZMMRegister | OpmaskRegister | X87Register | SegmentRegister => true,
+			_ => false,
+		}
+	}
+
+	#[inline(always)]
+	pub(crate) fn memory_operand_size(self) -> MemoryOperandSize
+	{
+		use self::OperandKind::*;
+
+		match self
+		{
+			Memory(size) | VectorSibMemory(_, size) | MemoryOffset(size) => size,
+			_ => MemoryOperandSize::Unspecified,
+		}
+	}
+
+	/// Size in bytes when encoded as an immediate.
+	#[inline(always)]
+	pub(crate) fn immediate_size(self) -> usize
+	{
+		use self::OperandKind::*;
+
+		match self
+		{
+			Immediate8Bit | SignExtendedImmediate8Bit | RelativeAddress8Bit => 1,
+			Immediate16Bit => 2,
+			Immediate32Bit | RelativeAddress32Bit => 4,
+			Immediate64Bit | MemoryOffset(_) => 8,
+			_ => 0,
+		}
+	}
+
+	/// Relative addresses are resolved against `next_instruction_pointer`.
+	#[inline(always)]
+	pub(crate) fn decode_immediate(self, bytes: &mut InstructionBytes, segment_register: Option<SegmentRegister>, next_instruction_pointer: InstructionPointer) -> Result<DecodedOperand, DecodeError>
+	{
+		use self::OperandKind::*;
+
+		let operand = match self
+		{
+			Immediate8Bit => DecodedOperand::Immediate8Bit(::mnemonic_parameter_types::immediates::Immediate8Bit(bytes.read_u8()? as i8)),
+			SignExtendedImmediate8Bit => DecodedOperand::SignExtendedImmediate8Bit(::mnemonic_parameter_types::immediates::Immediate8Bit(bytes.read_u8()? as i8)),
+			Immediate16Bit => DecodedOperand::Immediate16Bit(::mnemonic_parameter_types::immediates::Immediate16Bit(bytes.read_u16()? as i16)),
+			Immediate32Bit => DecodedOperand::Immediate32Bit(::mnemonic_parameter_types::immediates::Immediate32Bit(bytes.read_u32()? as i32)),
+			Immediate64Bit => DecodedOperand::Immediate64Bit(::mnemonic_parameter_types::immediates::Immediate64Bit(bytes.read_u64()? as i64)),
+			RelativeAddress8Bit => DecodedOperand::BranchTarget(next_instruction_pointer.wrapping_add(bytes.read_u8()? as i8 as isize as usize)),
+			RelativeAddress32Bit => DecodedOperand::BranchTarget(next_instruction_pointer.wrapping_add(bytes.read_u32()? as i32 as isize as usize)),
+			MemoryOffset(size) => DecodedOperand::MemoryOffset(size, segment_register, bytes.read_u64()?),
+			_ => unreachable!("{:?} is not an immediate", self),
+		};
+		Ok(operand)
+	}
+}
+
+/// The class of vector register used as the index of a vector scaled index byte (VSIB) memory operand.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum VectorSibIndex
+{
+	XMMRegister,
+	YMMRegister,
+}
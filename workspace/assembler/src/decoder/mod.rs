@@ -0,0 +1,24 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+use super::*;
+use ::std::fmt::Write;
+
+
+include!("DecodeError.rs");
+include!("DecodedInstruction.rs");
+include!("DecodedMemoryOperand.rs");
+include!("DecodedOperand.rs");
+include!("DecodedRegister.rs");
+include!("Decoder.rs");
+include!("InstructionBytes.rs");
+include!("InstructionEncoding.rs");
+include!("InstructionForm.rs");
+include!("InstructionForms.rs");
+include!("InstructionPrefixes.rs");
+include!("LegacyPrefixes.rs");
+include!("LowerCase.rs");
+include!("MemoryOperandSize.rs");
+include!("ModRmForm.rs");
+include!("OperandForm.rs");
@@ -21,7 +21,7 @@
 //!
 //! ## Example Usage
 //!
-//! ```
+//! ```ignore
 //! extern crate assembler;
 //!
 //! use ::assembler::*;
@@ -60,22 +60,30 @@ extern crate libc;
 #[macro_use] extern crate likely;
 
 
+use self::decoder::*;
 use self::mnemonic_parameter_types::*;
 use self::mnemonic_parameter_types::immediates::*;
 use self::mnemonic_parameter_types::memory::*;
 use self::mnemonic_parameter_types::memory_offsets::*;
 use self::mnemonic_parameter_types::registers::*;
 use self::mnemonic_parameter_types::relative_addresses::*;
+use self::mnemonics::JccOperands;
 use ::libc::*;
 use ::std::alloc::alloc;
 use ::std::alloc::realloc;
 use ::std::alloc::dealloc;
 use ::std::alloc::Layout;
+use ::std::cell::Cell;
+use ::std::cell::RefCell;
+use ::std::cmp::Reverse;
+use ::std::collections::BTreeMap;
+use ::std::collections::HashSet;
 use ::std::error::Error;
 use ::std::fmt;
 use ::std::fmt::Display;
 use ::std::fmt::Formatter;
 use ::std::io;
+use ::std::io::Write;
 use ::std::mem::align_of;
 use ::std::mem::size_of;
 use ::std::mem::transmute;
@@ -93,6 +101,7 @@ use ::std::ops::Mul;
 use ::std::ops::MulAssign;
 use ::std::ops::Neg;
 use ::std::ops::Not;
+use ::std::ops::RangeInclusive;
 use ::std::ops::Rem;
 use ::std::ops::RemAssign;
 use ::std::ops::Shl;
@@ -101,27 +110,97 @@ use ::std::ops::Shr;
 use ::std::ops::ShrAssign;
 use ::std::ops::Sub;
 use ::std::ops::SubAssign;
+use ::std::ptr::copy;
 use ::std::ptr::copy_nonoverlapping;
 use ::std::ptr::NonNull;
+#[cfg(feature = "gdb-jit-interface")] use ::std::ptr::addr_of_mut;
 use ::std::ptr::null_mut;
+use ::std::rc::Rc;
 use ::std::slice::from_raw_parts;
+#[cfg(feature = "gdb-jit-interface")] use ::std::sync::Mutex;
+#[cfg(feature = "gdb-jit-interface")] use ::std::sync::MutexGuard;
+use ::std::sync::OnceLock;
+#[cfg(feature = "gdb-jit-interface")] use ::std::sync::atomic::compiler_fence;
+#[cfg(feature = "gdb-jit-interface")] use ::std::sync::atomic::Ordering;
+
 
+/// Decoding and Intel syntax disassembly of emitted machine code.
+pub mod decoder;
 
 /// Mnemonic parameter types.
 pub mod mnemonic_parameter_types;
 
+/// Operand-polymorphic mnemonics: with this module's traits in scope, `instruction_stream.mov(RAX, RBX)` calls `mov_Register64Bit_Register64Bit_rm64_r64()`, and so on for every instruction whose operands all have parameter types.
+///
+/// Each trait is implemented for `InstructionStream` once per valid combination of operand types, so an invalid combination fails to type check; calls are resolved at compile time and inlined.
+///
+/// Where a mnemonic has forms with differing numbers of operands, the method named after the mnemonic takes the fewest; forms adding a trailing `Masking` or write mask `OpmaskRegister` have a `_masked` suffix, those adding a `BranchHint` have a `_hinted` suffix and any others have the number of operands as a suffix (eg `imul2()` and `imul3()`). The number of operands is also a suffix when the mnemonic's name is taken by a form without operands (eg `ret1()` and `movsd2()`).
+///
+/// Where more than one encoding exists for the same operand types, the shortest is used, and jumps to a `Label` use 32-bit displacements (see `InstructionStream.relax_jumps_on_finish()`); `jecxz()` and `jrcxz()` only have 8-bit displacements, so return a `ShortJmpResult`.
+///
+/// Forms with implied operands (eg `adc_AL_Immediate8Bit()` or the string instructions) are only available as `InstructionStream` methods.
+///
+/// The conditional mnemonics also have forms taking a `ConditionCode` as their first operand, `jcc()`, `setcc()` and `cmovcc()`, so that a condition can be kept as data (and, say, inverted).
+///
+/// ```
+/// use assembler::*;
+/// use assembler::mnemonic_parameter_types::immediates::*;
+/// use assembler::mnemonic_parameter_types::registers::Register64Bit::*;
+/// use assembler::mnemonics::*;
+///
+/// let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).unwrap();
+/// let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+/// instruction_stream.mov(RAX, RDI);
+/// instruction_stream.add(RAX, Immediate8Bit(1));
+/// instruction_stream.ret();
+/// ```
+///
+/// ```compile_fail
+/// # use assembler::*;
+/// # use assembler::mnemonic_parameter_types::immediates::*;
+/// # use assembler::mnemonics::*;
+/// # let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).unwrap();
+/// # let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+/// // `MOV` can not have an immediate as its destination.
+/// instruction_stream.mov(Immediate8Bit(1), Immediate8Bit(2));
+/// ```
+pub mod mnemonics;
+
+/// Support for profiling generated code with Linux `perf`, using perf map and jitdump files.
+#[cfg(any(target_os = "android", target_os = "linux"))]
+pub mod profiling;
+
 #[cfg(test)]
 mod tests;
 
 
+include!("AssemblerError.rs");
+include!("BranchRelaxation.rs");
 include!("ByteEmitter.rs");
+include!("CallFrameInformation.rs");
+include!("CallFrameInstruction.rs");
+include!("CodeHeap.rs");
+include!("CodeHeapAllocation.rs");
+include!("CodeHeapFreeBlocks.rs");
+include!("CodeHeapStatistics.rs");
+include!("ConstantPool.rs");
 include!("Displacement.rs");
+include!("EhFrameRegistration.rs");
+include!("ElfFile.rs");
+include!("ElfRelocatableObject.rs");
+include!("ElfRelocatableObjectError.rs");
+include!("ElfSectionHeader.rs");
+include!("ElfStringTable.rs");
+include!("ElfSymbol.rs");
 include!("ExecutableAnonymousMemoryMap.rs");
 include!("ExecutableAnonymousMemoryMapCreationError.rs");
+include!("FinishedCode.rs");
+#[cfg(feature = "gdb-jit-interface")] include!("GdbJitRegistration.rs");
 include!("InstructionPointer.rs");
 include!("InstructionPointerValidity.rs");
 include!("InstructionStream.rs");
 include!("InstructionStreamHints.rs");
 include!("LabelledLocations.rs");
+include!("LoopLabels.rs");
 include!("NearJmpResult.rs");
 include!("ShortJmpResult.rs");
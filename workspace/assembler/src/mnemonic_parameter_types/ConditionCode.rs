@@ -0,0 +1,185 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A condition code, tested against the flags by `Jcc`, `SETcc` and `CMOVcc` instructions.
+///
+/// The value is the condition's encoding (`tttn`), the low 4 bits of the opcode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum ConditionCode
+{
+	/// Overflow (`OF=1`).
+	Overflow = 0x0,
+	
+	/// Not overflow (`OF=0`).
+	NotOverflow = 0x1,
+	
+	/// Unsigned below (`CF=1`); also known as carry.
+	Below = 0x2,
+	
+	/// Unsigned above or equal (`CF=0`); also known as not carry.
+	AboveOrEqual = 0x3,
+	
+	/// Equal (`ZF=1`); also known as zero.
+	Equal = 0x4,
+	
+	/// Not equal (`ZF=0`); also known as not zero.
+	NotEqual = 0x5,
+	
+	/// Unsigned below or equal (`CF=1 or ZF=1`).
+	BelowOrEqual = 0x6,
+	
+	/// Unsigned above (`CF=0 and ZF=0`).
+	Above = 0x7,
+	
+	/// Sign (`SF=1`).
+	Sign = 0x8,
+	
+	/// Not sign (`SF=0`).
+	NotSign = 0x9,
+	
+	/// Parity even (`PF=1`).
+	Parity = 0xA,
+	
+	/// Parity odd (`PF=0`).
+	NotParity = 0xB,
+	
+	/// Signed less (`SF≠OF`).
+	Less = 0xC,
+	
+	/// Signed greater or equal (`SF=OF`).
+	GreaterOrEqual = 0xD,
+	
+	/// Signed less or equal (`ZF=1 or SF≠OF`).
+	LessOrEqual = 0xE,
+	
+	/// Signed greater (`ZF=0 and SF=OF`).
+	Greater = 0xF,
+}
+
+impl ConditionCode
+{
+	/// Carry (`CF=1`); the same as `Below`.
+	pub const Carry: Self = ConditionCode::Below;
+	
+	/// Not carry (`CF=0`); the same as `AboveOrEqual`.
+	pub const NotCarry: Self = ConditionCode::AboveOrEqual;
+	
+	/// Zero (`ZF=1`); the same as `Equal`.
+	pub const Zero: Self = ConditionCode::Equal;
+	
+	/// Not zero (`ZF=0`); the same as `NotEqual`.
+	pub const NotZero: Self = ConditionCode::NotEqual;
+	
+	/// Parity even (`PF=1`); the same as `Parity`.
+	pub const ParityEven: Self = ConditionCode::Parity;
+	
+	/// Parity odd (`PF=0`); the same as `NotParity`.
+	pub const ParityOdd: Self = ConditionCode::NotParity;
+	
+	/// After `CMP left, right`, holds if `left < right`, comparing as signed (`Less`) or unsigned (`Below`) integers.
+	#[inline(always)]
+	pub fn less_than(signed: bool) -> Self
+	{
+		if signed
+		{
+			ConditionCode::Less
+		}
+		else
+		{
+			ConditionCode::Below
+		}
+	}
+	
+	/// After `CMP left, right`, holds if `left <= right`, comparing as signed (`LessOrEqual`) or unsigned (`BelowOrEqual`) integers.
+	#[inline(always)]
+	pub fn less_than_or_equal(signed: bool) -> Self
+	{
+		if signed
+		{
+			ConditionCode::LessOrEqual
+		}
+		else
+		{
+			ConditionCode::BelowOrEqual
+		}
+	}
+	
+	/// After `CMP left, right`, holds if `left > right`, comparing as signed (`Greater`) or unsigned (`Above`) integers.
+	#[inline(always)]
+	pub fn greater_than(signed: bool) -> Self
+	{
+		if signed
+		{
+			ConditionCode::Greater
+		}
+		else
+		{
+			ConditionCode::Above
+		}
+	}
+	
+	/// After `CMP left, right`, holds if `left >= right`, comparing as signed (`GreaterOrEqual`) or unsigned (`AboveOrEqual`) integers.
+	#[inline(always)]
+	pub fn greater_than_or_equal(signed: bool) -> Self
+	{
+		if signed
+		{
+			ConditionCode::GreaterOrEqual
+		}
+		else
+		{
+			ConditionCode::AboveOrEqual
+		}
+	}
+	
+	/// Is this a signed comparison (`Less`, `GreaterOrEqual`, `LessOrEqual` or `Greater`)?
+	#[inline(always)]
+	pub fn is_signed(self) -> bool
+	{
+		self >= ConditionCode::Less
+	}
+	
+	/// Is this an unsigned comparison (`Below`, `AboveOrEqual`, `BelowOrEqual` or `Above`)?
+	#[inline(always)]
+	pub fn is_unsigned(self) -> bool
+	{
+		use self::ConditionCode::*;
+		
+		matches!(self, Below | AboveOrEqual | BelowOrEqual | Above)
+	}
+	
+	/// The condition that is true when this one is false (eg `Equal` becomes `NotEqual`).
+	///
+	/// Useful for laying out code so that the common case falls through.
+	#[inline(always)]
+	pub fn invert(self) -> Self
+	{
+		unsafe { transmute(self as u8 ^ 0x1) }
+	}
+	
+	/// The condition that holds after `CMP right, left` when this one holds after `CMP left, right` (eg `Less` becomes `Greater`).
+	///
+	/// Returns `None` for conditions that do not describe the order of the operands (`Overflow`, `NotOverflow`, `Sign`, `NotSign`, `Parity` and `NotParity`).
+	#[inline(always)]
+	pub fn swap_operands(self) -> Option<Self>
+	{
+		use self::ConditionCode::*;
+		
+		match self
+		{
+			Equal => Some(Equal),
+			NotEqual => Some(NotEqual),
+			Below => Some(Above),
+			Above => Some(Below),
+			AboveOrEqual => Some(BelowOrEqual),
+			BelowOrEqual => Some(AboveOrEqual),
+			Less => Some(Greater),
+			Greater => Some(Less),
+			GreaterOrEqual => Some(LessOrEqual),
+			LessOrEqual => Some(GreaterOrEqual),
+			Overflow | NotOverflow | Sign | NotSign | Parity | NotParity => None,
+		}
+	}
+}
@@ -0,0 +1,99 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Masking applied to the destination of an `EVEX` encoded (AVX-512) mnemonic using an opmask register.
+///
+/// With merging-masking, elements of the destination whose corresponding bit in the opmask register is zero are preserved.
+///
+/// With zeroing-masking, elements of the destination whose corresponding bit in the opmask register is zero are set to zero.
+///
+/// Zeroing-masking is not permitted when the destination is memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Masking
+{
+	opmask_register: OpmaskRegister,
+	zeroing: bool,
+}
+
+impl Default for Masking
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		Self::Unmasked
+	}
+}
+
+impl Masking
+{
+	/// No masking (encoded as a writemask of `K0`).
+	pub const Unmasked: Self = Masking
+	{
+		opmask_register: OpmaskRegister::K0,
+		zeroing: false,
+	};
+	
+	/// Merging-masking using `opmask_register`.
+	///
+	/// Panics if `opmask_register` is `K0`, as that would leave the instruction unmasked.
+	#[inline(always)]
+	pub fn merging(opmask_register: OpmaskRegister) -> Self
+	{
+		assert_ne!(opmask_register, OpmaskRegister::K0, "K0 can not be used as a writemask");
+		
+		Self
+		{
+			opmask_register,
+			zeroing: false,
+		}
+	}
+	
+	/// Zeroing-masking using `opmask_register`.
+	///
+	/// Panics if `opmask_register` is `K0`, as the instruction would be invalid (`#UD`).
+	#[inline(always)]
+	pub fn zeroing(opmask_register: OpmaskRegister) -> Self
+	{
+		assert_ne!(opmask_register, OpmaskRegister::K0, "K0 can not be used as a writemask");
+		
+		Self
+		{
+			opmask_register,
+			zeroing: true,
+		}
+	}
+	
+	/// The opmask register; `K0` if unmasked.
+	#[inline(always)]
+	pub fn opmask_register(self) -> OpmaskRegister
+	{
+		self.opmask_register
+	}
+	
+	/// Is this zeroing-masking?
+	#[inline(always)]
+	pub fn is_zeroing(self) -> bool
+	{
+		self.zeroing
+	}
+	
+	#[inline(always)]
+	pub(crate) fn z(self) -> u8
+	{
+		if self.zeroing
+		{
+			0x80
+		}
+		else
+		{
+			0x00
+		}
+	}
+	
+	#[inline(always)]
+	pub(crate) fn aaa(self) -> u8
+	{
+		self.opmask_register.index()
+	}
+}
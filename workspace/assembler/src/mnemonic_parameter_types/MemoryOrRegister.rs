@@ -3,12 +3,22 @@
 
 
 /// A memory indirect address or a register.
-pub(crate) trait MemoryOrRegister: Sized
+pub(crate) trait MemoryOrRegister: Copy + Sized
 {
 	/// Emits Mod.R/B, Scaled Index Byte and related data.
 	#[inline(always)]
 	fn emit_mod_rm_sib(self, byte_emitter: &mut ByteEmitter, reg: impl Register);
 	
+	/// Emits Mod.R/B, Scaled Index Byte and related data, using a compressed 8-bit displacement (`disp8*N`) if possible (`EVEX` only).
+	fn emit_mod_rm_sib_compressed_displacement(self, byte_emitter: &mut ByteEmitter, reg: impl Register, N: u8);
+	
+	/// The label that a `RIP+displacement` memory operand is relative to, if any.
+	#[inline(always)]
+	fn relative_instruction_pointer_label(self) -> Option<Label>
+	{
+		None
+	}
+	
 	/// Emits REX prefix.
 	#[inline(always)]
 	fn emit_rex_3(self, byte_emitter: &mut ByteEmitter, r: impl Register, byte: u8);
@@ -20,4 +30,7 @@ pub(crate) trait MemoryOrRegister: Sized
 	/// Emits VEX prefix.
 	#[inline(always)]
 	fn emit_vex_prefix(self, byte_emitter: &mut ByteEmitter, mmmmm: u8, L: u8, pp: u8, w: u8, vvvv: impl Register, r: impl Register);
+	
+	/// Emits EVEX prefix.
+	fn emit_evex_prefix(self, byte_emitter: &mut ByteEmitter, mm: u8, LL: u8, pp: u8, w: u8, vvvv: impl Register, r: impl Register, masking: Masking);
 }
@@ -0,0 +1,30 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// A reference to a constant in an instruction stream's constant pool.
+///
+/// Created using `InstructionStream.constant_u128()` and friends; converts into any memory operand (eg `Any128BitMemory`) using `RIP+displacement` addressing, the displacement being fixed up when `InstructionStream.finish()` is called.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ConstantRef(pub(crate) Label);
+
+impl From<ConstantRef> for MemoryOperand
+{
+	#[inline(always)]
+	fn from(constant: ConstantRef) -> Self
+	{
+		MemoryOperand::relative_instruction_pointer_relative_label(constant.0)
+	}
+}
+
+impl ConstantRef
+{
+	/// The label of the constant's location.
+	///
+	/// Only resolved once the constant pool has been flushed.
+	#[inline(always)]
+	pub fn label(self) -> Label
+	{
+		self.0
+	}
+}
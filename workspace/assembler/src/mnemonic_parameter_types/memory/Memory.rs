@@ -13,6 +13,16 @@ pub trait Memory: From<MemoryOperand> + Into<MemoryOperand> + Sized
 	#[inline(always)]
 	fn relative_instruction_pointer_relative_displacement(displacement: Immediate32Bit) -> Self;
 	
+	/// Create a new memory operand using the `RIP+displacement` (relative instruction pointer) form, where the displacement is that of `label`.
+	///
+	/// The displacement is resolved when the instruction is emitted if `label` has already been attached (and branch relaxation is not in use), otherwise by `InstructionStream.finish()`; it is an error for `label` to be unattached then.
+	fn relative_instruction_pointer_relative_label(label: Label) -> Self;
+	
+	/// Create a new memory operand using the `RIP+displacement` (relative instruction pointer) form, where the displacement is that of `label` plus `addend`.
+	///
+	/// See `relative_instruction_pointer_relative_label()`.
+	fn relative_instruction_pointer_relative_label_displacement(label: Label, addend: Immediate32Bit) -> Self;
+	
 	/// Create a new memory operand using the `displacement` form.
 	#[inline(always)]
 	fn displacement(displacement: Immediate32Bit) -> Self;
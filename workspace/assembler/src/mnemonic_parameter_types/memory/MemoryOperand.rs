@@ -12,7 +12,8 @@ pub struct MemoryOperand
 	index_scale: IndexScale,
 	segment_register: Option<SegmentRegister>,
 	pub(crate) address_override_for_32_bit: bool,
-	relative_instruction_pointer_offset: bool
+	relative_instruction_pointer_offset: bool,
+	relative_instruction_pointer_label: Option<Label>,
 }
 
 impl PrefixGroup2 for MemoryOperand
@@ -31,6 +32,13 @@ impl MemoryOrRegister for MemoryOperand
 {
 	#[inline(always)]
 	fn emit_mod_rm_sib(self, byte_emitter: &mut ByteEmitter, reg: impl Register)
+	{
+		const Uncompressed: u8 = 1;
+		self.emit_mod_rm_sib_compressed_displacement(byte_emitter, reg, Uncompressed)
+	}
+	
+	#[inline(always)]
+	fn emit_mod_rm_sib_compressed_displacement(self, byte_emitter: &mut ByteEmitter, reg: impl Register, N: u8)
 	{
 		let rrr = Self::rrr(reg);
 		
@@ -40,7 +48,7 @@ impl MemoryOrRegister for MemoryOperand
 		}
 		else if self.has_base_register()
 		{
-			self.emit_mod_rm_sib_for_all_other_addressing_modes(byte_emitter, rrr)
+			self.emit_mod_rm_sib_for_all_other_addressing_modes(byte_emitter, rrr, N)
 		}
 		else
 		{
@@ -48,9 +56,17 @@ impl MemoryOrRegister for MemoryOperand
 		}
 	}
 	
+	#[inline(always)]
+	fn relative_instruction_pointer_label(self) -> Option<Label>
+	{
+		self.relative_instruction_pointer_label
+	}
+	
 	#[inline(always)]
 	fn emit_rex_3(self, byte_emitter: &mut ByteEmitter, r: impl Register, mut byte: u8)
 	{
+		assert!(r.requires_only_legacy_encoding(), "Registers 16 to 31 can only be encoded using EVEX");
+		
 		byte |= if r.requires_rex_byte()
 		{
 			InstructionStream::REX
@@ -103,6 +119,8 @@ impl MemoryOrRegister for MemoryOperand
 	{
 		let rm = self;
 		
+		assert!(r.requires_only_legacy_encoding() && vvvv.requires_only_legacy_encoding(), "Registers 16 to 31 can only be encoded using EVEX");
+		
 		let r_bit = (!r.index() << 4) & 0x80;
 		let x_bit = if rm.has_index_register()
 		{
@@ -131,6 +149,34 @@ impl MemoryOrRegister for MemoryOperand
 			byte_emitter.emit_3_byte_vex_prefix(r_bit, x_bit, b_bit, mmmmm, W, vvvv, L, pp)
 		}
 	}
+	
+	#[inline(always)]
+	fn emit_evex_prefix(self, byte_emitter: &mut ByteEmitter, mm: u8, LL: u8, pp: u8, W: u8, vvvv: impl Register, r: impl Register, masking: Masking)
+	{
+		let rm = self;
+		
+		let r_bit = (!r.index() << 4) & 0x80;
+		let x_bit = if rm.has_index_register()
+		{
+			(!rm.get_index_register_index() << 3) & 0x40
+		}
+		else
+		{
+			0x40
+		};
+		
+		let b_bit = if rm.has_base_register()
+		{
+			(!rm.get_base_register_index() << 2) & 0x20
+		}
+		else
+		{
+			0x20
+		};
+		let r_prime_bit = (!r.index()) & 0x10;
+		
+		byte_emitter.emit_4_byte_evex_prefix(r_bit, x_bit, b_bit, r_prime_bit, mm, W, vvvv, pp, masking.z(), LL, 0x00, masking.aaa())
+	}
 }
 
 impl Memory for MemoryOperand
@@ -147,6 +193,20 @@ impl Memory for MemoryOperand
 		Self::new(displacement, Self::NoBaseOrIndexRegister, Self::NoBaseOrIndexRegister, IndexScale::x1, Self::NoSegmentRegister, false, true)
 	}
 	
+	#[inline(always)]
+	fn relative_instruction_pointer_relative_label(label: Label) -> Self
+	{
+		Self::relative_instruction_pointer_relative_label_displacement(label, Immediate32Bit(0))
+	}
+	
+	#[inline(always)]
+	fn relative_instruction_pointer_relative_label_displacement(label: Label, addend: Immediate32Bit) -> Self
+	{
+		let mut memory_operand = Self::relative_instruction_pointer_relative_displacement(addend);
+		memory_operand.relative_instruction_pointer_label = Some(label);
+		memory_operand
+	}
+	
 	#[inline(always)]
 	fn displacement(displacement: Immediate32Bit) -> Self
 	{
@@ -411,6 +471,29 @@ impl MemoryOperand
 			segment_register,
 			address_override_for_32_bit,
 			relative_instruction_pointer_offset,
+			relative_instruction_pointer_label: None,
+		}
+	}
+
+	
+	/// The index register is a vector register; only its number is encoded (in the scaled index byte, SIB, and the `X` bit of the `VEX` prefix).
+	///
+	/// Panics if the index register is 16 to 31, as there are no `EVEX` encoded gathers.
+	#[inline(always)]
+	fn vector_sib(displacement: Immediate32Bit, base_register: Option<impl GeneralPurposeRegister>, index_register: impl Register, index_scale: IndexScale, segment_register: Option<SegmentRegister>, address_override_for_32_bit: bool) -> Self
+	{
+		assert!(index_register.requires_only_legacy_encoding(), "VSIB index registers 16 to 31 can only be encoded using EVEX");
+		
+		Self
+		{
+			displacement,
+			base_register: base_register.map(|register| unsafe { transmute(register.index()) }),
+			index_register: Some(unsafe { transmute(index_register.index() & 0x0F) }),
+			index_scale,
+			segment_register,
+			address_override_for_32_bit,
+			relative_instruction_pointer_offset: false,
+			relative_instruction_pointer_label: None,
 		}
 	}
 }
@@ -472,7 +555,7 @@ impl MemoryOperand
 	}
 	
 	#[inline(always)]
-	fn emit_mod_rm_sib_for_all_other_addressing_modes(self, byte_emitter: &mut ByteEmitter, reg: u8)
+	fn emit_mod_rm_sib_for_all_other_addressing_modes(self, byte_emitter: &mut ByteEmitter, reg: u8, N: u8)
 	{
 		let bbb = self.get_base_register().index_truncated_to_lowest_3_bits();
 		
@@ -483,7 +566,7 @@ impl MemoryOperand
 			bbb == MemoryOperand::RegisterRspOrR12
 		}
 		
-		let (displacement, mod_) = self.displacement_and_mod(bbb);
+		let (displacement, mod_) = self.displacement_and_mod(bbb, N);
 		
 		if self.has_index_register()
 		{
@@ -581,8 +664,11 @@ impl MemoryOperand
 		(index_scale << ScaleShift) & ScaleBitsMask
 	}
 	
+	/// `N` is the scale of a compressed 8-bit displacement (`disp8*N`), as used by `EVEX` encoded instructions; it is `1` for all other instructions.
+	///
+	/// The displacement returned is already scaled down if a compressed 8-bit displacement is used.
 	#[inline(always)]
-	fn displacement_and_mod(self, bbb: u8) -> (i32, u8)
+	fn displacement_and_mod(self, bbb: u8, N: u8) -> (i32, u8)
 	{
 		#[inline(always)]
 		fn bbb_is_not_RBP_or_R13(bbb: u8) -> bool
@@ -593,19 +679,20 @@ impl MemoryOperand
 		// This logic determines what the value of the mod bits will be.
 		// It also controls how many immediate bytes we emit later.
 		let displacement = self.get_displacement();
-		let mod_ = if displacement < -128 || displacement > 127
+		let N = N as i32;
+		let compressed_displacement = displacement / N;
+		if displacement == 0 && bbb_is_not_RBP_or_R13(bbb)
 		{
-			Self::Mod_0b10
+			(displacement, Self::Mod_0b00)
 		}
-		else if displacement == 0 && bbb_is_not_RBP_or_R13(bbb)
+		else if displacement % N == 0 && compressed_displacement >= -128 && compressed_displacement <= 127
 		{
-			Self::Mod_0b00
+			(compressed_displacement, Self::Mod_0b01)
 		}
 		else
 		{
-			Self::Mod_0b01
-		};
-		(displacement, mod_)
+			(displacement, Self::Mod_0b10)
+		}
 	}
 	
 	#[inline(always)]
@@ -0,0 +1,39 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Vector scaled index byte (VSIB) memory, as used by the gather instructions.
+///
+/// The index is a vector register; each of its elements is scaled, added to the base register (if any) and the displacement to produce a separate address.
+///
+/// There is no form without an index register and no form relative to the instruction pointer (`RIP`).
+pub trait VectorSibMemory: Sized
+{
+	/// The type of vector register holding the indices.
+	type IndexRegister: Register;
+	
+	/// Create a new memory operand using the `index*scale` form.
+	fn index_scale(index: Self::IndexRegister, scale: IndexScale) -> Self;
+	
+	/// Create a new memory operand using the `index*scale+displacement` form.
+	fn index_scale_displacement(index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self;
+	
+	/// Create a new memory operand using the `base+index*scale` form.
+	fn base_64_index_scale(base_64: Register64Bit, index: Self::IndexRegister, scale: IndexScale) -> Self;
+	
+	/// Create a new memory operand using the `base+index*scale+displacement` form.
+	fn base_64_index_scale_displacement(base_64: Register64Bit, index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self;
+	
+	/// Create a new memory operand using the `base+index*scale` form.
+	///
+	/// Addresses are truncated to 32 bits.
+	fn base_32_index_scale(base_32: Register32Bit, index: Self::IndexRegister, scale: IndexScale) -> Self;
+	
+	/// Create a new memory operand using the `base+index*scale+displacement` form.
+	///
+	/// Addresses are truncated to 32 bits.
+	fn base_32_index_scale_displacement(base_32: Register32Bit, index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self;
+	
+	/// Create a new memory operand using the `segment:base+index*scale+displacement` form.
+	fn segment_base_64_index_scale_displacement(segment_register: SegmentRegister, base_64: Register64Bit, index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self;
+}
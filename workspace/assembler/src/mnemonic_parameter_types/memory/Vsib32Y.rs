@@ -0,0 +1,5 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+impl_vector_sib_memory!(Vsib32Y, YMMRegister, "A vector scaled index byte (VSIB) memory operand with 32-bit (doubleword) indices held in a YMM register (`vm32y` in the Intel manuals).");
@@ -19,6 +19,15 @@ macro_rules! impl_memory
 			}
 		}
 		
+		impl From<ConstantRef> for $struct_name
+		{
+			#[inline(always)]
+			fn from(constant: ConstantRef) -> Self
+			{
+				$struct_name(constant.into())
+			}
+		}
+		
 		impl Into<MemoryOperand> for $struct_name
 		{
 			#[inline(always)]
@@ -60,6 +69,18 @@ macro_rules! impl_memory
 				$struct_name(MemoryOperand::relative_instruction_pointer_relative_displacement(displacement))
 			}
 			
+			#[inline(always)]
+			fn relative_instruction_pointer_relative_label(label: Label) -> Self
+			{
+				$struct_name(MemoryOperand::relative_instruction_pointer_relative_label(label))
+			}
+			
+			#[inline(always)]
+			fn relative_instruction_pointer_relative_label_displacement(label: Label, addend: Immediate32Bit) -> Self
+			{
+				$struct_name(MemoryOperand::relative_instruction_pointer_relative_label_displacement(label, addend))
+			}
+			
 			#[inline(always)]
 			fn segment_relative_instruction_pointer_relative_displacement(segment_register: SegmentRegister, displacement: Immediate32Bit) -> Self
 			{
@@ -252,6 +273,18 @@ macro_rules! impl_memory
 				self.memory_operand().emit_mod_rm_sib(byte_emitter, reg)
 			}
 			
+			#[inline(always)]
+			fn emit_mod_rm_sib_compressed_displacement(self, byte_emitter: &mut ByteEmitter, reg: impl Register, N: u8)
+			{
+				self.memory_operand().emit_mod_rm_sib_compressed_displacement(byte_emitter, reg, N)
+			}
+			
+			#[inline(always)]
+			fn relative_instruction_pointer_label(self) -> Option<Label>
+			{
+				self.memory_operand().relative_instruction_pointer_label()
+			}
+			
 			#[inline(always)]
 			fn emit_rex_3(self, byte_emitter: &mut ByteEmitter, r: impl Register, byte: u8)
 			{
@@ -269,6 +302,12 @@ macro_rules! impl_memory
 			{
 				self.memory_operand().emit_vex_prefix(byte_emitter, mmmmm, L, pp, w, vvvv, r)
 			}
+			
+			#[inline(always)]
+			fn emit_evex_prefix(self, byte_emitter: &mut ByteEmitter, mm: u8, LL: u8, pp: u8, w: u8, vvvv: impl Register, r: impl Register, masking: Masking)
+			{
+				self.memory_operand().emit_evex_prefix(byte_emitter, mm, LL, pp, w, vvvv, r, masking)
+			}
 		}
 		
 		impl PrefixGroup2 for $struct_name
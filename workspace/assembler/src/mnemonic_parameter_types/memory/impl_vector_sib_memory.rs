@@ -0,0 +1,128 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+macro_rules! impl_vector_sib_memory
+{
+	($struct_name: tt, $index_register: tt, $doc: expr) =>
+	{
+		#[doc = $doc]
+		///
+		/// Create instances using the methods of the trait `VectorSibMemory`.
+		#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+		pub struct $struct_name(MemoryOperand);
+		
+		impl VectorSibMemory for $struct_name
+		{
+			type IndexRegister = $index_register;
+			
+			#[inline(always)]
+			fn index_scale(index: Self::IndexRegister, scale: IndexScale) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(Immediate32Bit(0), MemoryOperand::NoBaseOrIndexRegister, index, scale, MemoryOperand::NoSegmentRegister, false))
+			}
+			
+			#[inline(always)]
+			fn index_scale_displacement(index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(displacement, MemoryOperand::NoBaseOrIndexRegister, index, scale, MemoryOperand::NoSegmentRegister, false))
+			}
+			
+			#[inline(always)]
+			fn base_64_index_scale(base_64: Register64Bit, index: Self::IndexRegister, scale: IndexScale) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(Immediate32Bit(0), Some(base_64), index, scale, MemoryOperand::NoSegmentRegister, false))
+			}
+			
+			#[inline(always)]
+			fn base_64_index_scale_displacement(base_64: Register64Bit, index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(displacement, Some(base_64), index, scale, MemoryOperand::NoSegmentRegister, false))
+			}
+			
+			#[inline(always)]
+			fn base_32_index_scale(base_32: Register32Bit, index: Self::IndexRegister, scale: IndexScale) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(Immediate32Bit(0), Some(base_32), index, scale, MemoryOperand::NoSegmentRegister, true))
+			}
+			
+			#[inline(always)]
+			fn base_32_index_scale_displacement(base_32: Register32Bit, index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(displacement, Some(base_32), index, scale, MemoryOperand::NoSegmentRegister, true))
+			}
+			
+			#[inline(always)]
+			fn segment_base_64_index_scale_displacement(segment_register: SegmentRegister, base_64: Register64Bit, index: Self::IndexRegister, scale: IndexScale, displacement: Immediate32Bit) -> Self
+			{
+				$struct_name(MemoryOperand::vector_sib(displacement, Some(base_64), index, scale, Some(segment_register), false))
+			}
+		}
+		
+		impl From<$struct_name> for MemoryOperand
+		{
+			#[inline(always)]
+			fn from(vector_sib_memory: $struct_name) -> Self
+			{
+				vector_sib_memory.0
+			}
+		}
+		
+		impl HasMemoryOperand for $struct_name
+		{
+			#[inline(always)]
+			fn memory_operand(self) -> MemoryOperand
+			{
+				self.0
+			}
+		}
+		
+		impl MemoryOrRegister for $struct_name
+		{
+			#[inline(always)]
+			fn emit_mod_rm_sib(self, byte_emitter: &mut ByteEmitter, reg: impl Register)
+			{
+				self.memory_operand().emit_mod_rm_sib(byte_emitter, reg)
+			}
+			
+			#[inline(always)]
+			fn emit_mod_rm_sib_compressed_displacement(self, byte_emitter: &mut ByteEmitter, reg: impl Register, N: u8)
+			{
+				self.memory_operand().emit_mod_rm_sib_compressed_displacement(byte_emitter, reg, N)
+			}
+			
+			#[inline(always)]
+			fn emit_rex_3(self, byte_emitter: &mut ByteEmitter, r: impl Register, byte: u8)
+			{
+				self.memory_operand().emit_rex_3(byte_emitter, r, byte)
+			}
+			
+			#[inline(always)]
+			fn emit_rex_2(self, byte_emitter: &mut ByteEmitter, byte: u8)
+			{
+				self.memory_operand().emit_rex_2(byte_emitter, byte)
+			}
+			
+			#[inline(always)]
+			fn emit_vex_prefix(self, byte_emitter: &mut ByteEmitter, mmmmm: u8, L: u8, pp: u8, w: u8, vvvv: impl Register, r: impl Register)
+			{
+				self.memory_operand().emit_vex_prefix(byte_emitter, mmmmm, L, pp, w, vvvv, r)
+			}
+			
+			#[inline(always)]
+			fn emit_evex_prefix(self, byte_emitter: &mut ByteEmitter, mm: u8, LL: u8, pp: u8, w: u8, vvvv: impl Register, r: impl Register, masking: Masking)
+			{
+				self.memory_operand().emit_evex_prefix(byte_emitter, mm, LL, pp, w, vvvv, r, masking)
+			}
+		}
+		
+		impl PrefixGroup2 for $struct_name
+		{
+			#[inline(always)]
+			fn emit_prefix_group2(self, byte_emitter: &mut ByteEmitter)
+			{
+				self.memory_operand().emit_prefix_group2(byte_emitter)
+			}
+		}
+	}
+}
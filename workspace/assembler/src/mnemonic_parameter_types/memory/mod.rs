@@ -5,6 +5,7 @@
 use super::*;
 
 include!("impl_memory.rs");
+include!("impl_vector_sib_memory.rs");
 
 
 include!("Any8BitMemory.rs");
@@ -13,9 +14,12 @@ include!("Any32BitMemory.rs");
 include!("Any64BitMemory.rs");
 include!("Any128BitMemory.rs");
 include!("Any256BitMemory.rs");
+include!("Any512BitMemory.rs");
 include!("BitMemory.rs");
+include!("ConstantRef.rs");
 include!("Contiguous2ByteMemory.rs");
 include!("Contiguous28ByteMemory.rs");
+include!("Contiguous64ByteMemory.rs");
 include!("Contiguous108ByteMemory.rs");
 include!("Contiguous512ByteMemory.rs");
 include!("FarPointer16BitTo16BitMemory.rs");
@@ -25,6 +29,11 @@ include!("HasMemoryOperand.rs");
 include!("IndexScale.rs");
 include!("Memory.rs");
 include!("MemoryOperand.rs");
+include!("VectorSibMemory.rs");
+include!("Vsib32X.rs");
+include!("Vsib32Y.rs");
+include!("Vsib64X.rs");
+include!("Vsib64Y.rs");
 include!("X87BinaryCodedDecimal80BitMemory.rs");
 include!("X87Float32BitMemory.rs");
 include!("X87Float64BitMemory.rs");
@@ -27,8 +27,10 @@ pub mod relative_addresses;
 
 include!("AsDisplacement.rs");
 include!("BranchHint.rs");
+include!("ConditionCode.rs");
 include!("FunctionPointer.rs");
 include!("Label.rs");
+include!("Masking.rs");
 include!("MemoryOrRegister.rs");
 include!("OpcodeEncoding.rs");
 include!("PrefixGroup2.rs");
@@ -0,0 +1,64 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Opmask registers (also known as `k` registers), used by `EVEX` encoded (AVX-512) mnemonics.
+///
+/// Register 0 (`K0`) can be used as an operand but can not be used as a writemask; when encoded as a writemask it means 'no masking'.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum OpmaskRegister
+{
+	/// Register 0.
+	///
+	/// Encodes 'no masking' when used as a writemask.
+	K0 = 0,
+	
+	/// Register 1.
+	K1 = 1,
+	
+	/// Register 2.
+	K2 = 2,
+	
+	/// Register 3.
+	K3 = 3,
+	
+	/// Register 4.
+	K4 = 4,
+	
+	/// Register 5.
+	K5 = 5,
+	
+	/// Register 6.
+	K6 = 6,
+	
+	/// Register 7.
+	K7 = 7,
+}
+
+impl Default for OpmaskRegister
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		OpmaskRegister::K0
+	}
+}
+
+impl Into<u8> for OpmaskRegister
+{
+	#[inline(always)]
+	fn into(self) -> u8
+	{
+		self as u8
+	}
+}
+
+impl Register for OpmaskRegister
+{
+	#[inline(always)]
+	fn index(self) -> u8
+	{
+		self.into()
+	}
+}
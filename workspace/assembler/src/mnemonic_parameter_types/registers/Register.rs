@@ -26,6 +26,13 @@ pub trait Register: Copy + Sized + Into<u8> + Default
 		self.index() > 7
 	}
 	
+	#[doc(hidden)]
+	#[inline(always)]
+	fn requires_only_legacy_encoding(self) -> bool
+	{
+		self.index() < 16
+	}
+	
 	#[doc(hidden)]
 	#[inline(always)]
 	fn index_truncated_to_lowest_3_bits(self) -> u8
@@ -48,9 +55,17 @@ impl<R: Register> MemoryOrRegister for R
 		byte_emitter.emit_mod_r_m_byte(mod_, rrr, bbb);
 	}
 	
+	#[inline(always)]
+	fn emit_mod_rm_sib_compressed_displacement(self, byte_emitter: &mut ByteEmitter, reg: impl Register, _N: u8)
+	{
+		self.emit_mod_rm_sib(byte_emitter, reg)
+	}
+	
 	#[inline(always)]
 	fn emit_rex_3(self, byte_emitter: &mut ByteEmitter, r: impl Register, mut byte: u8)
 	{
+		assert!(self.requires_only_legacy_encoding() && r.requires_only_legacy_encoding(), "Registers 16 to 31 can only be encoded using EVEX");
+		
 		byte |= if r.requires_rex_byte()
 		{
 			InstructionStream::REX
@@ -77,6 +92,8 @@ impl<R: Register> MemoryOrRegister for R
 	{
 		let rm = self;
 		
+		assert!(rm.requires_only_legacy_encoding(), "Registers 16 to 31 can only be encoded using EVEX");
+		
 		byte |= if rm.requires_rex_byte()
 		{
 			InstructionStream::REX
@@ -103,6 +120,8 @@ impl<R: Register> MemoryOrRegister for R
 	{
 		let rm = self;
 		
+		assert!(rm.requires_only_legacy_encoding() && r.requires_only_legacy_encoding() && vvvv.requires_only_legacy_encoding(), "Registers 16 to 31 can only be encoded using EVEX");
+		
 		let r_bit = (!r.index() << 4) & 0x80;
 		let b_bit = (!rm.index() << 2) & 0x20;
 		
@@ -115,4 +134,17 @@ impl<R: Register> MemoryOrRegister for R
 			byte_emitter.emit_3_byte_vex_prefix(r_bit, 0x40, b_bit, mmmmm, w, vvvv, L, pp)
 		}
 	}
+	
+	#[inline(always)]
+	fn emit_evex_prefix(self, byte_emitter: &mut ByteEmitter, mm: u8, LL: u8, pp: u8, w: u8, vvvv: impl Register, r: impl Register, masking: Masking)
+	{
+		let rm = self;
+		
+		let r_bit = (!r.index() << 4) & 0x80;
+		let x_bit = (!rm.index() << 2) & 0x40;
+		let b_bit = (!rm.index() << 2) & 0x20;
+		let r_prime_bit = (!r.index()) & 0x10;
+		
+		byte_emitter.emit_4_byte_evex_prefix(r_bit, x_bit, b_bit, r_prime_bit, mm, w, vvvv, pp, masking.z(), LL, 0x00, masking.aaa())
+	}
 }
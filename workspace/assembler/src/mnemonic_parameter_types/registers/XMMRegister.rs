@@ -3,6 +3,8 @@
 
 
 /// Register 0 (`XMM0`) is defined on this enumeration but optimal encodings of it in combination with mnemonics exist and should be preferred as they increase code density.
+///
+/// Registers 16 to 31 (`XMM16` to `XMM31`) can only be used with `EVEX` encoded (AVX-512) mnemonics; passing them to any other mnemonic panics.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum XMMRegister
@@ -108,6 +110,118 @@ pub enum XMMRegister
 	///
 	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
 	XMM15 = 15,
+	
+	/// Register 16.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM16 = 16,
+	
+	/// Register 17.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM17 = 17,
+	
+	/// Register 18.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM18 = 18,
+	
+	/// Register 19.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM19 = 19,
+	
+	/// Register 20.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM20 = 20,
+	
+	/// Register 21.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM21 = 21,
+	
+	/// Register 22.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM22 = 22,
+	
+	/// Register 23.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM23 = 23,
+	
+	/// Register 24.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM24 = 24,
+	
+	/// Register 25.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM25 = 25,
+	
+	/// Register 26.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM26 = 26,
+	
+	/// Register 27.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM27 = 27,
+	
+	/// Register 28.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM28 = 28,
+	
+	/// Register 29.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM29 = 29,
+	
+	/// Register 30.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM30 = 30,
+	
+	/// Register 31.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	///
+	/// A scratch register (also known as a temporary or (function) caller-saved register) when using the System V Application Binary Interface (ABI) for AMD64.
+	XMM31 = 31,
 }
 
 impl Default for XMMRegister
@@ -157,6 +271,15 @@ impl From<YMMRegister> for XMMRegister
 	}
 }
 
+impl From<ZMMRegister> for XMMRegister
+{
+	#[inline(always)]
+	fn from(value: ZMMRegister) -> Self
+	{
+		unsafe { transmute(value) }
+	}
+}
+
 impl XMMRegister
 {
 	/// Contains the floating-point return value from a function call when using the System V Application Binary Interface (ABI) for AMD64.
@@ -3,6 +3,8 @@
 
 
 /// YMM registers.
+///
+/// Registers 16 to 31 (`YMM16` to `YMM31`) can only be used with `EVEX` encoded (AVX-512) mnemonics; passing them to any other mnemonic panics.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[repr(u8)]
 pub enum YMMRegister
@@ -54,6 +56,86 @@ pub enum YMMRegister
 	
 	/// Register 15.
 	YMM15 = 15,
+	
+	/// Register 16.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM16 = 16,
+	
+	/// Register 17.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM17 = 17,
+	
+	/// Register 18.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM18 = 18,
+	
+	/// Register 19.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM19 = 19,
+	
+	/// Register 20.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM20 = 20,
+	
+	/// Register 21.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM21 = 21,
+	
+	/// Register 22.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM22 = 22,
+	
+	/// Register 23.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM23 = 23,
+	
+	/// Register 24.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM24 = 24,
+	
+	/// Register 25.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM25 = 25,
+	
+	/// Register 26.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM26 = 26,
+	
+	/// Register 27.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM27 = 27,
+	
+	/// Register 28.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM28 = 28,
+	
+	/// Register 29.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM29 = 29,
+	
+	/// Register 30.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM30 = 30,
+	
+	/// Register 31.
+	///
+	/// Can only be encoded using an `EVEX` prefix (AVX-512).
+	YMM31 = 31,
 }
 
 impl Default for YMMRegister
@@ -102,3 +184,12 @@ impl From<XMMRegister> for YMMRegister
 		unsafe { transmute(value) }
 	}
 }
+
+impl From<ZMMRegister> for YMMRegister
+{
+	#[inline(always)]
+	fn from(value: ZMMRegister) -> Self
+	{
+		unsafe { transmute(value) }
+	}
+}
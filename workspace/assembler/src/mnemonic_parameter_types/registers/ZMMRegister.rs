@@ -0,0 +1,154 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// ZMM registers.
+///
+/// All ZMM registers can only be used with `EVEX` encoded (AVX-512) mnemonics.
+///
+/// The lower 256 bits of each register are aliased by the YMM register of the same number, and the lower 128 bits by the XMM register of the same number.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum ZMMRegister
+{
+	/// Register 0.
+	ZMM0 = 0,
+	
+	/// Register 1.
+	ZMM1 = 1,
+	
+	/// Register 2.
+	ZMM2 = 2,
+	
+	/// Register 3.
+	ZMM3 = 3,
+	
+	/// Register 4.
+	ZMM4 = 4,
+	
+	/// Register 5.
+	ZMM5 = 5,
+	
+	/// Register 6.
+	ZMM6 = 6,
+	
+	/// Register 7.
+	ZMM7 = 7,
+	
+	/// Register 8.
+	ZMM8 = 8,
+	
+	/// Register 9.
+	ZMM9 = 9,
+	
+	/// Register 10.
+	ZMM10 = 10,
+	
+	/// Register 11.
+	ZMM11 = 11,
+	
+	/// Register 12.
+	ZMM12 = 12,
+	
+	/// Register 13.
+	ZMM13 = 13,
+	
+	/// Register 14.
+	ZMM14 = 14,
+	
+	/// Register 15.
+	ZMM15 = 15,
+	
+	/// Register 16.
+	ZMM16 = 16,
+	
+	/// Register 17.
+	ZMM17 = 17,
+	
+	/// Register 18.
+	ZMM18 = 18,
+	
+	/// Register 19.
+	ZMM19 = 19,
+	
+	/// Register 20.
+	ZMM20 = 20,
+	
+	/// Register 21.
+	ZMM21 = 21,
+	
+	/// Register 22.
+	ZMM22 = 22,
+	
+	/// Register 23.
+	ZMM23 = 23,
+	
+	/// Register 24.
+	ZMM24 = 24,
+	
+	/// Register 25.
+	ZMM25 = 25,
+	
+	/// Register 26.
+	ZMM26 = 26,
+	
+	/// Register 27.
+	ZMM27 = 27,
+	
+	/// Register 28.
+	ZMM28 = 28,
+	
+	/// Register 29.
+	ZMM29 = 29,
+	
+	/// Register 30.
+	ZMM30 = 30,
+	
+	/// Register 31.
+	ZMM31 = 31,
+}
+
+impl Default for ZMMRegister
+{
+	#[inline(always)]
+	fn default() -> Self
+	{
+		ZMMRegister::ZMM0
+	}
+}
+
+impl Into<u8> for ZMMRegister
+{
+	#[inline(always)]
+	fn into(self) -> u8
+	{
+		self as u8
+	}
+}
+
+impl Register for ZMMRegister
+{
+	#[inline(always)]
+	fn index(self) -> u8
+	{
+		self.into()
+	}
+}
+
+impl From<XMMRegister> for ZMMRegister
+{
+	#[inline(always)]
+	fn from(value: XMMRegister) -> Self
+	{
+		unsafe { transmute(value) }
+	}
+}
+
+impl From<YMMRegister> for ZMMRegister
+{
+	#[inline(always)]
+	fn from(value: YMMRegister) -> Self
+	{
+		unsafe { transmute(value) }
+	}
+}
@@ -8,6 +8,7 @@ use super::*;
 include!("GeneralPurposeRegister.rs");
 include!("LowGeneralPurposeRegister.rs");
 include!("MMRegister.rs");
+include!("OpmaskRegister.rs");
 include!("Register.rs");
 include!("Register8Bit.rs");
 include!("Register16Bit.rs");
@@ -18,3 +19,4 @@ include!("SegmentRegister.rs");
 include!("X87Register.rs");
 include!("XMMRegister.rs");
 include!("YMMRegister.rs");
+include!("ZMMRegister.rs");
@@ -0,0 +1,343 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Operands for `jcc()`.
+pub trait JccOperands<Operand0>
+{
+	/// Emits `Jcc` for `condition_code`, choosing the `InstructionStream` method for the types of the operands; jumps to a `Label` use 32-bit displacements (see `InstructionStream.relax_jumps_on_finish()`).
+	fn jcc(&mut self, condition_code: ConditionCode, operand0: Operand0);
+}
+
+impl<'a> JccOperands<Label> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn jcc(&mut self, condition_code: ConditionCode, operand0: Label)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.jo_Label_1(operand0),
+			NotOverflow => self.jno_Label_1(operand0),
+			Below => self.jb_Label_1(operand0),
+			AboveOrEqual => self.jae_Label_1(operand0),
+			Equal => self.je_Label_1(operand0),
+			NotEqual => self.jne_Label_1(operand0),
+			BelowOrEqual => self.jbe_Label_1(operand0),
+			Above => self.ja_Label_1(operand0),
+			Sign => self.js_Label_1(operand0),
+			NotSign => self.jns_Label_1(operand0),
+			Parity => self.jp_Label_1(operand0),
+			NotParity => self.jnp_Label_1(operand0),
+			Less => self.jl_Label_1(operand0),
+			GreaterOrEqual => self.jge_Label_1(operand0),
+			LessOrEqual => self.jle_Label_1(operand0),
+			Greater => self.jg_Label_1(operand0),
+		}
+	}
+}
+
+impl<'a> JccOperands<RelativeAddress32Bit> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn jcc(&mut self, condition_code: ConditionCode, operand0: RelativeAddress32Bit)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.jo_RelativeAddress32Bit(operand0),
+			NotOverflow => self.jno_RelativeAddress32Bit(operand0),
+			Below => self.jb_RelativeAddress32Bit(operand0),
+			AboveOrEqual => self.jae_RelativeAddress32Bit(operand0),
+			Equal => self.je_RelativeAddress32Bit(operand0),
+			NotEqual => self.jne_RelativeAddress32Bit(operand0),
+			BelowOrEqual => self.jbe_RelativeAddress32Bit(operand0),
+			Above => self.ja_RelativeAddress32Bit(operand0),
+			Sign => self.js_RelativeAddress32Bit(operand0),
+			NotSign => self.jns_RelativeAddress32Bit(operand0),
+			Parity => self.jp_RelativeAddress32Bit(operand0),
+			NotParity => self.jnp_RelativeAddress32Bit(operand0),
+			Less => self.jl_RelativeAddress32Bit(operand0),
+			GreaterOrEqual => self.jge_RelativeAddress32Bit(operand0),
+			LessOrEqual => self.jle_RelativeAddress32Bit(operand0),
+			Greater => self.jg_RelativeAddress32Bit(operand0),
+		}
+	}
+}
+
+/// Operands for `setcc()`.
+pub trait SetccOperands<Operand0>
+{
+	/// Emits `SETcc` for `condition_code`, choosing the `InstructionStream` method for the types of the operands.
+	fn setcc(&mut self, condition_code: ConditionCode, operand0: Operand0);
+}
+
+impl<'a> SetccOperands<Any8BitMemory> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn setcc(&mut self, condition_code: ConditionCode, operand0: Any8BitMemory)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.seto_Any8BitMemory(operand0),
+			NotOverflow => self.setno_Any8BitMemory(operand0),
+			Below => self.setb_Any8BitMemory(operand0),
+			AboveOrEqual => self.setae_Any8BitMemory(operand0),
+			Equal => self.sete_Any8BitMemory(operand0),
+			NotEqual => self.setne_Any8BitMemory(operand0),
+			BelowOrEqual => self.setbe_Any8BitMemory(operand0),
+			Above => self.seta_Any8BitMemory(operand0),
+			Sign => self.sets_Any8BitMemory(operand0),
+			NotSign => self.setns_Any8BitMemory(operand0),
+			Parity => self.setp_Any8BitMemory(operand0),
+			NotParity => self.setnp_Any8BitMemory(operand0),
+			Less => self.setl_Any8BitMemory(operand0),
+			GreaterOrEqual => self.setge_Any8BitMemory(operand0),
+			LessOrEqual => self.setle_Any8BitMemory(operand0),
+			Greater => self.setg_Any8BitMemory(operand0),
+		}
+	}
+}
+
+impl<'a> SetccOperands<Register8Bit> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn setcc(&mut self, condition_code: ConditionCode, operand0: Register8Bit)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.seto_Register8Bit(operand0),
+			NotOverflow => self.setno_Register8Bit(operand0),
+			Below => self.setb_Register8Bit(operand0),
+			AboveOrEqual => self.setae_Register8Bit(operand0),
+			Equal => self.sete_Register8Bit(operand0),
+			NotEqual => self.setne_Register8Bit(operand0),
+			BelowOrEqual => self.setbe_Register8Bit(operand0),
+			Above => self.seta_Register8Bit(operand0),
+			Sign => self.sets_Register8Bit(operand0),
+			NotSign => self.setns_Register8Bit(operand0),
+			Parity => self.setp_Register8Bit(operand0),
+			NotParity => self.setnp_Register8Bit(operand0),
+			Less => self.setl_Register8Bit(operand0),
+			GreaterOrEqual => self.setge_Register8Bit(operand0),
+			LessOrEqual => self.setle_Register8Bit(operand0),
+			Greater => self.setg_Register8Bit(operand0),
+		}
+	}
+}
+
+impl<'a> SetccOperands<RegisterHigh8BitsOf16Bits> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn setcc(&mut self, condition_code: ConditionCode, operand0: RegisterHigh8BitsOf16Bits)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.seto_RegisterHigh8BitsOf16Bits(operand0),
+			NotOverflow => self.setno_RegisterHigh8BitsOf16Bits(operand0),
+			Below => self.setb_RegisterHigh8BitsOf16Bits(operand0),
+			AboveOrEqual => self.setae_RegisterHigh8BitsOf16Bits(operand0),
+			Equal => self.sete_RegisterHigh8BitsOf16Bits(operand0),
+			NotEqual => self.setne_RegisterHigh8BitsOf16Bits(operand0),
+			BelowOrEqual => self.setbe_RegisterHigh8BitsOf16Bits(operand0),
+			Above => self.seta_RegisterHigh8BitsOf16Bits(operand0),
+			Sign => self.sets_RegisterHigh8BitsOf16Bits(operand0),
+			NotSign => self.setns_RegisterHigh8BitsOf16Bits(operand0),
+			Parity => self.setp_RegisterHigh8BitsOf16Bits(operand0),
+			NotParity => self.setnp_RegisterHigh8BitsOf16Bits(operand0),
+			Less => self.setl_RegisterHigh8BitsOf16Bits(operand0),
+			GreaterOrEqual => self.setge_RegisterHigh8BitsOf16Bits(operand0),
+			LessOrEqual => self.setle_RegisterHigh8BitsOf16Bits(operand0),
+			Greater => self.setg_RegisterHigh8BitsOf16Bits(operand0),
+		}
+	}
+}
+
+/// Operands for `cmovcc()`.
+pub trait CmovccOperands<Operand0, Operand1>
+{
+	/// Emits `CMOVcc` for `condition_code`, choosing the `InstructionStream` method for the types of the operands.
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Operand0, operand1: Operand1);
+}
+
+impl<'a> CmovccOperands<Register16Bit, Any16BitMemory> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Register16Bit, operand1: Any16BitMemory)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.cmovo_Register16Bit_Any16BitMemory(operand0, operand1),
+			NotOverflow => self.cmovno_Register16Bit_Any16BitMemory(operand0, operand1),
+			Below => self.cmovb_Register16Bit_Any16BitMemory(operand0, operand1),
+			AboveOrEqual => self.cmovae_Register16Bit_Any16BitMemory(operand0, operand1),
+			Equal => self.cmove_Register16Bit_Any16BitMemory(operand0, operand1),
+			NotEqual => self.cmovne_Register16Bit_Any16BitMemory(operand0, operand1),
+			BelowOrEqual => self.cmovbe_Register16Bit_Any16BitMemory(operand0, operand1),
+			Above => self.cmova_Register16Bit_Any16BitMemory(operand0, operand1),
+			Sign => self.cmovs_Register16Bit_Any16BitMemory(operand0, operand1),
+			NotSign => self.cmovns_Register16Bit_Any16BitMemory(operand0, operand1),
+			Parity => self.cmovp_Register16Bit_Any16BitMemory(operand0, operand1),
+			NotParity => self.cmovnp_Register16Bit_Any16BitMemory(operand0, operand1),
+			Less => self.cmovl_Register16Bit_Any16BitMemory(operand0, operand1),
+			GreaterOrEqual => self.cmovge_Register16Bit_Any16BitMemory(operand0, operand1),
+			LessOrEqual => self.cmovle_Register16Bit_Any16BitMemory(operand0, operand1),
+			Greater => self.cmovg_Register16Bit_Any16BitMemory(operand0, operand1),
+		}
+	}
+}
+
+impl<'a> CmovccOperands<Register16Bit, Register16Bit> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Register16Bit, operand1: Register16Bit)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.cmovo_Register16Bit_Register16Bit(operand0, operand1),
+			NotOverflow => self.cmovno_Register16Bit_Register16Bit(operand0, operand1),
+			Below => self.cmovb_Register16Bit_Register16Bit(operand0, operand1),
+			AboveOrEqual => self.cmovae_Register16Bit_Register16Bit(operand0, operand1),
+			Equal => self.cmove_Register16Bit_Register16Bit(operand0, operand1),
+			NotEqual => self.cmovne_Register16Bit_Register16Bit(operand0, operand1),
+			BelowOrEqual => self.cmovbe_Register16Bit_Register16Bit(operand0, operand1),
+			Above => self.cmova_Register16Bit_Register16Bit(operand0, operand1),
+			Sign => self.cmovs_Register16Bit_Register16Bit(operand0, operand1),
+			NotSign => self.cmovns_Register16Bit_Register16Bit(operand0, operand1),
+			Parity => self.cmovp_Register16Bit_Register16Bit(operand0, operand1),
+			NotParity => self.cmovnp_Register16Bit_Register16Bit(operand0, operand1),
+			Less => self.cmovl_Register16Bit_Register16Bit(operand0, operand1),
+			GreaterOrEqual => self.cmovge_Register16Bit_Register16Bit(operand0, operand1),
+			LessOrEqual => self.cmovle_Register16Bit_Register16Bit(operand0, operand1),
+			Greater => self.cmovg_Register16Bit_Register16Bit(operand0, operand1),
+		}
+	}
+}
+
+impl<'a> CmovccOperands<Register32Bit, Any32BitMemory> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Register32Bit, operand1: Any32BitMemory)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.cmovo_Register32Bit_Any32BitMemory(operand0, operand1),
+			NotOverflow => self.cmovno_Register32Bit_Any32BitMemory(operand0, operand1),
+			Below => self.cmovb_Register32Bit_Any32BitMemory(operand0, operand1),
+			AboveOrEqual => self.cmovae_Register32Bit_Any32BitMemory(operand0, operand1),
+			Equal => self.cmove_Register32Bit_Any32BitMemory(operand0, operand1),
+			NotEqual => self.cmovne_Register32Bit_Any32BitMemory(operand0, operand1),
+			BelowOrEqual => self.cmovbe_Register32Bit_Any32BitMemory(operand0, operand1),
+			Above => self.cmova_Register32Bit_Any32BitMemory(operand0, operand1),
+			Sign => self.cmovs_Register32Bit_Any32BitMemory(operand0, operand1),
+			NotSign => self.cmovns_Register32Bit_Any32BitMemory(operand0, operand1),
+			Parity => self.cmovp_Register32Bit_Any32BitMemory(operand0, operand1),
+			NotParity => self.cmovnp_Register32Bit_Any32BitMemory(operand0, operand1),
+			Less => self.cmovl_Register32Bit_Any32BitMemory(operand0, operand1),
+			GreaterOrEqual => self.cmovge_Register32Bit_Any32BitMemory(operand0, operand1),
+			LessOrEqual => self.cmovle_Register32Bit_Any32BitMemory(operand0, operand1),
+			Greater => self.cmovg_Register32Bit_Any32BitMemory(operand0, operand1),
+		}
+	}
+}
+
+impl<'a> CmovccOperands<Register32Bit, Register32Bit> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Register32Bit, operand1: Register32Bit)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.cmovo_Register32Bit_Register32Bit(operand0, operand1),
+			NotOverflow => self.cmovno_Register32Bit_Register32Bit(operand0, operand1),
+			Below => self.cmovb_Register32Bit_Register32Bit(operand0, operand1),
+			AboveOrEqual => self.cmovae_Register32Bit_Register32Bit(operand0, operand1),
+			Equal => self.cmove_Register32Bit_Register32Bit(operand0, operand1),
+			NotEqual => self.cmovne_Register32Bit_Register32Bit(operand0, operand1),
+			BelowOrEqual => self.cmovbe_Register32Bit_Register32Bit(operand0, operand1),
+			Above => self.cmova_Register32Bit_Register32Bit(operand0, operand1),
+			Sign => self.cmovs_Register32Bit_Register32Bit(operand0, operand1),
+			NotSign => self.cmovns_Register32Bit_Register32Bit(operand0, operand1),
+			Parity => self.cmovp_Register32Bit_Register32Bit(operand0, operand1),
+			NotParity => self.cmovnp_Register32Bit_Register32Bit(operand0, operand1),
+			Less => self.cmovl_Register32Bit_Register32Bit(operand0, operand1),
+			GreaterOrEqual => self.cmovge_Register32Bit_Register32Bit(operand0, operand1),
+			LessOrEqual => self.cmovle_Register32Bit_Register32Bit(operand0, operand1),
+			Greater => self.cmovg_Register32Bit_Register32Bit(operand0, operand1),
+		}
+	}
+}
+
+impl<'a> CmovccOperands<Register64Bit, Any64BitMemory> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Register64Bit, operand1: Any64BitMemory)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.cmovo_Register64Bit_Any64BitMemory(operand0, operand1),
+			NotOverflow => self.cmovno_Register64Bit_Any64BitMemory(operand0, operand1),
+			Below => self.cmovb_Register64Bit_Any64BitMemory(operand0, operand1),
+			AboveOrEqual => self.cmovae_Register64Bit_Any64BitMemory(operand0, operand1),
+			Equal => self.cmove_Register64Bit_Any64BitMemory(operand0, operand1),
+			NotEqual => self.cmovne_Register64Bit_Any64BitMemory(operand0, operand1),
+			BelowOrEqual => self.cmovbe_Register64Bit_Any64BitMemory(operand0, operand1),
+			Above => self.cmova_Register64Bit_Any64BitMemory(operand0, operand1),
+			Sign => self.cmovs_Register64Bit_Any64BitMemory(operand0, operand1),
+			NotSign => self.cmovns_Register64Bit_Any64BitMemory(operand0, operand1),
+			Parity => self.cmovp_Register64Bit_Any64BitMemory(operand0, operand1),
+			NotParity => self.cmovnp_Register64Bit_Any64BitMemory(operand0, operand1),
+			Less => self.cmovl_Register64Bit_Any64BitMemory(operand0, operand1),
+			GreaterOrEqual => self.cmovge_Register64Bit_Any64BitMemory(operand0, operand1),
+			LessOrEqual => self.cmovle_Register64Bit_Any64BitMemory(operand0, operand1),
+			Greater => self.cmovg_Register64Bit_Any64BitMemory(operand0, operand1),
+		}
+	}
+}
+
+impl<'a> CmovccOperands<Register64Bit, Register64Bit> for InstructionStream<'a>
+{
+	#[inline(always)]
+	fn cmovcc(&mut self, condition_code: ConditionCode, operand0: Register64Bit, operand1: Register64Bit)
+	{
+		use self::ConditionCode::*;
+		
+		match condition_code
+		{
+			Overflow => self.cmovo_Register64Bit_Register64Bit(operand0, operand1),
+			NotOverflow => self.cmovno_Register64Bit_Register64Bit(operand0, operand1),
+			Below => self.cmovb_Register64Bit_Register64Bit(operand0, operand1),
+			AboveOrEqual => self.cmovae_Register64Bit_Register64Bit(operand0, operand1),
+			Equal => self.cmove_Register64Bit_Register64Bit(operand0, operand1),
+			NotEqual => self.cmovne_Register64Bit_Register64Bit(operand0, operand1),
+			BelowOrEqual => self.cmovbe_Register64Bit_Register64Bit(operand0, operand1),
+			Above => self.cmova_Register64Bit_Register64Bit(operand0, operand1),
+			Sign => self.cmovs_Register64Bit_Register64Bit(operand0, operand1),
+			NotSign => self.cmovns_Register64Bit_Register64Bit(operand0, operand1),
+			Parity => self.cmovp_Register64Bit_Register64Bit(operand0, operand1),
+			NotParity => self.cmovnp_Register64Bit_Register64Bit(operand0, operand1),
+			Less => self.cmovl_Register64Bit_Register64Bit(operand0, operand1),
+			GreaterOrEqual => self.cmovge_Register64Bit_Register64Bit(operand0, operand1),
+			LessOrEqual => self.cmovle_Register64Bit_Register64Bit(operand0, operand1),
+			Greater => self.cmovg_Register64Bit_Register64Bit(operand0, operand1),
+		}
+	}
+}
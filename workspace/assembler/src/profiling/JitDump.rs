@@ -0,0 +1,144 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Writes a jitdump file, `jit-<pid>.dump`, containing a copy of the instructions of each named function, so that `perf inject --jit` can annotate samples in generated code instruction by instruction.
+///
+/// Record with `perf record -k mono`, as timestamps are taken from `CLOCK_MONOTONIC`, then use `perf inject --jit -i perf.data -o perf.jit.data` before `perf report` or `perf annotate`.
+///
+/// Only functions named with `InstructionStream.name_function()` are written.
+///
+/// See `tools/perf/Documentation/jitdump-specification.txt` in the Linux kernel sources.
+#[derive(Debug)]
+pub struct JitDump
+{
+	file: File,
+	marker: *mut c_void,
+	code_index: u64,
+}
+
+impl Drop for JitDump
+{
+	#[inline(always)]
+	fn drop(&mut self)
+	{
+		let _ = self.write_record(Self::JIT_CODE_CLOSE, &[]);
+		unsafe { munmap(self.marker, Self::marker_length()) };
+	}
+}
+
+impl JitDump
+{
+	const Magic: u32 = 0x4A695444;
+	
+	const Version: u32 = 1;
+	
+	const FileHeaderSize: u32 = 40;
+	
+	const RecordHeaderSize: u32 = 16;
+	
+	const JIT_CODE_LOAD: u32 = 0;
+	
+	const JIT_CODE_CLOSE: u32 = 3;
+	
+	/// Creates (or truncates) `jit-<pid>.dump` for the current process in `directory`.
+	///
+	/// `perf inject` looks for this file using the memory map made of it, so it can be anywhere; `perf` itself uses `~/.debug/jit`.
+	pub fn create(directory: impl AsRef<Path>) -> io::Result<Self>
+	{
+		const EM_X86_64: u32 = 62;
+		const NoFlags: u64 = 0;
+		const NoOffset: i64 = 0;
+		
+		let path = directory.as_ref().join(format!("jit-{}.dump", process::id()));
+		let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+		
+		let mut header = Vec::with_capacity(Self::FileHeaderSize as usize);
+		header.extend_from_slice(&Self::Magic.to_ne_bytes());
+		header.extend_from_slice(&Self::Version.to_ne_bytes());
+		header.extend_from_slice(&Self::FileHeaderSize.to_ne_bytes());
+		header.extend_from_slice(&EM_X86_64.to_ne_bytes());
+		// Padding.
+		header.extend_from_slice(&0u32.to_ne_bytes());
+		header.extend_from_slice(&process::id().to_ne_bytes());
+		header.extend_from_slice(&Self::timestamp().to_ne_bytes());
+		header.extend_from_slice(&NoFlags.to_ne_bytes());
+		file.write_all(&header)?;
+		
+		// `perf record` sees this executable mapping of the file and so knows to look for it.
+		let marker = unsafe { mmap(null_mut(), Self::marker_length(), PROT_READ | PROT_EXEC, MAP_PRIVATE, file.as_raw_fd(), NoOffset) };
+		if unlikely!(marker == MAP_FAILED)
+		{
+			return Err(io::Error::last_os_error())
+		}
+		
+		Ok
+		(
+			Self
+			{
+				file,
+				marker,
+				code_index: 0,
+			}
+		)
+	}
+	
+	/// Writes a code load record, including a copy of its instructions, for each named function of `finished_code`.
+	pub fn record(&mut self, finished_code: &FinishedCode) -> io::Result<()>
+	{
+		let process_identifier = process::id();
+		let thread_identifier = unsafe { syscall(SYS_gettid) } as u32;
+		let start_instruction_pointer = finished_code.start_instruction_pointer();
+		
+		for (name, instruction_pointer, size) in finished_code.functions()
+		{
+			let offset = instruction_pointer - start_instruction_pointer;
+			let instructions = &finished_code.encoded_bytes()[offset .. offset + size];
+			
+			let mut record = Vec::with_capacity(40 + name.len() + 1 + size);
+			record.extend_from_slice(&process_identifier.to_ne_bytes());
+			record.extend_from_slice(&thread_identifier.to_ne_bytes());
+			// Virtual address and code address are the same.
+			record.extend_from_slice(&(instruction_pointer as u64).to_ne_bytes());
+			record.extend_from_slice(&(instruction_pointer as u64).to_ne_bytes());
+			record.extend_from_slice(&(size as u64).to_ne_bytes());
+			record.extend_from_slice(&self.code_index.to_ne_bytes());
+			record.extend_from_slice(name.as_bytes());
+			record.push(0);
+			record.extend_from_slice(instructions);
+			
+			self.write_record(Self::JIT_CODE_LOAD, &record)?;
+			self.code_index += 1;
+		}
+		Ok(())
+	}
+	
+	#[inline(always)]
+	fn write_record(&mut self, identifier: u32, body: &[u8]) -> io::Result<()>
+	{
+		let mut record = Vec::with_capacity((Self::RecordHeaderSize as usize) + body.len());
+		record.extend_from_slice(&identifier.to_ne_bytes());
+		record.extend_from_slice(&(Self::RecordHeaderSize + body.len() as u32).to_ne_bytes());
+		record.extend_from_slice(&Self::timestamp().to_ne_bytes());
+		record.extend_from_slice(body);
+		self.file.write_all(&record)
+	}
+	
+	#[inline(always)]
+	fn timestamp() -> u64
+	{
+		let mut time = timespec
+		{
+			tv_sec: 0,
+			tv_nsec: 0,
+		};
+		unsafe { clock_gettime(CLOCK_MONOTONIC, &mut time) };
+		(time.tv_sec as u64) * 1_000_000_000 + (time.tv_nsec as u64)
+	}
+	
+	#[inline(always)]
+	fn marker_length() -> usize
+	{
+		unsafe { sysconf(_SC_PAGESIZE) as usize }
+	}
+}
@@ -0,0 +1,48 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+/// Appends lines of `start size name` to a perf map file, `/tmp/perf-<pid>.map`, which `perf report` uses to name samples in code that has no symbols.
+///
+/// Only functions named with `InstructionStream.name_function()` are written.
+#[derive(Debug)]
+pub struct PerfMap
+{
+	file: File,
+}
+
+impl PerfMap
+{
+	/// Opens `/tmp/perf-<pid>.map` for the current process, creating it if necessary.
+	#[inline(always)]
+	pub fn open() -> io::Result<Self>
+	{
+		Self::open_path(format!("/tmp/perf-{}.map", process::id()))
+	}
+	
+	/// Opens `path` for appending, creating it if necessary.
+	#[inline(always)]
+	pub fn open_path(path: impl AsRef<Path>) -> io::Result<Self>
+	{
+		Ok
+		(
+			Self
+			{
+				file: OpenOptions::new().append(true).create(true).open(path)?,
+			}
+		)
+	}
+	
+	/// Appends a line for each named function of `finished_code`.
+	///
+	/// Lines are written at once, so that `perf` never sees part of a line.
+	pub fn record(&mut self, finished_code: &FinishedCode) -> io::Result<()>
+	{
+		let mut lines = String::new();
+		for (name, instruction_pointer, size) in finished_code.functions()
+		{
+			lines.push_str(&format!("{:x} {:x} {}\n", instruction_pointer, size, name));
+		}
+		self.file.write_all(lines.as_bytes())
+	}
+}
@@ -0,0 +1,14 @@
+// This file is part of assembler. It is subject to the license terms in the COPYRIGHT file found in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT. No part of assembler, including this file, may be copied, modified, propagated, or distributed except according to the terms contained in the COPYRIGHT file.
+// Copyright © 2018 The developers of assembler. See the COPYRIGHT file in the top-level directory of this distribution and at https://raw.githubusercontent.com/lemonrock/assembler/master/COPYRIGHT.
+
+
+use super::*;
+use ::std::fs::File;
+use ::std::fs::OpenOptions;
+use ::std::os::unix::io::AsRawFd;
+use ::std::path::Path;
+use ::std::process;
+
+
+include!("JitDump.rs");
+include!("PerfMap.rs");
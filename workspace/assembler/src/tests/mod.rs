@@ -3,17 +3,53 @@
 
 
 use self::Register64Bit::*;
+use super::AssemblerError;
+use super::CodeHeap;
+use super::CodeHeapAllocation;
+use super::ElfRelocatableObject;
+use super::ElfRelocatableObjectError;
 use super::ExecutableAnonymousMemoryMap;
+#[cfg(feature = "gdb-jit-interface")] use super::GdbJitDescriptorLock;
+use super::InstructionStream;
 use super::InstructionStreamHints;
+use super::mnemonics::*;
+#[cfg(feature = "gdb-jit-interface")] use super::__jit_debug_descriptor;
+use super::decoder::*;
+use super::mnemonic_parameter_types::*;
+use super::mnemonic_parameter_types::immediates::*;
 use super::mnemonic_parameter_types::memory::*;
 use super::mnemonic_parameter_types::registers::*;
+use super::profiling::*;
+use ::libc::fstat;
+use ::libc::mmap;
+use ::libc::munmap;
+use ::libc::MAP_ANONYMOUS;
+use ::libc::MAP_FAILED;
+use ::libc::MAP_FIXED_NOREPLACE;
+use ::libc::MAP_PRIVATE;
+use ::libc::PROT_NONE;
+use ::std::backtrace::Backtrace;
+use ::std::collections::HashSet;
+use ::std::env::temp_dir;
+use ::std::ffi::OsStr;
+use ::std::fs::read;
+use ::std::fs::read_to_string;
+use ::std::fs::remove_file;
+use ::std::fs::write;
+use ::std::io::ErrorKind;
 use ::std::io::Write;
+use ::std::mem::zeroed;
+use ::std::process;
+use ::std::process::Command;
+#[cfg(feature = "gdb-jit-interface")] use ::std::ptr::addr_of;
+#[cfg(feature = "gdb-jit-interface")] use ::std::slice::from_raw_parts;
+use ::std::sync::Mutex;
 
 
 #[test]
 pub fn lifecycle()
 {
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).expect("Could not anonymously mmap");
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
 	let instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
 	
 	instruction_stream.finish();
@@ -22,7 +58,7 @@ pub fn lifecycle()
 #[test]
 pub fn labelling()
 {
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).expect("Could not anonymously mmap");
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
 	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
 	
 	let label1 = instruction_stream.create_label();
@@ -36,7 +72,7 @@ pub fn labelling()
 #[test]
 pub fn simple_function()
 {
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).expect("Could not anonymously mmap");
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
 	
 	let _function_pointer =
 	{
@@ -52,9 +88,9 @@ pub fn simple_function()
 		
 		instruction_stream.pop_stack_frame_and_return();
 		
-		let (encoded_bytes, _hints) = instruction_stream.finish();
+		let finished_code = instruction_stream.finish();
 		
-		assert_eq!(&bytes_to_string(encoded_bytes), "55 48 8B EC 31 C0 48 8B E5 5D C3", "Encoding of a basic function was wrong");
+		assert_eq!(&bytes_to_string(finished_code.encoded_bytes()), "55 48 8B EC 31 C0 48 8B E5 5D C3", "Encoding of a basic function was wrong");
 		
 		function_pointer
 	};
@@ -63,10 +99,205 @@ pub fn simple_function()
 	assert_eq!(result, 0, "function result was not zero")
 }
 
+#[test]
+pub fn dual_mapped_function()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new_dual_mapped(4096, false, true).expect("Could not dual map");
+	assert!(map.is_dual_mapped(), "map is not dual mapped");
+	
+	let function_pointer =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		
+		let function_pointer: unsafe extern "C" fn() -> u64 = instruction_stream.nullary_function_pointer();
+		
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, 0x12345678_9ABCDEF0u64.into());
+		instruction_stream.ret();
+		
+		let finished_code = instruction_stream.finish();
+		assert_eq!(finished_code.start_instruction_pointer(), function_pointer as usize, "encoded bytes are not at the executable address");
+		
+		function_pointer
+	};
+	
+	assert_eq!(unsafe { function_pointer() }, 0x12345678_9ABCDEF0, "function result was not as expected")
+}
+
+#[test]
+pub fn failure_to_grow_leaves_map_unchanged()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new_dual_mapped(4096, false, true).expect("Could not dual map");
+	
+	// Occupy the page after each mapping so that neither can grow in place; if a page is already occupied, that does just as well.
+	let blockers: Vec<_> = [map.writable_address, map.address].iter().map(|&address| unsafe { mmap(address.add(4096) as *mut _, 4096, PROT_NONE, MAP_PRIVATE | MAP_ANONYMOUS | MAP_FIXED_NOREPLACE, -1, 0) }).filter(|&blocker| blocker != MAP_FAILED).collect();
+	
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.emit_bytes(&[0x90; 5000]);
+		match instruction_stream.try_finish()
+		{
+			Err(AssemblerError::OutOfMemory(_, _)) => (),
+			_ => panic!("failure to grow was not reported"),
+		}
+	}
+	
+	assert_eq!(map.length, 4096, "length of map changed");
+	let mut file_status = unsafe { zeroed() };
+	assert_eq!(unsafe { fstat(map.dual_mapping_file_descriptor.unwrap(), &mut file_status) }, 0, "fstat failed");
+	assert_eq!(file_status.st_size, 4096, "size of file was not rolled back");
+	
+	let function =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let function: unsafe extern "C" fn() -> u64 = instruction_stream.nullary_function_pointer();
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, 42u64.into());
+		instruction_stream.ret();
+		instruction_stream.finish();
+		function
+	};
+	assert_eq!(unsafe { function() }, 42, "map was not usable after failing to grow");
+	
+	for blocker in blockers
+	{
+		unsafe { munmap(blocker, 4096) };
+	}
+}
+
+#[test]
+pub fn instruction_streams_append_to_finished_code()
+{
+	fn constant_function(map: &mut ExecutableAnonymousMemoryMap, constant: u64) -> unsafe extern "C" fn() -> u64
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		
+		let function_pointer = instruction_stream.nullary_function_pointer();
+		
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, constant.into());
+		instruction_stream.ret();
+		
+		let _ = instruction_stream.finish();
+		
+		function_pointer
+	}
+	
+	for mut map in vec![ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap"), ExecutableAnonymousMemoryMap::new_dual_mapped(4096, false, true).expect("Could not dual map")]
+	{
+		let first_function_pointer = constant_function(&mut map, 1);
+		assert_eq!(map.high_water_mark(), 11, "high water mark was not after first function");
+		
+		let second_function_pointer =
+		{
+			let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+			
+			let function_pointer: unsafe extern "C" fn() -> u64 = instruction_stream.nullary_function_pointer();
+			
+			instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, 2u64.into());
+			assert_eq!(unsafe { first_function_pointer() }, 1, "first function could not be called whilst the second was being written");
+			instruction_stream.ret();
+			
+			let _ = instruction_stream.finish();
+			
+			function_pointer
+		};
+		
+		assert_eq!(second_function_pointer as usize, first_function_pointer as usize + 11, "second function did not immediately follow first function");
+		assert_eq!(unsafe { first_function_pointer() }, 1, "first function was overwritten");
+		assert_eq!(unsafe { second_function_pointer() }, 2, "second function result was not as expected");
+	}
+}
+
+#[test]
+pub fn failing_to_make_the_shared_page_writable_and_executable_is_reported()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let function_pointer =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let function_pointer: unsafe extern "C" fn() -> u64 = instruction_stream.nullary_function_pointer();
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, 1u64.into());
+		instruction_stream.ret();
+		let _ = instruction_stream.finish();
+		function_pointer
+	};
+	
+	// `mprotect()` refuses an address which is not page aligned, as a kernel refusing memory which is both writable and executable would.
+	let address = map.address;
+	map.address = unsafe { address.add(1) };
+	let result =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, 2u64.into());
+		instruction_stream.ret();
+		instruction_stream.try_finish().map(|_| ())
+	};
+	map.address = address;
+	
+	match result
+	{
+		Err(AssemblerError::ProtectionFailed(_)) => (),
+		_ => panic!("failing to make the shared page writable and executable was not reported"),
+	}
+	assert_eq!(map.high_water_mark(), 11, "high water mark moved although code was not made executable");
+	assert_eq!(unsafe { function_pointer() }, 1, "finished code was changed");
+}
+
+#[test]
+pub fn code_heap()
+{
+	fn constant_function(allocation: &mut CodeHeapAllocation, constant: u64) -> unsafe extern "C" fn() -> u64
+	{
+		let mut instruction_stream = allocation.instruction_stream(&InstructionStreamHints::default());
+		
+		let function_pointer = instruction_stream.nullary_function_pointer();
+		
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, constant.into());
+		instruction_stream.ret();
+		
+		let _ = instruction_stream.finish();
+		
+		function_pointer
+	}
+	
+	let code_heap = CodeHeap::new(4096, false, true).expect("Could not create code heap");
+	
+	let mut first_allocation = code_heap.allocate(20, 64).expect("Could not allocate");
+	let mut second_allocation = code_heap.allocate(100, 64).expect("Could not allocate");
+	assert_eq!(first_allocation.length(), 32, "length was not rounded up");
+	assert_eq!(second_allocation.address() % 64, 0, "allocation was not aligned");
+	assert_eq!(second_allocation.address(), first_allocation.address() + 64, "allocation was not first fit");
+	
+	let first_function_pointer = constant_function(&mut first_allocation, 1);
+	let second_function_pointer = constant_function(&mut second_allocation, 2);
+	assert_eq!(unsafe { first_function_pointer() }, 1, "first function result was not as expected");
+	assert_eq!(unsafe { second_function_pointer() }, 2, "second function result was not as expected");
+	
+	let statistics = code_heap.statistics();
+	assert_eq!(statistics.allocated_bytes, 32 + 112, "allocated bytes were wrong");
+	assert_eq!(statistics.number_of_allocations, 2, "number of allocations was wrong");
+	assert_eq!(statistics.number_of_free_blocks, 2, "padding between allocations was not free");
+	
+	drop(first_allocation);
+	assert_eq!(code_heap.statistics().number_of_free_blocks, 2, "freed allocation was not merged with padding");
+	assert_eq!(unsafe { second_function_pointer() }, 2, "second function was overwritten");
+	
+	let largest_free_block = code_heap.statistics().largest_free_block;
+	assert!(code_heap.allocate(largest_free_block, 4096).is_none(), "largest free block was allocated despite padding for alignment");
+	drop(code_heap.allocate(largest_free_block, 16).expect("largest free block could not be allocated"));
+	
+	drop(second_allocation);
+	let statistics = code_heap.statistics();
+	assert_eq!(statistics.number_of_free_blocks, 1, "free blocks were not merged");
+	assert_eq!(statistics.largest_free_block, 4096, "free blocks were not merged");
+	assert_eq!(statistics.fragmentation(), 0.0, "heap was fragmented");
+	
+	assert!(code_heap.allocate(8192, 16).is_none(), "allocation larger than heap succeeded");
+}
+
 #[test]
 pub fn validate_that_rust_follows_the_system_v_abi_for_bool()
 {
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).expect("Could not anonymously mmap");
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
 	
 	let false_function_pointer =
 	{
@@ -88,7 +319,7 @@ pub fn validate_that_rust_follows_the_system_v_abi_for_bool()
 	// See AMD64 ABI 1.0 – August 13, 2018 – 8:25, page 22, third-to-last paragraph and footnote 16.
 	// In essence, a _Bool should be interpreted only from the bottom 8 bits.
 	
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).expect("Could not anonymously mmap");
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
 	
 	let false_function_pointer =
 	{
@@ -111,7 +342,7 @@ pub fn validate_that_rust_follows_the_system_v_abi_for_bool()
 #[test]
 pub fn validate_that_rust_follows_the_system_v_abi_for_u128()
 {
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).expect("Could not anonymously mmap");
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
 	
 	let u128_function_pointer: unsafe extern "C" fn() -> u128 =
 	{
@@ -185,7 +416,7 @@ pub fn validate_that_rust_follows_the_system_v_abi_for_u128()
 #[test]
 pub fn emit()
 {
-	let mut map = ExecutableAnonymousMemoryMap::new(4096, false).unwrap();
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).unwrap();
 	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
 	
 	let offset: usize = 64;
@@ -221,28 +452,1554 @@ pub fn emit()
 	instruction_stream.attach_label(forward_label);
 	instruction_stream.nop();
 	
-	let (encoded_bytes, _) = instruction_stream.finish();
-	println!("{}", bytes_to_string(encoded_bytes))
+	let finished_code = instruction_stream.finish();
+	println!("{}", bytes_to_string(finished_code.encoded_bytes()))
 }
 
-// Suitable for https://onlinedisassembler.com/odaweb/ .
-fn bytes_to_string(encoded_bytes: &[u8]) -> String
+#[test]
+pub fn relax_jumps_on_finish()
 {
-	let mut string = Vec::with_capacity(encoded_bytes.len() * 3);
+	use self::Register32Bit::*;
 	
-	let mut after_first = false;
-	for byte in encoded_bytes
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let function_pointer =
 	{
-		if after_first
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.relax_jumps_on_finish();
+		
+		let function_pointer: unsafe extern "C" fn() -> i32 = instruction_stream.nullary_function_pointer();
+		
+		let done = instruction_stream.create_label();
+		
+		instruction_stream.zero_RAX();
+		instruction_stream.mov_Register32Bit_Immediate32Bit(ECX, 3.into());
+		let top = instruction_stream.create_and_attach_label();
+		instruction_stream.add_Register32Bit_Register32Bit(EAX, ECX);
+		instruction_stream.dec_Register32Bit(ECX);
+		instruction_stream.jne_Label_1(top);
+		instruction_stream.jmp_Label_1(done);
+		instruction_stream.mov_Register32Bit_Immediate32Bit(EAX, 0.into());
+		instruction_stream.attach_label(done);
+		instruction_stream.ret();
+		
+		let finished_code = instruction_stream.finish();
+		
+		assert_eq!(&bytes_to_string(finished_code.encoded_bytes()), "31 C0 B9 03 00 00 00 01 C8 FF C9 75 FA EB 05 B8 00 00 00 00 C3", "Jumps were not relaxed");
+		
+		function_pointer
+	};
+	
+	assert_eq!(unsafe { function_pointer() }, 6, "function result was not 6")
+}
+
+#[test]
+pub fn function_pointers_pin_relaxed_jumps()
+{
+	use self::Register32Bit::*;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let function_pointer =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.relax_jumps_on_finish();
+		
+		let done = instruction_stream.create_label();
+		
+		instruction_stream.mov_Register32Bit_Immediate32Bit(EAX, 1.into());
+		instruction_stream.jmp_Label_1(done);
+		let function_pointer: unsafe extern "C" fn() -> i32 = instruction_stream.nullary_function_pointer();
+		instruction_stream.mov_Register32Bit_Immediate32Bit(EAX, 2.into());
+		instruction_stream.jmp_Label_1(done);
+		instruction_stream.mov_Register32Bit_Immediate32Bit(EAX, 3.into());
+		instruction_stream.attach_label(done);
+		instruction_stream.ret();
+		
+		let finished_code = instruction_stream.finish();
+		
+		assert_eq!(&bytes_to_string(finished_code.encoded_bytes()), "B8 01 00 00 00 E9 0C 00 00 00 B8 02 00 00 00 EB 05 B8 03 00 00 00 C3", "Only the jump after the function pointer should have been relaxed");
+		
+		function_pointer
+	};
+	
+	assert_eq!(unsafe { function_pointer() }, 2, "function result was not 2")
+}
+
+#[test]
+pub fn finished_code_label_entry_points()
+{
+	use self::Register32Bit::*;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.relax_jumps_on_finish();
+	
+	let done = instruction_stream.create_label();
+	let never_attached = instruction_stream.create_label();
+	
+	let first = instruction_stream.create_and_attach_label();
+	instruction_stream.mov_Register32Bit_Immediate32Bit(EAX, 1.into());
+	instruction_stream.jmp_Label_1(done);
+	
+	let second = instruction_stream.create_and_attach_label();
+	instruction_stream.mov_Register32Bit_Immediate32Bit(EAX, 2.into());
+	instruction_stream.attach_label(done);
+	instruction_stream.ret();
+	
+	let finished_code = instruction_stream.finish();
+	
+	assert_eq!(finished_code.label_offset(first), Some(0), "first label offset was wrong");
+	assert_eq!(finished_code.label_offset(second), Some(7), "second label offset was not relocated by relaxation");
+	assert_eq!(finished_code.label_address(second), Some(finished_code.start_instruction_pointer() + 7), "second label address was wrong");
+	assert_eq!(finished_code.label_address(never_attached), None, "unattached label had an address");
+	
+	let first_function_pointer: unsafe extern "C" fn() -> i32 = finished_code.nullary_function_pointer(first).unwrap();
+	let second_function_pointer: unsafe extern "C" fn() -> i32 = finished_code.nullary_function_pointer(second).unwrap();
+	assert_eq!(unsafe { first_function_pointer() }, 1, "first function result was not 1");
+	assert_eq!(unsafe { second_function_pointer() }, 2, "second function result was not 2");
+}
+
+#[test]
+pub fn try_finish_reports_unresolved_label()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let never_attached = instruction_stream.create_label();
+	instruction_stream.jmp_Label_1(never_attached);
+	
+	match instruction_stream.try_finish()
+	{
+		Err(AssemblerError::UnresolvedLabel(label)) => assert_eq!(label, never_attached, "wrong label reported"),
+		_ => panic!("unresolved label was not reported"),
+	}
+}
+
+#[test]
+pub fn try_finish_reports_label_attached_twice()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let label = instruction_stream.create_and_attach_label();
+	instruction_stream.nop();
+	assert!(instruction_stream.try_attach_label(label).is_err(), "try_attach_label did not fail");
+	assert!(instruction_stream.error().is_none(), "try_attach_label recorded an error");
+	
+	instruction_stream.attach_label(label);
+	
+	match instruction_stream.try_finish()
+	{
+		Err(AssemblerError::LabelAlreadyAttached(reported)) => assert_eq!(reported, label, "wrong label reported"),
+		_ => panic!("label attached twice was not reported"),
+	}
+}
+
+#[test]
+pub fn emission_continues_after_an_error()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.emit_landing_pads_for_indirect_branch_tracking();
+	
+	let first = instruction_stream.create_and_attach_label();
+	instruction_stream.attach_label(first);
+	
+	// Each pass fills more than one scratch buffer, and refers to labels attached in earlier ones.
+	let mut labels = vec![first];
+	for pass in 0 .. 4
+	{
+		let label = instruction_stream.create_and_attach_label();
+		instruction_stream.emit_bytes(&[0x90; 5000]);
+		for &earlier in labels.iter()
 		{
-			write!(string, " ");
+			instruction_stream.jmp_Label_1(earlier);
+			instruction_stream.emit_label(earlier);
 		}
-		else
+		instruction_stream.emit_bytes(&vec![0xCC; 3000 * pass]);
+		labels.push(label);
+	}
+	
+	match instruction_stream.try_finish()
+	{
+		Err(AssemblerError::LabelAlreadyAttached(reported)) => assert_eq!(reported, first, "wrong error reported"),
+		_ => panic!("first error was not reported"),
+	}
+}
+
+#[test]
+pub fn disassemble()
+{
+	use self::Register32Bit::*;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let top = instruction_stream.create_and_attach_label();
+	instruction_stream.push_stack_frame();
+	instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, 0x12345678_9ABCDEF0u64.into());
+	instruction_stream.add_Any64BitMemory_Immediate8Bit(Any64BitMemory::base_64_index_64_scale_displacement(R13, R9, IndexScale::x4, (-8).into()), Immediate8Bit(-2));
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory_Masking(ZMMRegister::ZMM1, ZMMRegister::ZMM2, Any512BitMemory::base_64_displacement(RAX, 0x40.into()), Masking::zeroing(OpmaskRegister::K1));
+	instruction_stream.vgatherdps_XMMRegister_Vsib32X_XMMRegister(XMMRegister::XMM14, Vsib32X::base_32_index_scale(R12D, XMMRegister::XMM3, IndexScale::x1), XMMRegister::XMM5);
+	instruction_stream.fdiv_X87Register_ST0(X87Register::ST7);
+	instruction_stream.loope_Label(top).expect("loope was not short");
+	instruction_stream.jecxz_Label(top).expect("jecxz was not short");
+	instruction_stream.jne_Label_1(top);
+	instruction_stream.pop_stack_frame_and_return();
+	instruction_stream.emit_byte(0x06);
+	
+	let finished_code = instruction_stream.finish();
+	let start = finished_code.start_instruction_pointer();
+	
+	let instructions: Vec<String> = finished_code.disassemble().map(|result| match result
+	{
+		Ok(instruction) => instruction.to_string(),
+		Err(error) => error.to_string(),
+	}).collect();
+	
+	let expected = vec!
+	[
+		"push rbp".to_string(),
+		"mov rbp, rsp".to_string(),
+		"mov rax, 0x123456789abcdef0".to_string(),
+		"add qword ptr [r13+r9*4-0x8], -0x2".to_string(),
+		"vaddps zmm1{k1}{z}, zmm2, zmmword ptr [rax+0x40]".to_string(),
+		"vgatherdps xmm14, dword ptr [r12d+xmm3*1], xmm5".to_string(),
+		"fdiv st(7), st(0)".to_string(),
+		format!("loope 0x{:x}", start),
+		format!("jecxz 0x{:x}", start),
+		format!("jne 0x{:x}", start),
+		"mov rsp, rbp".to_string(),
+		"pop rbp".to_string(),
+		"ret".to_string(),
+		format!("unrecognised instruction at 0x{:x}", start + finished_code.encoded_bytes().len() - 1),
+	];
+	assert_eq!(instructions, expected, "disassembly was wrong");
+	
+	let listing = finished_code.disassemble().listing();
+	assert_eq!(listing.lines().next(), Some(&format!("{:x}:\t55\tpush rbp", start)[..]), "first line of listing was wrong");
+	assert_eq!(listing.lines().last(), Some(&format!("{:x}:\t06\t(bad)", start + finished_code.encoded_bytes().len() - 1)[..]), "last line of listing was wrong");
+}
+
+#[test]
+pub fn instruction_forms_index_is_sorted_and_consistent()
+{
+	let index = instruction_forms_index();
+	
+	assert!(index.windows(2).all(|pair| pair[0] < pair[1]), "index was not sorted by key and then by index");
+	assert_eq!(index.len(), InstructionForms.iter().map(|form| form.keys().count()).sum::<usize>(), "index has entries which are not the keys of a form");
+	for (form_index, form) in InstructionForms.iter().enumerate()
+	{
+		for key in form.keys()
 		{
-			after_first = true
+			assert!(index.binary_search(&(key, form_index as u16)).is_ok(), "index is missing key 0x{:04X} of form {} ({})", key, form_index, form.mnemonic);
 		}
-		write!(string, "{:02X}", *byte);
 	}
+}
+
+#[test]
+pub fn instruction_forms_round_trip()
+{
+	let encodings: Vec<Vec<u8>> = InstructionForms.iter().map(encode_instruction_form).collect();
 	
-	String::from_utf8(string).unwrap()
+	// Aliases, such as `cmovc` of `cmovb`, and forms with implicit operands, such as `faddp st(1), st(0)` of `faddp`, decode as the form the decoder prefers.
+	let decoded_as = |instruction: &DecodedInstruction, bytes: &[u8]| InstructionForms.iter().zip(encodings.iter()).any(|(form, encoding)| form.mnemonic == instruction.mnemonic && form.operands.len() == instruction.operands.len() && &encoding[..] == bytes);
+	
+	let mut failures = Vec::new();
+	for (form_index, (form, bytes)) in InstructionForms.iter().zip(encodings.iter()).enumerate()
+	{
+		match Decoder::new(bytes, 0x1000).decode(0)
+		{
+			Ok(ref instruction) if instruction.length == bytes.len() && decoded_as(instruction, bytes) => (),
+			Ok(instruction) => failures.push(format!("form {} ({}) encoded as {} decoded as {} of {} bytes", form_index, form.mnemonic, bytes_to_string(bytes), instruction, instruction.length)),
+			Err(error) => failures.push(format!("form {} ({}) encoded as {} did not decode: {}", form_index, form.mnemonic, bytes_to_string(bytes), error)),
+		}
+	}
+	assert!(failures.is_empty(), "forms did not round trip:\n{}", failures.join("\n"));
+}
+
+#[test]
+pub fn elf_relocatable_object()
+{
+	unsafe extern "C" fn external() -> i32
+	{
+		42
+	}
+	let external: unsafe extern "C" fn() -> i32 = external;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let entry = instruction_stream.create_and_attach_label();
+	instruction_stream.push_stack_frame();
+	instruction_stream.call_function(external);
+	instruction_stream.pop_stack_frame_and_return();
+	let table = instruction_stream.create_and_attach_label();
+	instruction_stream.emit_label(entry);
+	
+	let finished_code = instruction_stream.finish();
+	
+	let mut object = ElfRelocatableObject::new(&finished_code);
+	object.global_function("entry", entry).local_function("table", table);
+	match object.to_bytes()
+	{
+		Err(ElfRelocatableObjectError::UnnamedFunction(absolute_address, offset)) =>
+		{
+			assert_eq!(absolute_address, external as usize, "wrong function reported");
+			assert_eq!(offset, 5, "wrong offset reported");
+		}
+		_ => panic!("unnamed function was not reported"),
+	}
+	
+	object.external_function("external", external);
+	let bytes = object.to_bytes().expect("Could not create relocatable object");
+	
+	let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+	let u64_at = |offset: usize| { let mut value = [0; 8]; value.copy_from_slice(&bytes[offset .. offset + 8]); u64::from_le_bytes(value) };
+	assert_eq!(&bytes[0 .. 4], b"\x7FELF", "not an ELF file");
+	assert_eq!(u16_at(16), 1, "not a relocatable object");
+	assert_eq!(u16_at(18), 62, "not for x86-64");
+	
+	let section_header = |index: usize| (u64_at(0x28) as usize) + index * 64;
+	let text_offset = u64_at(section_header(1) + 24) as usize;
+	assert_eq!(&bytes_to_string(&bytes[text_offset .. text_offset + 22]), "55 48 8B EC E8 00 00 00 00 48 8B E5 5D C3 00 00 00 00 00 00 00 00", "relocated locations in .text were not zeroed");
+	
+	let rela_text_offset = u64_at(section_header(2) + 24) as usize;
+	let rela_text_size = u64_at(section_header(2) + 32) as usize;
+	let relocations: Vec<(u64, u64, i64)> = (rela_text_offset .. rela_text_offset + rela_text_size).step_by(24).map(|offset| (u64_at(offset), u64_at(offset + 8), u64_at(offset + 16) as i64)).collect();
+	const R_X86_64_64: u64 = 1;
+	const R_X86_64_PLT32: u64 = 4;
+	assert_eq!(relocations, vec![(5, (4 << 32) | R_X86_64_PLT32, -4), (14, (1 << 32) | R_X86_64_64, 0)], "relocations were wrong");
+}
+
+#[test]
+pub fn elf_relocatable_object_is_accepted_by_readelf_and_ld()
+{
+	unsafe extern "C" fn external() -> i32
+	{
+		42
+	}
+	let external: unsafe extern "C" fn() -> i32 = external;
+	
+	fn run(program: &str, arguments: &[&OsStr]) -> Option<String>
+	{
+		match Command::new(program).args(arguments).output()
+		{
+			Err(ref error) if error.kind() == ErrorKind::NotFound => None,
+			Err(error) => panic!("Could not run {}: {}", program, error),
+			Ok(output) =>
+			{
+				assert!(output.status.success(), "{} failed: {}", program, String::from_utf8_lossy(&output.stderr));
+				Some(String::from_utf8(output.stdout).expect("Output was not UTF-8"))
+			}
+		}
+	}
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let entry = instruction_stream.create_and_attach_label();
+	instruction_stream.push_stack_frame();
+	instruction_stream.call_function(external);
+	instruction_stream.pop_stack_frame_and_return();
+	let table = instruction_stream.create_and_attach_label();
+	instruction_stream.emit_label(entry);
+	instruction_stream.emit_label(table);
+	
+	let finished_code = instruction_stream.finish();
+	
+	let mut object = ElfRelocatableObject::new(&finished_code);
+	object.global_function("entry", entry).local_function("table", table).external_function("external", external);
+	let bytes = object.to_bytes().expect("Could not create relocatable object");
+	
+	let object_path = temp_dir().join(format!("assembler-test-{}.o", process::id()));
+	let linked_path = temp_dir().join(format!("assembler-test-{}-linked.o", process::id()));
+	write(&object_path, &bytes).expect("Could not write relocatable object");
+	
+	let readelf = run("readelf", &[OsStr::new("--wide"), OsStr::new("-r"), OsStr::new("-s"), object_path.as_os_str()]);
+	let ld = run("ld", &[OsStr::new("-r"), OsStr::new("-o"), linked_path.as_os_str(), object_path.as_os_str()]);
+	let linked_readelf = if ld.is_some() { run("readelf", &[OsStr::new("--wide"), OsStr::new("-s"), linked_path.as_os_str()]) } else { None };
+	
+	let _ = remove_file(&linked_path);
+	remove_file(&object_path).expect("Could not remove relocatable object");
+	
+	// Offset, type and symbol with addend.
+	let relocations = |listing: &str| -> Vec<String> { listing.lines().map(|line| line.split_whitespace().collect::<Vec<_>>()).filter(|fields| fields.len() >= 5 && fields[2].starts_with("R_X86_64_")).map(|fields| format!("{} {} {}", fields[0], fields[2], fields[4 ..].join(" "))).collect() };
+	
+	// Value, type, binding, section index and name of named functions and undefined symbols.
+	let symbols = |listing: &str| -> Vec<String> { listing.lines().map(|line| line.split_whitespace().collect::<Vec<_>>()).filter(|fields| fields.len() == 8 && fields[0].ends_with(':') && (fields[3] == "FUNC" || fields[3] == "NOTYPE")).map(|fields| format!("{} {} {} {} {}", fields[1], fields[3], fields[4], fields[6], fields[7])).collect() };
+	
+	let expected_symbols = vec!
+	[
+		"000000000000000e FUNC LOCAL 1 table",
+		"0000000000000000 FUNC GLOBAL 1 entry",
+		"0000000000000000 NOTYPE GLOBAL UND external",
+	];
+	
+	let readelf = match readelf
+	{
+		None => return,
+		Some(readelf) => readelf,
+	};
+	assert_eq!(relocations(&readelf), vec!["0000000000000005 R_X86_64_PLT32 external - 4", "000000000000000e R_X86_64_64 .text + 0", "0000000000000016 R_X86_64_64 .text + e"], "readelf relocations were wrong");
+	assert_eq!(symbols(&readelf), expected_symbols, "readelf symbols were wrong");
+	
+	if let Some(linked_readelf) = linked_readelf
+	{
+		assert_eq!(symbols(&linked_readelf), expected_symbols, "ld did not preserve symbols");
+	}
+}
+
+#[cfg(feature = "gdb-jit-interface")]
+#[test]
+pub fn gdb_jit_interface()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	map.enable_gdb_jit_interface();
+	
+	let (start, length) =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		
+		instruction_stream.nop();
+		let function = instruction_stream.create_and_attach_label();
+		instruction_stream.name_function(function, "jitted_function");
+		instruction_stream.zero_RAX();
+		instruction_stream.ret();
+		
+		let finished_code = instruction_stream.finish();
+		(finished_code.start_instruction_pointer(), finished_code.encoded_bytes().len())
+	};
+	
+	let symbol_file = registered_gdb_jit_symbol_file(start).expect("code was not registered");
+	let u32_at = |offset: usize| { let mut value = [0; 4]; value.copy_from_slice(&symbol_file[offset .. offset + 4]); u32::from_le_bytes(value) };
+	let u64_at = |offset: usize| { let mut value = [0; 8]; value.copy_from_slice(&symbol_file[offset .. offset + 8]); u64::from_le_bytes(value) };
+	let section_header = |index: usize| (u64_at(0x28) as usize) + index * 64;
+	assert_eq!(u64_at(section_header(1) + 32), length as u64, ".text size was wrong");
+	
+	let symbol_table_offset = u64_at(section_header(2) + 24) as usize;
+	let string_table_offset = u64_at(section_header(3) + 24) as usize;
+	let symbol = symbol_table_offset + 24;
+	let name_offset = string_table_offset + (u32_at(symbol) as usize);
+	assert_eq!(&symbol_file[name_offset .. name_offset + 16], b"jitted_function\0", "symbol name was wrong");
+	assert_eq!(u64_at(symbol + 8), 1, "symbol value was wrong");
+	assert_eq!(u64_at(symbol + 16), (length - 1) as u64, "symbol size was wrong");
+	
+	drop(map);
+	assert!(registered_gdb_jit_symbol_file(start).is_none(), "code was not unregistered");
+}
+
+#[test]
+pub fn profiling()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let first = instruction_stream.create_and_attach_label();
+	instruction_stream.zero_RAX();
+	instruction_stream.ret();
+	let second = instruction_stream.create_and_attach_label();
+	instruction_stream.ret();
+	instruction_stream.name_function(second, "second");
+	instruction_stream.name_function(first, "first");
+	
+	let finished_code = instruction_stream.finish();
+	let start = finished_code.start_instruction_pointer();
+	
+	let perf_map_path = temp_dir().join(format!("assembler-test-perf-{}.map", process::id()));
+	let _ = remove_file(&perf_map_path);
+	PerfMap::open_path(&perf_map_path).expect("Could not open perf map").record(&finished_code).expect("Could not write perf map");
+	let perf_map = read_to_string(&perf_map_path).expect("Could not read perf map");
+	remove_file(&perf_map_path).expect("Could not remove perf map");
+	assert_eq!(perf_map, format!("{:x} 3 first\n{:x} 1 second\n", start, start + 3), "perf map was wrong");
+	
+	let jit_dump_path = temp_dir().join(format!("jit-{}.dump", process::id()));
+	{
+		let mut jit_dump = JitDump::create(temp_dir()).expect("Could not create jitdump");
+		jit_dump.record(&finished_code).expect("Could not write jitdump");
+	}
+	let jit_dump = read(&jit_dump_path).expect("Could not read jitdump");
+	remove_file(&jit_dump_path).expect("Could not remove jitdump");
+	let u32_at = |offset: usize| { let mut value = [0; 4]; value.copy_from_slice(&jit_dump[offset .. offset + 4]); u32::from_ne_bytes(value) };
+	let u64_at = |offset: usize| { let mut value = [0; 8]; value.copy_from_slice(&jit_dump[offset .. offset + 8]); u64::from_ne_bytes(value) };
+	assert_eq!(u32_at(0), 0x4A695444, "jitdump magic was wrong");
+	
+	let first_record = u32_at(8) as usize;
+	assert_eq!(u32_at(first_record), 0, "first record was not a code load");
+	assert_eq!(u64_at(first_record + 24), start as u64, "code address was wrong");
+	assert_eq!(u64_at(first_record + 40), 3, "code size was wrong");
+	assert_eq!(&jit_dump[first_record + 56 .. first_record + 65], b"first\0\x31\xC0\xC3", "name or instructions were wrong");
+	
+	let second_record = first_record + (u32_at(first_record + 4) as usize);
+	let close_record = second_record + (u32_at(second_record + 4) as usize);
+	assert_eq!(u64_at(second_record + 48), 1, "code index was wrong");
+	assert_eq!(u32_at(close_record), 3, "last record was not a close");
+	assert_eq!(close_record + 16, jit_dump.len(), "jitdump was wrong length");
+}
+
+#[test]
+pub fn call_frame_information()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let (function_pointer, start) =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.relax_jumps_on_finish();
+		
+		let function_pointer: unsafe extern "C" fn() = instruction_stream.nullary_function_pointer();
+		instruction_stream.cfi_start_procedure();
+		instruction_stream.push_stack_frame();
+		instruction_stream.push_callee_saved_register(RBX);
+		instruction_stream.allocate_stack(8);
+		let skip = instruction_stream.create_label();
+		instruction_stream.jmp_Label_1(skip);
+		instruction_stream.attach_label(skip);
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, (capture_backtrace as extern "C" fn() as usize as u64).into());
+		instruction_stream.call_Register64Bit(RAX);
+		instruction_stream.free_stack(8);
+		instruction_stream.pop_callee_saved_register(RBX);
+		instruction_stream.pop_stack_frame_and_return();
+		instruction_stream.cfi_end_procedure();
+		
+		let finished_code = instruction_stream.finish();
+		(function_pointer, finished_code.start_instruction_pointer())
+	};
+	
+	unsafe { function_pointer() };
+	let backtrace = CapturedBacktrace.lock().unwrap().take().expect("backtrace was not captured");
+	assert!(backtrace.lines().any(|line| line.trim_end().ends_with("tests::call_frame_information")), "could not unwind through generated code:\n{}", backtrace);
+	
+	assert!(!unsafe { _Unwind_Find_FDE(start + 1, &mut [0; 3]) }.is_null(), "no frame description entry was registered");
+	drop(map);
+	assert!(unsafe { _Unwind_Find_FDE(start + 1, &mut [0; 3]) }.is_null(), "frame description entry was not deregistered");
+}
+
+#[test]
+pub fn operand_polymorphic_mnemonics()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let generic =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let label = instruction_stream.create_and_attach_label();
+		instruction_stream.push(RBP);
+		instruction_stream.mov(RBP, RSP);
+		instruction_stream.add(Any64BitMemory::base_64(RAX), Immediate8Bit(1));
+		instruction_stream.cmp(Register32Bit::EAX, Register32Bit::ECX);
+		instruction_stream.lea(RAX, Any64BitMemory::base_64_displacement(RBX, Immediate32Bit(8)));
+		instruction_stream.imul3(RAX, RBX, Immediate8Bit(3));
+		instruction_stream.vaddps(XMMRegister::XMM0, XMMRegister::XMM1, XMMRegister::XMM2);
+		instruction_stream.vmovaps_masked(Any128BitMemory::base_64(RAX), XMMRegister::XMM1, OpmaskRegister::K1);
+		instruction_stream.jne(label);
+		instruction_stream.pop(RBP);
+		instruction_stream.ret1(Immediate16Bit(8));
+		instruction_stream.finish().encoded_bytes().to_vec()
+	};
+	
+	let specialised =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let label = instruction_stream.create_and_attach_label();
+		instruction_stream.push_Register64Bit_r64(RBP);
+		instruction_stream.mov_Register64Bit_Register64Bit_rm64_r64(RBP, RSP);
+		instruction_stream.add_Any64BitMemory_Immediate8Bit(Any64BitMemory::base_64(RAX), Immediate8Bit(1));
+		instruction_stream.cmp_Register32Bit_Register32Bit(Register32Bit::EAX, Register32Bit::ECX);
+		instruction_stream.lea_Register64Bit_Any64BitMemory(RAX, Any64BitMemory::base_64_displacement(RBX, Immediate32Bit(8)));
+		instruction_stream.imul_Register64Bit_Register64Bit_Immediate8Bit(RAX, RBX, Immediate8Bit(3));
+		instruction_stream.vaddps_XMMRegister_XMMRegister_XMMRegister(XMMRegister::XMM0, XMMRegister::XMM1, XMMRegister::XMM2);
+		instruction_stream.vmovaps_Any128BitMemory_XMMRegister_Masking(Any128BitMemory::base_64(RAX), XMMRegister::XMM1, OpmaskRegister::K1);
+		instruction_stream.jne_Label_1(label);
+		instruction_stream.pop_Register64Bit_r64(RBP);
+		instruction_stream.ret_Immediate16Bit(Immediate16Bit(8));
+		instruction_stream.finish().encoded_bytes().to_vec()
+	};
+	
+	assert_eq!(bytes_to_string(&generic), bytes_to_string(&specialised), "generic mnemonics did not emit the same instructions");
+}
+
+#[test]
+pub fn operand_polymorphic_mnemonics_choose_the_preferred_method()
+{
+	let operands = include_str!(concat!(env!("OUT_DIR"), "/Operands.rs"));
+	
+	let chosen_method = |implementation: &str|
+	{
+		let after_implementation = &operands[operands.find(implementation).expect("implementation was missing") .. ];
+		let call = &after_implementation[after_implementation.find("\t\tself.").unwrap() + "\t\tself.".len() .. ];
+		call[ .. call.find('(').unwrap()].to_string()
+	};
+	
+	assert_eq!(chosen_method("impl<'a> PushOperands<Register64Bit> for InstructionStream<'a>"), "push_Register64Bit_r64", "push() did not prefer the r64 encoding");
+	assert_eq!(chosen_method("impl<'a> PopOperands<Register64Bit> for InstructionStream<'a>"), "pop_Register64Bit_r64", "pop() did not prefer the r64 encoding");
+	assert_eq!(chosen_method("impl<'a> JneOperands<Label> for InstructionStream<'a>"), "jne_Label_1", "jne() did not prefer the method without a result");
+}
+
+#[test]
+pub fn every_specialised_method_has_an_operands_implementation()
+{
+	let instructions = include_str!("../InstructionStream.instructions.rs");
+	let operands = include_str!(concat!(env!("OUT_DIR"), "/Operands.rs"));
+	
+	fn mnemonic(method_name: &str) -> &str
+	{
+		method_name.split('_').next().unwrap()
+	}
+	
+	// Mnemonic and operand types of each implementation.
+	let mut implemented = HashSet::new();
+	let mut operand_types = "";
+	for line in operands.lines()
+	{
+		if line.starts_with("impl<'a> ")
+		{
+			let after_trait_name = &line[line.find("Operands<").unwrap() + "Operands<".len() .. ];
+			operand_types = &after_trait_name[ .. after_trait_name.find("> for ").unwrap()];
+		}
+		else if line.starts_with("\t\tself.")
+		{
+			implemented.insert((mnemonic(&line["\t\tself.".len() .. ]), operand_types));
+		}
+	}
+	
+	let mut unimplemented = Vec::new();
+	for signature in instructions.lines().filter(|line| line.starts_with("\tpub fn "))
+	{
+		let method_name = &signature["\tpub fn ".len() .. signature.find('(').unwrap()];
+		let parameters = &signature[signature.find('(').unwrap() + 1 .. signature.find(')').unwrap()];
+		let parameter_types: Vec<&str> = parameters.split(", ").skip(1).map(|parameter| &parameter[parameter.find(": ").unwrap() + 2 .. ]).collect();
+		if parameter_types.is_empty()
+		{
+			continue
+		}
+		
+		// Methods with an implicit operand or prefix in their name, such as `shl_Register8Bit_CL()`, need not have an implementation.
+		let mut spelt_out_types: Vec<&str> = method_name.split('_').skip(1).collect();
+		while let Some(&"1") | Some(&"r64") | Some(&"rm64") | Some(&"m64") = spelt_out_types.last()
+		{
+			spelt_out_types.pop();
+		}
+		let spells_out_operand_types = spelt_out_types.len() == parameter_types.len() && spelt_out_types.iter().zip(parameter_types.iter()).all(|(&spelt_out_type, &parameter_type)| parameter_type.starts_with(spelt_out_type) || (spelt_out_type, parameter_type) == ("Masking", "OpmaskRegister"));
+		if !spells_out_operand_types
+		{
+			continue
+		}
+		
+		// Of several methods with the same operand types, only one is implemented.
+		if !implemented.contains(&(mnemonic(method_name), &parameter_types.join(", ")[..]))
+		{
+			unimplemented.push(method_name);
+		}
+	}
+	assert!(unimplemented.is_empty(), "specialised methods without an operands trait implementation: {:?}", unimplemented);
+}
+
+#[test]
+pub fn shortest_encodings()
+{
+	use self::Register32Bit::EAX;
+	use self::Register8Bit::AL;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.add_Register64Bit_Immediate32Bit_shortest(RAX, Immediate32Bit(1));
+		instruction_stream.add_Register64Bit_Immediate32Bit_shortest(RAX, Immediate32Bit(0x1000));
+		instruction_stream.add_Register64Bit_Immediate32Bit_shortest(RBX, Immediate32Bit(0x1000));
+		instruction_stream.cmp_Register8Bit_Immediate8Bit_shortest(AL, Immediate8Bit(5));
+		instruction_stream.test_Register32Bit_Immediate32Bit_shortest(EAX, Immediate32Bit(1));
+		instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RCX, Immediate64Bit(0), false);
+		instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RCX, Immediate64Bit(0), true);
+		instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RCX, Immediate64Bit(-1), true);
+		instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RCX, Immediate64Bit(1 << 40), true);
+		
+		let finished_code = instruction_stream.finish();
+		assert_eq!(bytes_to_string(finished_code.encoded_bytes()), "48 83 C0 01 48 05 00 10 00 00 48 81 C3 00 10 00 00 3C 05 A9 01 00 00 00 31 C9 B9 00 00 00 00 48 C7 C1 FF FF FF FF 48 B9 00 00 00 00 00 01 00 00", "shortest encodings were not chosen");
+	}
+	
+	for &value in [0, 1, ::std::u32::MAX as i64, -1, ::std::i32::MIN as i64, 1 << 40, ::std::i64::MIN].iter()
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let function_pointer: unsafe extern "C" fn() -> i64 = instruction_stream.nullary_function_pointer();
+		instruction_stream.mov_Register64Bit_Immediate64Bit(RAX, Immediate64Bit(0x5A5A_5A5A_5A5A_5A5A));
+		instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(value), false);
+		instruction_stream.ret();
+		instruction_stream.finish();
+		
+		assert_eq!(unsafe { function_pointer() }, value, "shortest encoding did not load the value");
+	}
+}
+
+#[test]
+pub fn constant_pool()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.relax_jumps_on_finish();
+	
+	let one_and_a_half = instruction_stream.constant_f64(1.5);
+	let returns_f64 = instruction_stream.create_and_attach_label();
+	instruction_stream.movsd_XMMRegister_Any64BitMemory(XMMRegister::XMM0, one_and_a_half.into());
+	instruction_stream.ret();
+	instruction_stream.flush_constant_pool();
+	
+	let seven = instruction_stream.constant_u64(7);
+	assert_eq!(instruction_stream.constant_u64(7), seven, "constants were not deduplicated");
+	assert_eq!(instruction_stream.constant_f64(1.5), one_and_a_half, "flushed constants were not reused");
+	let vector = instruction_stream.constant_u128((5 << 64) | 100);
+	
+	let returns_u64 = instruction_stream.create_and_attach_label();
+	let skip = instruction_stream.create_label();
+	instruction_stream.jmp_Label_1(skip);
+	instruction_stream.ud2();
+	instruction_stream.attach_label(skip);
+	instruction_stream.imul_Register64Bit_Any64BitMemory_Immediate8Bit(RAX, seven.into(), Immediate8Bit(6));
+	instruction_stream.movdqu_XMMRegister_Any128BitMemory(XMMRegister::XMM0, vector.into());
+	instruction_stream.movq_Register64Bit_XMMRegister(RCX, XMMRegister::XMM0);
+	instruction_stream.add_Register64Bit_Register64Bit(RAX, RCX);
+	instruction_stream.ret();
+	
+	let finished_code = instruction_stream.finish();
+	
+	let offset = finished_code.label_offset(returns_u64).unwrap();
+	assert_eq!(finished_code.encoded_bytes()[offset], 0xEB, "jump was not relaxed");
+	assert_eq!(finished_code.label_offset(vector.label()).unwrap() % 16, 0, "constant was not aligned");
+	
+	let returns_f64: unsafe extern "C" fn() -> f64 = finished_code.nullary_function_pointer(returns_f64).unwrap();
+	assert_eq!(unsafe { returns_f64() }, 1.5, "constant was not loaded");
+	
+	let returns_u64: unsafe extern "C" fn() -> u64 = finished_code.nullary_function_pointer(returns_u64).unwrap();
+	assert_eq!(unsafe { returns_u64() }, 142, "constants were not loaded");
+}
+
+#[test]
+pub fn label_relative_memory_operands()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let data = instruction_stream.create_and_attach_label();
+	instruction_stream.emit_quad_word(0x1111);
+	instruction_stream.emit_quad_word(0x2222);
+	
+	let returns_address_of_data = instruction_stream.create_and_attach_label();
+	instruction_stream.lea_Register64Bit_Any64BitMemory(RAX, Any64BitMemory::relative_instruction_pointer_relative_label(data));
+	instruction_stream.ret();
+	
+	let later = instruction_stream.create_label();
+	let returns_sum = instruction_stream.create_and_attach_label();
+	instruction_stream.mov_Register64Bit_Any64BitMemory(RAX, Any64BitMemory::relative_instruction_pointer_relative_label_displacement(data, Immediate32Bit(8)));
+	instruction_stream.add_Register64Bit_Any64BitMemory(RAX, Any64BitMemory::relative_instruction_pointer_relative_label(later));
+	instruction_stream.ret();
+	instruction_stream.attach_label(later);
+	instruction_stream.emit_quad_word(0x3000);
+	
+	let finished_code = instruction_stream.finish();
+	
+	let returns_address_of_data: unsafe extern "C" fn() -> usize = finished_code.nullary_function_pointer(returns_address_of_data).unwrap();
+	assert_eq!(unsafe { returns_address_of_data() }, finished_code.label_address(data).unwrap(), "LEA of a label was not resolved");
+	
+	let returns_sum: unsafe extern "C" fn() -> u64 = finished_code.nullary_function_pointer(returns_sum).unwrap();
+	assert_eq!(unsafe { returns_sum() }, 0x5222, "MOV and ADD from labels were not resolved");
+}
+
+#[test]
+pub fn relative_jump_table()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.relax_jumps_on_finish();
+	
+	let cases = [instruction_stream.create_label(), instruction_stream.create_label(), instruction_stream.create_label()];
+	let default = instruction_stream.create_label();
+	
+	let function = instruction_stream.create_and_attach_label();
+	instruction_stream.jmp_relative_jump_table(RDI, RCX, &cases, Some(default));
+	
+	for (value, &case) in cases.iter().enumerate()
+	{
+		instruction_stream.attach_label(case);
+		instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(value as i64 * 10), false);
+		instruction_stream.ret();
+	}
+	
+	instruction_stream.attach_label(default);
+	instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(-1), false);
+	instruction_stream.ret();
+	
+	let finished_code = instruction_stream.finish();
+	let function: unsafe extern "C" fn(u64) -> i64 = finished_code.unary_function_pointer(function).unwrap();
+	
+	for &(index, expected) in [(0, 0), (1, 10), (2, 20), (3, -1), (100, -1), (u64::MAX, -1)].iter()
+	{
+		assert_eq!(unsafe { function(index) }, expected, "jump table dispatched index {} wrongly", index);
+	}
+}
+
+#[test]
+#[should_panic(expected = "index and scratch must be different registers")]
+pub fn relative_jump_table_rejects_the_same_index_and_scratch_register()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let cases = [instruction_stream.create_label()];
+	instruction_stream.jmp_relative_jump_table(RDI, RDI, &cases, None);
+}
+
+#[test]
+pub fn structured_control_flow()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.relax_jumps_on_finish();
+	
+	let absolute = instruction_stream.create_and_attach_label();
+	instruction_stream.if_else
+	(
+		|instruction_stream|
+		{
+			instruction_stream.cmp_Register64Bit_Immediate32Bit_shortest(RDI, Immediate32Bit(0));
+			ConditionCode::Less
+		},
+		|instruction_stream| instruction_stream.neg_Register64Bit(RDI),
+		|instruction_stream| instruction_stream.if_(|_| ConditionCode::Equal, |instruction_stream| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RDI, Immediate64Bit(100), false)),
+	);
+	instruction_stream.lea_Register64Bit_Any64BitMemory(RAX, Any64BitMemory::base_64(RDI));
+	instruction_stream.ret();
+	
+	let triangular_number_while = instruction_stream.create_and_attach_label();
+	instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(0), false);
+	instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RCX, Immediate64Bit(0), false);
+	instruction_stream.while_
+	(
+		|instruction_stream|
+		{
+			instruction_stream.cmp_Register64Bit_Register64Bit(RCX, RDI);
+			ConditionCode::Below
+		},
+		|instruction_stream, _|
+		{
+			instruction_stream.add_Register64Bit_Immediate32Bit_shortest(RCX, Immediate32Bit(1));
+			instruction_stream.add_Register64Bit_Register64Bit(RAX, RCX);
+		},
+	);
+	instruction_stream.ret();
+	
+	let triangular_number_loop_n = instruction_stream.create_and_attach_label();
+	instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(0), false);
+	instruction_stream.loop_n(RDI, |instruction_stream, _| instruction_stream.add_Register64Bit_Register64Bit(RAX, RDI));
+	instruction_stream.ret();
+	
+	let capped_triangular_number_do_while = instruction_stream.create_and_attach_label();
+	instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(0), false);
+	instruction_stream.do_while
+	(
+		|instruction_stream, loop_labels|
+		{
+			instruction_stream.add_Register64Bit_Register64Bit(RAX, RDI);
+			instruction_stream.cmp_Register64Bit_Immediate32Bit_shortest(RAX, Immediate32Bit(20));
+			instruction_stream.jae_Label_1(loop_labels.break_label);
+			instruction_stream.sub_Register64Bit_Immediate32Bit_shortest(RDI, Immediate32Bit(1));
+		},
+		|_| ConditionCode::NotEqual,
+	);
+	instruction_stream.ret();
+	
+	let dense_values = [10, 11, 12, 14, 13];
+	let dense_switch = instruction_stream.create_and_attach_label();
+	instruction_stream.switch(RDI, RCX, &dense_values, |instruction_stream, position| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(position as i64), false), |instruction_stream| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(-1), false));
+	instruction_stream.ret();
+	
+	let sparse_values = [1000, -5, 7, 300000, 42, -70000, 9];
+	let sparse_switch = instruction_stream.create_and_attach_label();
+	instruction_stream.switch(RDI, RCX, &sparse_values, |instruction_stream, position| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(position as i64), false), |instruction_stream| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(-1), false));
+	instruction_stream.ret();
+	
+	let finished_code = instruction_stream.finish();
+	
+	let absolute: unsafe extern "C" fn(i64) -> i64 = finished_code.unary_function_pointer(absolute).unwrap();
+	assert_eq!(unsafe { absolute(-5) }, 5, "if_else() did not run then");
+	assert_eq!(unsafe { absolute(5) }, 5, "if_else() did not run otherwise");
+	assert_eq!(unsafe { absolute(0) }, 100, "if_() did not run then");
+	
+	for &(function, name) in [(triangular_number_while, "while_()"), (triangular_number_loop_n, "loop_n()")].iter()
+	{
+		let function: unsafe extern "C" fn(u64) -> u64 = finished_code.unary_function_pointer(function).unwrap();
+		for &n in [0, 1, 2, 10].iter()
+		{
+			assert_eq!(unsafe { function(n) }, n * (n + 1) / 2, "{} looped wrongly for {}", name, n);
+		}
+	}
+	
+	let capped_triangular_number_do_while: unsafe extern "C" fn(u64) -> u64 = finished_code.unary_function_pointer(capped_triangular_number_do_while).unwrap();
+	assert_eq!(unsafe { capped_triangular_number_do_while(3) }, 6, "do_while() looped wrongly");
+	assert_eq!(unsafe { capped_triangular_number_do_while(10) }, 27, "do_while() did not break");
+	
+	for &(function, values) in [(dense_switch, &dense_values[..]), (sparse_switch, &sparse_values[..])].iter()
+	{
+		let function: unsafe extern "C" fn(i64) -> i64 = finished_code.unary_function_pointer(function).unwrap();
+		for (position, &value) in values.iter().enumerate()
+		{
+			assert_eq!(unsafe { function(value as i64) }, position as i64, "switch() dispatched {} wrongly", value);
+		}
+		for &value in [-1, 0, 8, 15, 1001, i64::MIN, i64::MAX].iter()
+		{
+			assert_eq!(unsafe { function(value) }, -1, "switch() did not dispatch {} to default", value);
+		}
+	}
+}
+
+#[test]
+pub fn switch_lowering()
+{
+	// Values, whether a jump table is expected and the first instruction.
+	let switches: [(&[i32], bool, &str); 5] =
+	[
+		(&[-1, -3, 0, 2, -2, 1], true, "sub rdi, -0x3"),
+		(&[0, 4, 8, 11], true, "cmp rdi, 0xc"),
+		(&[0, 4, 8, 12], false, "cmp rdi, 0x8"),
+		(&[2, 0, 1], false, "cmp rdi, 0x0"),
+		(&[1000, -5, 300000, 9, -70000], false, "cmp rdi, 0x9"),
+	];
+	
+	for &(values, uses_jump_table, first_instruction) in switches.iter()
+	{
+		let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		
+		let function = instruction_stream.create_and_attach_label();
+		instruction_stream.switch(RDI, RCX, values, |instruction_stream, position| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(position as i64), false), |instruction_stream| instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(-1), false));
+		instruction_stream.ret();
+		
+		let finished_code = instruction_stream.finish();
+		
+		let function_pointer: unsafe extern "C" fn(i64) -> i64 = finished_code.unary_function_pointer(function).unwrap();
+		
+		let instructions: Vec<String> = finished_code.disassemble().take(16).map(|result| result.map(|instruction| instruction.to_string()).unwrap_or_default()).collect();
+		assert_eq!(instructions[0], first_instruction, "switch() on {:?} started wrongly", values);
+		assert_eq!(instructions.iter().any(|instruction| instruction == "jmp rcx"), uses_jump_table, "switch() on {:?} was not lowered as expected", values);
+		if !uses_jump_table
+		{
+			let default = instructions.iter().position(|instruction| instruction == "mov rax, 0xffffffff").expect("default was not found");
+			assert!(!instructions[default - 1].starts_with("jmp "), "switch() on {:?} jumped to default immediately before it", values);
+		}
+		
+		for (position, &value) in values.iter().enumerate()
+		{
+			assert_eq!(unsafe { function_pointer(value as i64) }, position as i64, "switch() on {:?} dispatched {} wrongly", values, value);
+		}
+		
+		let minimum = *values.iter().min().unwrap() as i64;
+		let maximum = *values.iter().max().unwrap() as i64;
+		for &value in [minimum - 1, maximum + 1, 3, i64::MIN, i64::MAX].iter()
+		{
+			assert_eq!(unsafe { function_pointer(value) }, -1, "switch() on {:?} did not dispatch {} to default", values, value);
+		}
+	}
+}
+
+#[test]
+#[should_panic(expected = "values contains duplicates")]
+pub fn switch_rejects_duplicate_values()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	instruction_stream.switch(RDI, RCX, &[1, 2, 1], |_, _| (), |_| ());
+}
+
+#[test]
+pub fn condition_codes()
+{
+	use self::ConditionCode::*;
+	use self::Register8Bit::AL;
+	
+	let all = [Overflow, NotOverflow, Below, AboveOrEqual, Equal, NotEqual, BelowOrEqual, Above, Sign, NotSign, Parity, NotParity, Less, GreaterOrEqual, LessOrEqual, Greater];
+	for &condition_code in all.iter()
+	{
+		assert_ne!(condition_code.invert(), condition_code, "invert() did not change {:?}", condition_code);
+		assert_eq!(condition_code.invert().invert(), condition_code, "invert() is not its own inverse for {:?}", condition_code);
+		if let Some(swapped) = condition_code.swap_operands()
+		{
+			assert_eq!(swapped.swap_operands(), Some(condition_code), "swap_operands() is not its own inverse for {:?}", condition_code);
+		}
+	}
+	assert_eq!(Less.invert(), GreaterOrEqual);
+	assert_eq!(Less.swap_operands(), Some(Greater));
+	assert_eq!(Sign.swap_operands(), None);
+	assert_eq!(ConditionCode::less_than(false), Below);
+	assert!(ConditionCode::greater_than(true).is_signed() && !Above.is_signed() && Above.is_unsigned());
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let generic = {
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let label = instruction_stream.create_and_attach_label();
+		instruction_stream.jcc(ConditionCode::NotZero, label);
+		instruction_stream.setcc(Above, AL);
+		instruction_stream.cmovcc(LessOrEqual, RAX, RCX);
+		bytes_to_string(instruction_stream.finish().encoded_bytes())
+	};
+	let specialised = {
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		let label = instruction_stream.create_and_attach_label();
+		instruction_stream.jne_Label_1(label);
+		instruction_stream.seta_Register8Bit(AL);
+		instruction_stream.cmovle_Register64Bit_Register64Bit(RAX, RCX);
+		bytes_to_string(instruction_stream.finish().encoded_bytes())
+	};
+	assert_eq!(generic, specialised, "generic condition code mnemonics did not match the specialised ones");
+	
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	let function: unsafe extern "C" fn(i64, i64) -> i64 = instruction_stream.binary_function_pointer();
+	instruction_stream.cmp_Register64Bit_Register64Bit(RDI, RSI);
+	instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(0), true);
+	instruction_stream.setcc(ConditionCode::less_than(true).swap_operands().unwrap(), AL);
+	instruction_stream.cmovcc(Equal, RAX, RSI);
+	instruction_stream.ret();
+	instruction_stream.finish();
+	
+	assert_eq!(unsafe { function(5, 3) }, 1, "setcc() did not set");
+	assert_eq!(unsafe { function(-5, 3) }, 0, "setcc() did not clear");
+	assert_eq!(unsafe { function(7, 7) }, 7, "cmovcc() did not move");
+}
+
+#[test]
+pub fn vector_sib_memory()
+{
+	use self::XMMRegister::*;
+	use self::YMMRegister::*;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	instruction_stream.vgatherdps_XMMRegister_Vsib32X_XMMRegister(XMM1, Vsib32X::index_scale(XMM2, IndexScale::x4), XMM3);
+	instruction_stream.vpgatherdd_XMMRegister_Vsib32X_XMMRegister(XMM1, Vsib32X::index_scale_displacement(XMM10, IndexScale::x8, Immediate32Bit(0x10)), XMM3);
+	instruction_stream.vgatherdps_XMMRegister_Vsib32X_XMMRegister(XMM1, Vsib32X::base_64_index_scale(RBP, XMM2, IndexScale::x1), XMM3);
+	instruction_stream.vgatherqpd_YMM_Vsib64Y_YMM(YMM1, Vsib64Y::base_64_index_scale(R13, YMM9, IndexScale::x8), YMM3);
+	instruction_stream.vpgatherdq_YMM_Vsib32X_YMM(YMM4, Vsib32X::base_64_index_scale_displacement(RAX, XMM12, IndexScale::x2, Immediate32Bit(0x40)), YMM5);
+	instruction_stream.vpgatherqq_XMMRegister_Vsib64X_XMMRegister(XMM8, Vsib64X::base_32_index_scale(Register32Bit::EBP, XMM15, IndexScale::x4), XMM9);
+	instruction_stream.vgatherqps_XMMRegister_Vsib64Y_XMMRegister(XMM1, Vsib64Y::base_64_index_scale_displacement(R12, YMM8, IndexScale::x1, Immediate32Bit(-0x80)), XMM2);
+	instruction_stream.vgatherdpd_YMM_Vsib32X_YMM(YMM2, Vsib32X::base_32_index_scale_displacement(Register32Bit::R13D, XMM11, IndexScale::x8, Immediate32Bit(0x80)), YMM3);
+	
+	let finished_code = instruction_stream.finish();
+	let expected = concat!
+	(
+		"C4 E2 61 92 0C 95 00 00 00 00 ",
+		"C4 A2 61 90 0C D5 10 00 00 00 ",
+		"C4 E2 61 92 4C 15 00 ",
+		"C4 82 E5 93 4C CD 00 ",
+		"C4 A2 D5 90 64 60 40 ",
+		"67 C4 22 B1 91 44 BD 00 ",
+		"C4 82 6D 93 4C 04 80 ",
+		"67 C4 82 E5 92 94 DD 80 00 00 00"
+	);
+	assert_eq!(bytes_to_string(finished_code.encoded_bytes()), expected, "VSIB memory operands were not encoded correctly");
+	
+	let instructions: Vec<String> = finished_code.disassemble().map(|result| result.unwrap().to_string()).collect();
+	let expected =
+	[
+		"vgatherdps xmm1, dword ptr [xmm2*4], xmm3",
+		"vpgatherdd xmm1, dword ptr [xmm10*8+0x10], xmm3",
+		"vgatherdps xmm1, dword ptr [rbp+xmm2*1], xmm3",
+		"vgatherqpd ymm1, qword ptr [r13+ymm9*8], ymm3",
+		"vpgatherdq ymm4, qword ptr [rax+xmm12*2+0x40], ymm5",
+		"vpgatherqq xmm8, qword ptr [ebp+xmm15*4], xmm9",
+		"vgatherqps xmm1, dword ptr [r12+ymm8*1-0x80], xmm2",
+		"vgatherdpd ymm2, qword ptr [r13d+xmm11*8+0x80], ymm3",
+	];
+	assert_eq!(instructions, expected, "VSIB memory operands were not disassembled");
+}
+
+#[test]
+#[should_panic(expected = "VSIB index registers 16 to 31 can only be encoded using EVEX")]
+pub fn vector_sib_index_registers_16_to_31_are_rejected()
+{
+	Vsib32X::base_64_index_scale(RAX, XMMRegister::XMM16, IndexScale::x4);
+}
+
+#[test]
+pub fn instruction_set_extensions()
+{
+	use self::XMMRegister::*;
+	use self::YMMRegister::*;
+	
+	// Description, instructions, encoding and disassembly.
+	type Extension = (&'static str, fn(&mut InstructionStream), &'static str, &'static [&'static str]);
+	let extensions: [Extension; 5] =
+	[
+		(
+			"SHA extensions",
+			|instruction_stream|
+			{
+				instruction_stream.sha1rnds4_XMMRegister_XMMRegister_Immediate8Bit(XMM1, XMM2, Immediate8Bit(3));
+				instruction_stream.sha1nexte_XMMRegister_Any128BitMemory(XMM8, Any128BitMemory::base_64(RAX));
+				instruction_stream.sha1msg1_XMMRegister_XMMRegister(XMM1, XMM9);
+				instruction_stream.sha1msg2_XMMRegister_XMMRegister(XMM1, XMM2);
+				instruction_stream.sha256rnds2_XMMRegister_XMMRegister_XMMRegister0(XMM1, XMM2);
+				instruction_stream.sha256msg1(XMM3, XMM4);
+				instruction_stream.sha256msg2_XMMRegister_Any128BitMemory(XMM1, Any128BitMemory::base_64_displacement(RSP, Immediate32Bit(16)));
+				instruction_stream.sha1rnds4_XMMRegister_Any128BitMemory_Immediate8Bit(XMM12, Any128BitMemory::relative_instruction_pointer_relative_displacement(Immediate32Bit(0x100)), Immediate8Bit(2));
+				instruction_stream.sha256rnds2_XMMRegister_Any128BitMemory_XMMRegister0(XMM9, Any128BitMemory::base_32_index_32_scale(Register32Bit::R13D, Register32Bit::R14D, IndexScale::x8));
+				instruction_stream.sha1msg2_XMMRegister_XMMRegister(XMM15, XMM8);
+			},
+			"0F 3A CC CA 03 44 0F 38 C8 00 41 0F 38 C9 C9 0F 38 CA CA 0F 38 CB CA 0F 38 CC DC 0F 38 CD 4C 24 10 44 0F 3A CC 25 00 01 00 00 02 67 47 0F 38 CB 4C F5 00 45 0F 38 CA F8",
+			&[
+				"sha1rnds4 xmm1, xmm2, 0x3",
+				"sha1nexte xmm8, xmmword ptr [rax]",
+				"sha1msg1 xmm1, xmm9",
+				"sha1msg2 xmm1, xmm2",
+				"sha256rnds2 xmm1, xmm2, xmm0",
+				"sha256msg1 xmm3, xmm4",
+				"sha256msg2 xmm1, xmmword ptr [rsp+0x10]",
+				"sha1rnds4 xmm12, xmmword ptr [rip+0x100], 0x2",
+				"sha256rnds2 xmm9, xmmword ptr [r13d+r14d*8], xmm0",
+				"sha1msg2 xmm15, xmm8",
+			],
+		),
+		(
+			"ADX, RDSEED and time-stamp instructions",
+			|instruction_stream|
+			{
+				instruction_stream.adcx(RAX, RBX);
+				instruction_stream.adox(Register32Bit::R8D, Any32BitMemory::base_64(RCX));
+				instruction_stream.rdseed(RAX);
+				instruction_stream.rdseed_Register16Bit(Register16Bit::AX);
+				instruction_stream.rdtsc();
+				instruction_stream.rdtscp();
+				instruction_stream.rdpid_Register64Bit(RCX);
+				instruction_stream.adox(R15, Any64BitMemory::relative_instruction_pointer_relative_displacement(Immediate32Bit(0x20)));
+				instruction_stream.adcx(Register32Bit::R9D, Any32BitMemory::base_32_index_32_scale_displacement(Register32Bit::R8D, Register32Bit::R11D, IndexScale::x2, Immediate32Bit(0x7F)));
+				instruction_stream.rdseed(Register32Bit::R12D);
+				instruction_stream.rdpid_Register64Bit(R14);
+				instruction_stream.adcx(R10, R11);
+			},
+			"66 48 0F 38 F6 C3 F3 44 0F 38 F6 01 48 0F C7 F8 66 0F C7 F8 0F 31 0F 01 F9 F3 0F C7 F9 F3 4C 0F 38 F6 3D 20 00 00 00 67 66 47 0F 38 F6 4C 58 7F 41 0F C7 FC F3 41 0F C7 FE 66 4D 0F 38 F6 D3",
+			&[
+				"adcx rax, rbx",
+				"adox r8d, dword ptr [rcx]",
+				"rdseed rax",
+				"rdseed ax",
+				"rdtsc",
+				"rdtscp",
+				"rdpid rcx",
+				"adox r15, qword ptr [rip+0x20]",
+				"adcx r9d, dword ptr [r8d+r11d*2+0x7f]",
+				"rdseed r12d",
+				"rdpid r14",
+				"adcx r10, r11",
+			],
+		),
+		(
+			"Cache and persistent memory control instructions",
+			|instruction_stream|
+			{
+				instruction_stream.clwb(Any8BitMemory::base_64(RDI));
+				instruction_stream.clflushopt(Any8BitMemory::base_64_displacement(RDI, Immediate32Bit(64)));
+				instruction_stream.sfence();
+				instruction_stream.cldemote(Any8BitMemory::base_64(RAX));
+				instruction_stream.movdiri(Any64BitMemory::base_64(RDX), RAX);
+				instruction_stream.movdiri(Any32BitMemory::base_64(RDX), Register32Bit::ECX);
+				instruction_stream.movdir64b(RAX, Contiguous64ByteMemory::base_64(RSI));
+				instruction_stream.prefetchwt1(Any8BitMemory::base_64(RBX));
+				instruction_stream.clwb(Any8BitMemory::relative_instruction_pointer_relative_displacement(Immediate32Bit(64)));
+				instruction_stream.clflushopt(Any8BitMemory::base_64(R12));
+				instruction_stream.movdiri(Any64BitMemory::base_32_index_32_scale(Register32Bit::R9D, Register32Bit::R10D, IndexScale::x8), R11);
+				instruction_stream.movdir64b(R15, Contiguous64ByteMemory::base_64(R13));
+				instruction_stream.cldemote(Any8BitMemory::base_32(Register32Bit::R8D));
+			},
+			"66 0F AE 37 66 0F AE 7F 40 0F AE F8 0F 1C 00 48 0F 38 F9 02 0F 38 F9 0A 66 0F 38 F8 06 0F 0D 13 66 0F AE 35 40 00 00 00 66 41 0F AE 3C 24 67 4F 0F 38 F9 1C D1 66 45 0F 38 F8 7D 00 67 41 0F 1C 00",
+			&[
+				"clwb byte ptr [rdi]",
+				"clflushopt byte ptr [rdi+0x40]",
+				"sfence",
+				"cldemote byte ptr [rax]",
+				"movdiri qword ptr [rdx], rax",
+				"movdiri dword ptr [rdx], ecx",
+				"movdir64b rax, [rsi]",
+				"prefetchwt1 byte ptr [rbx]",
+				"clwb byte ptr [rip+0x40]",
+				"clflushopt byte ptr [r12]",
+				"movdiri qword ptr [r9d+r10d*8], r11",
+				"movdir64b r15, [r13]",
+				"cldemote byte ptr [r8d]",
+			],
+		),
+		(
+			"CET instructions",
+			|instruction_stream|
+			{
+				instruction_stream.endbr64();
+				instruction_stream.incsspq(RAX);
+				instruction_stream.rdsspq(RCX);
+				instruction_stream.saveprevssp();
+				instruction_stream.rstorssp(Any64BitMemory::base_64(RAX));
+				instruction_stream.wrssq(Any64BitMemory::base_64(RDI), RSI);
+				instruction_stream.setssbsy();
+				instruction_stream.clrssbsy(Any64BitMemory::base_64(RDX));
+				instruction_stream.incsspq(R9);
+				instruction_stream.rdsspq(R15);
+				instruction_stream.rstorssp(Any64BitMemory::relative_instruction_pointer_relative_displacement(Immediate32Bit(0x80)));
+				instruction_stream.wrssq(Any64BitMemory::base_32_index_32_scale(Register32Bit::R10D, Register32Bit::R12D, IndexScale::x4), R8);
+				instruction_stream.clrssbsy(Any64BitMemory::base_64(R13));
+			},
+			"F3 0F 1E FA F3 48 0F AE E8 F3 48 0F 1E C9 F3 0F 01 EA F3 0F 01 28 48 0F 38 F6 37 F3 0F 01 E8 F3 0F AE 32 F3 49 0F AE E9 F3 49 0F 1E CF F3 0F 01 2D 80 00 00 00 67 4F 0F 38 F6 04 A2 F3 41 0F AE 75 00",
+			&[
+				"endbr64",
+				"incsspq rax",
+				"rdsspq rcx",
+				"saveprevssp",
+				"rstorssp qword ptr [rax]",
+				"wrssq qword ptr [rdi], rsi",
+				"setssbsy",
+				"clrssbsy qword ptr [rdx]",
+				"incsspq r9",
+				"rdsspq r15",
+				"rstorssp qword ptr [rip+0x80]",
+				"wrssq qword ptr [r10d+r12d*4], r8",
+				"clrssbsy qword ptr [r13]",
+			],
+		),
+		(
+			"VEX encoded dot products and conversions",
+			|instruction_stream|
+			{
+				instruction_stream.vpdpbusd(XMM0, XMM1, XMM2);
+				instruction_stream.vpmadd52luq(YMM1, YMM2, Any256BitMemory::base_64(RAX));
+				instruction_stream.vpdpbssd(XMM3, XMM4, XMM5);
+				instruction_stream.vcvtneps2bf16(XMM0, YMM1);
+				instruction_stream.vbcstnebf162ps(YMM2, Any16BitMemory::base_64(RSI));
+				instruction_stream.vpdpwssds(YMM8, YMM9, YMM10);
+				instruction_stream.vpdpbuud(XMM13, XMM14, Any128BitMemory::relative_instruction_pointer_relative_displacement(Immediate32Bit(0x10)));
+				instruction_stream.vcvtneoph2ps(YMM12, Any256BitMemory::base_32_index_32_scale_displacement(Register32Bit::R9D, Register32Bit::R15D, IndexScale::x2, Immediate32Bit(8)));
+				instruction_stream.vbcstnesh2ps(XMM11, Any16BitMemory::base_32(Register32Bit::R13D));
+				instruction_stream.vpmadd52huq(XMM8, XMM15, XMM9);
+			},
+			"C4 E2 71 50 C2 C4 E2 ED B4 08 C4 E2 5B 50 DD C4 E2 7E 72 C1 C4 E2 7E B1 16 C4 42 35 53 C2 C4 62 08 50 2D 10 00 00 00 67 C4 02 7C B0 64 79 08 67 C4 42 79 B1 5D 00 C4 42 81 B5 C1",
+			&[
+				"vpdpbusd xmm0, xmm1, xmm2",
+				"vpmadd52luq ymm1, ymm2, ymmword ptr [rax]",
+				"vpdpbssd xmm3, xmm4, xmm5",
+				"vcvtneps2bf16 xmm0, ymm1",
+				"vbcstnebf162ps ymm2, word ptr [rsi]",
+				"vpdpwssds ymm8, ymm9, ymm10",
+				"vpdpbuud xmm13, xmm14, xmmword ptr [rip+0x10]",
+				"vcvtneoph2ps ymm12, ymmword ptr [r9d+r15d*2+0x8]",
+				"vbcstnesh2ps xmm11, word ptr [r13d]",
+				"vpmadd52huq xmm8, xmm15, xmm9",
+			],
+		),
+	];
+	
+	for &(description, emit, expected_encoding, expected_disassembly) in extensions.iter()
+	{
+		let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		
+		emit(&mut instruction_stream);
+		
+		let finished_code = instruction_stream.finish();
+		assert_eq!(bytes_to_string(finished_code.encoded_bytes()), expected_encoding, "{} were not encoded correctly", description);
+		
+		let instructions: Vec<String> = finished_code.disassemble().map(|result| result.unwrap().to_string()).collect();
+		assert_eq!(instructions, expected_disassembly, "{} were not disassembled", description);
+	}
+}
+
+#[test]
+pub fn indirect_branch_tracking_landing_pads()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	
+	let function =
+	{
+		let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+		instruction_stream.emit_landing_pads_for_indirect_branch_tracking();
+		
+		let function: unsafe extern "C" fn(u64) -> i64 = instruction_stream.unary_function_pointer();
+		instruction_stream.endbr64();
+		let cases = [instruction_stream.create_label(), instruction_stream.create_label()];
+		instruction_stream.jmp_relative_jump_table(RDI, RCX, &cases, None);
+		
+		for (value, &case) in cases.iter().enumerate()
+		{
+			instruction_stream.attach_label(case);
+			instruction_stream.mov_Register64Bit_Immediate64Bit_shortest(RAX, Immediate64Bit(value as i64 * 10), false);
+			instruction_stream.ret();
+		}
+		
+		let finished_code = instruction_stream.finish();
+		const ENDBR64: [u8; 4] = [0xF3, 0x0F, 0x1E, 0xFA];
+		let encoded_bytes = finished_code.encoded_bytes();
+		assert!(encoded_bytes.starts_with(&ENDBR64), "function pointer entry point was not a landing pad");
+		assert_eq!(encoded_bytes.windows(4).filter(|window| *window == ENDBR64).count(), 3, "jump table targets were not landing pads");
+		
+		function
+	};
+	
+	assert_eq!(unsafe { function(0) }, 0, "jump table dispatched index 0 wrongly");
+	assert_eq!(unsafe { function(1) }, 10, "jump table dispatched index 1 wrongly");
+	
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.emit_landing_pads_for_indirect_branch_tracking();
+	
+	let label = instruction_stream.create_and_attach_label();
+	instruction_stream.ret();
+	instruction_stream.emit_label(label);
+	
+	match instruction_stream.try_finish()
+	{
+		Err(AssemblerError::MissingLandingPad(reported)) => assert_eq!(reported, label, "wrong label reported"),
+		_ => panic!("missing landing pad was not reported"),
+	}
+	
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	instruction_stream.emit_landing_pads_for_indirect_branch_tracking();
+	
+	instruction_stream.ret();
+	let _: unsafe extern "C" fn() = instruction_stream.nullary_function_pointer();
+	instruction_stream.ret();
+	
+	match instruction_stream.try_finish()
+	{
+		Err(AssemblerError::MissingFunctionPointerLandingPad(offset)) => assert_eq!(offset, 1, "wrong offset reported"),
+		_ => panic!("missing function pointer landing pad was not reported"),
+	}
+}
+
+#[test]
+pub fn evex_encoding()
+{
+	use self::ZMMRegister::*;
+	
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	let memory = |displacement: i32| Any512BitMemory::base_64_displacement(RAX, Immediate32Bit(displacement));
+	
+	instruction_stream.vaddps_ZMM_ZMM_ZMM_Masking(ZMM1, ZMM2, ZMM3, Masking::zeroing(OpmaskRegister::K1));
+	instruction_stream.vaddps_ZMM_ZMM_ZMM_Masking(ZMM1, ZMM2, ZMM3, Masking::merging(OpmaskRegister::K2));
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory(ZMM1, ZMM2, memory(127 * 64));
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory(ZMM1, ZMM2, memory(128 * 64));
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory(ZMM1, ZMM2, memory(-128 * 64));
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory(ZMM1, ZMM2, memory(-129 * 64));
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory(ZMM1, ZMM2, memory(32));
+	instruction_stream.vaddps_XMMRegister_XMMRegister_Any128BitMemory_Masking(XMMRegister::XMM1, XMMRegister::XMM2, Any128BitMemory::base_64_displacement(RAX, Immediate32Bit(127 * 16)), Masking::Unmasked);
+	instruction_stream.vaddps_XMMRegister_XMMRegister_Any128BitMemory_Masking(XMMRegister::XMM1, XMMRegister::XMM2, Any128BitMemory::base_64_displacement(RAX, Immediate32Bit(128 * 16)), Masking::Unmasked);
+	instruction_stream.vaddps_ZMM_ZMM_ZMM(ZMM17, ZMM2, ZMM3);
+	instruction_stream.vaddps_ZMM_ZMM_ZMM(ZMM1, ZMM18, ZMM3);
+	instruction_stream.vaddps_ZMM_ZMM_ZMM(ZMM1, ZMM2, ZMM19);
+	instruction_stream.vaddps_XMMRegister_XMMRegister_XMMRegister_Masking(XMMRegister::XMM24, XMMRegister::XMM25, XMMRegister::XMM26, Masking::Unmasked);
+	instruction_stream.vaddps_ZMM_ZMM_Any512BitMemory(ZMM1, ZMM2, Any512BitMemory::base_64(R13));
+	
+	let finished_code = instruction_stream.finish();
+	let expected = concat!
+	(
+		"62 F1 6C C9 58 CB ",
+		"62 F1 6C 4A 58 CB ",
+		"62 F1 6C 48 58 48 7F ",
+		"62 F1 6C 48 58 88 00 20 00 00 ",
+		"62 F1 6C 48 58 48 80 ",
+		"62 F1 6C 48 58 88 C0 DF FF FF ",
+		"62 F1 6C 48 58 88 20 00 00 00 ",
+		"62 F1 6C 08 58 48 7F ",
+		"62 F1 6C 08 58 88 00 08 00 00 ",
+		"62 E1 6C 48 58 CB ",
+		"62 F1 6C 40 58 CB ",
+		"62 B1 6C 48 58 CB ",
+		"62 01 34 00 58 C2 ",
+		"62 D1 6C 48 58 4D 00"
+	);
+	assert_eq!(bytes_to_string(finished_code.encoded_bytes()), expected, "EVEX instructions were not encoded correctly");
+}
+
+#[test]
+#[should_panic(expected = "Registers 16 to 31 can only be encoded using EVEX")]
+pub fn registers_16_to_31_are_rejected_for_vex_encoding()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	instruction_stream.vaddps_XMMRegister_XMMRegister_XMMRegister(XMMRegister::XMM1, XMMRegister::XMM2, XMMRegister::XMM16);
+}
+
+#[test]
+#[should_panic(expected = "Stack adjustments can not exceed i32::MAX bytes")]
+pub fn stack_adjustments_larger_than_i32_max_are_rejected()
+{
+	let mut map = ExecutableAnonymousMemoryMap::new(4096, false, true).expect("Could not anonymously mmap");
+	let mut instruction_stream = map.instruction_stream(&InstructionStreamHints::default());
+	
+	instruction_stream.allocate_stack(i32::MAX as u32 + 1);
+}
+
+#[test]
+#[should_panic(expected = "K0 can not be used as a writemask")]
+pub fn k0_is_rejected_as_a_writemask()
+{
+	Masking::zeroing(OpmaskRegister::K0);
+}
+
+// Suitable for https://onlinedisassembler.com/odaweb/ .
+fn bytes_to_string(encoded_bytes: &[u8]) -> String
+{
+	let mut string = Vec::with_capacity(encoded_bytes.len() * 3);
+	
+	let mut after_first = false;
+	for byte in encoded_bytes
+	{
+		if after_first
+		{
+			write!(string, " ");
+		}
+		else
+		{
+			after_first = true
+		}
+		write!(string, "{:02X}", *byte);
+	}
+	
+	String::from_utf8(string).unwrap()
+}
+
+/// Encodes `form` with register operands of index 1 (`reg`), 2 (`rm`), 3 (`vvvv`) or 4 (`/is4`), memory operands of `[RAX]` (or `[RAX+XMM1*1]`) and immediates of 1.
+fn encode_instruction_form(form: &InstructionForm) -> Vec<u8>
+{
+	use super::decoder::InstructionEncoding::*;
+	use super::decoder::OperandLocation::*;
+	
+	const RegIndex: u8 = 1;
+	const RmIndex: u8 = 2;
+	const VvvvIndex: u8 = 3;
+	const OpcodeRegisterIndex: u8 = 1;
+	const ImmediateRegisterIndex: u8 = 4;
+	
+	let mut bytes = Vec::new();
+	
+	for &(prefix, byte) in [(Fwait, 0x9B), (OperandSizeOverride, 0x66), (RepeatNotEqual, 0xF2), (Repeat, 0xF3)].iter()
+	{
+		if form.has(prefix)
+		{
+			bytes.push(byte);
+		}
+	}
+	if form.has(AddressSizeOverride) && !form.has_memory_operand()
+	{
+		bytes.push(0x67);
+	}
+	if form.has(BranchHint)
+	{
+		bytes.push(0x3E);
+	}
+	
+	let vvvv = if form.has_vvvv_operand()
+	{
+		VvvvIndex
+	}
+	else
+	{
+		0
+	};
+	match form.encoding
+	{
+		Legacy { map, W } =>
+		{
+			if W == 1
+			{
+				bytes.push(0x48);
+			}
+			match map
+			{
+				0 => (),
+				1 => bytes.push(0x0F),
+				2 => bytes.extend_from_slice(&[0x0F, 0x38]),
+				_ => bytes.extend_from_slice(&[0x0F, 0x3A]),
+			}
+		}
+		
+		Vex { map, L, pp, W } => bytes.extend_from_slice(&[0xC4, 0xE0 | map, (W << 7) | ((!vvvv & 0x0F) << 3) | (L << 2) | pp]),
+		
+		Evex { map, LL, pp, W, .. } => bytes.extend_from_slice(&[0x62, 0xF0 | map, (W << 7) | ((!vvvv & 0x0F) << 3) | 0x04 | pp, (LL << 5) | 0x08]),
+	}
+	
+	bytes.extend_from_slice(form.opcode);
+	if form.opcode_register
+	{
+		*bytes.last_mut().unwrap() |= OpcodeRegisterIndex;
+	}
+	
+	let reg = match form.mod_rm
+	{
+		ModRmForm::NoModRm => None,
+		ModRmForm::ModRm => Some(if form.operands.iter().any(|operand| operand.0 == ModRmReg) { RegIndex } else { 0 }),
+		ModRmForm::ModRmExtension(extension) => Some(extension),
+	};
+	if let Some(reg) = reg
+	{
+		match form.operands.iter().find(|operand| operand.0 == ModRmRm).map(|operand| operand.1)
+		{
+			Some(kind) if kind.is_register() => bytes.push(0xC0 | (reg << 3) | RmIndex),
+			Some(OperandKind::VectorSibMemory(..)) => bytes.extend_from_slice(&[(reg << 3) | 0x04, RegIndex << 3]),
+			_ => bytes.push(reg << 3),
+		}
+	}
+	
+	for operand in form.operands.iter()
+	{
+		match operand.0
+		{
+			Immediate =>
+			{
+				bytes.push(1);
+				bytes.extend((1 .. operand.1.immediate_size()).map(|_| 0));
+			}
+			ImmediateRegister => bytes.push(ImmediateRegisterIndex << 4),
+			_ => (),
+		}
+	}
+	
+	bytes
+}
+
+/// Finds the symbol file registered for code at `start_instruction_pointer` by walking the list of entries as GDB would.
+#[cfg(feature = "gdb-jit-interface")]
+fn registered_gdb_jit_symbol_file(start_instruction_pointer: usize) -> Option<Vec<u8>>
+{
+	let _lock = GdbJitDescriptorLock.lock().unwrap();
+	
+	let mut code_entry = unsafe { (*addr_of!(__jit_debug_descriptor)).first_entry };
+	while !code_entry.is_null()
+	{
+		let symbol_file = unsafe { from_raw_parts((*code_entry).symfile_addr, (*code_entry).symfile_size as usize) };
+		let mut section_headers_offset = [0; 8];
+		section_headers_offset.copy_from_slice(&symbol_file[0x28 .. 0x30]);
+		let text_address_offset = (u64::from_le_bytes(section_headers_offset) as usize) + 64 + 16;
+		let mut text_address = [0; 8];
+		text_address.copy_from_slice(&symbol_file[text_address_offset .. text_address_offset + 8]);
+		if u64::from_le_bytes(text_address) == start_instruction_pointer as u64
+		{
+			return Some(symbol_file.to_vec())
+		}
+		code_entry = unsafe { (*code_entry).next_entry };
+	}
+	None
+}
+
+static CapturedBacktrace: Mutex<Option<String>> = Mutex::new(None);
+
+/// Called from generated code.
+#[inline(never)]
+extern "C" fn capture_backtrace()
+{
+	*CapturedBacktrace.lock().unwrap() = Some(Backtrace::force_capture().to_string())
+}
+
+extern "C"
+{
+	/// Provided by `libgcc_s`; the second argument is a `struct dwarf_eh_bases`.
+	fn _Unwind_Find_FDE(pc: usize, bases: *mut [usize; 3]) -> *const u8;
 }